@@ -47,6 +47,7 @@
 !objectives_num = $82FFFC ; bits 0-15
 !objectives_addrs = $8FEBC0
 !objectives_bitmasks #= !objectives_addrs+(2*!objectives_max)
+!update_objective_flags = $82F818 ; objective_tracking.asm
 
 ; range of item PLMs in bank 84
 !item_plm_start = #$DF89
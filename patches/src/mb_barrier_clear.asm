@@ -84,8 +84,7 @@ check_objective:
     bmi .bypass_check
     asl
     tax
-    lda.w #$007E
-    sta.b $02
+    jsl !update_objective_flags : nop  ; update derived objective flags, and set $02 = $7E
     lda.l !objectives_addrs, X
     sta.b $00
     lda.l !objectives_bitmasks, X
@@ -0,0 +1,128 @@
+; Tracking for objectives that are derived from the overall game state rather than from a single event
+; (item percentage, all Energy Tanks, all map stations, number of bosses defeated).
+;
+; These objectives are represented by randomizer-specific event flags in $7ED824, which are updated
+; whenever the objectives are checked (in mb_barrier_clear.asm and pause_menu_objectives.asm):
+;   $0001: collected the required percentage of items
+;   $0002: collected all Energy Tanks
+;   $0004: used all map stations
+;   $0008: defeated the required number of major bosses (Kraid, Phantoon, Draygon, Ridley)
+arch snes.cpu
+lorom
+
+!bank_82_free_space_start = $82F810
+!bank_82_free_space_end = $82FA00
+
+!derived_objective_flags = $7ED824
+
+org !bank_82_free_space_start
+
+; Objective targets, written by the randomizer: these must be at addresses matching what is in `patch.rs`.
+item_count_target:
+    dw $0000           ; number of item collection bits set (including for "Nothing" items, which start out set)
+max_energy_target:
+    dw $0000           ; max energy after collecting all Energy Tanks
+map_station_area_mask:
+    dw $0000           ; bitmask of map areas whose map station must be used
+boss_count_target:
+    dw $0000           ; number of major bosses that must be defeated
+
+; Called when checking an objective, in place of setting the bank byte of the objective flag pointer.
+; This must be at $82F818, matching the address in constants.asm.
+; X = objective index * 2
+update_objective_flags:
+    CPX #$0000         ;\
+    BNE .done          ;} Only update the flags when checking the first objective
+    PHX
+    PHY
+    JSR check_items
+    JSR check_energy_tanks
+    JSR check_map_stations
+    JSR check_bosses
+    PLY
+    PLX
+.done:
+    LDA #$007E         ;\
+    STA $02            ;} Run hi-jacked code
+    RTL
+
+; A = flag bit to set
+set_flag:
+    ORA.l !derived_objective_flags
+    STA.l !derived_objective_flags
+    RTS
+
+check_items:
+    LDY #$0000         ; Y = number of item collection bits set
+    LDX #$003E
+.word_loop:
+    LDA.l $7ED870,x
+.bit_loop:
+    BEQ .next_word
+    LSR
+    BCC .bit_loop
+    INY
+    BRA .bit_loop
+.next_word:
+    DEX
+    DEX
+    BPL .word_loop
+    TYA
+    CMP.l item_count_target
+    BCC .done
+    LDA #$0001
+    JSR set_flag
+.done:
+    RTS
+
+check_energy_tanks:
+    LDA.l $7E09C4      ; max energy
+    CMP.l max_energy_target
+    BCC .done
+    LDA #$0002
+    JSR set_flag
+.done:
+    RTS
+
+check_map_stations:
+    LDX #$0000         ; X = map area
+    LDA.l map_station_area_mask
+.loop:
+    LSR
+    BCC .next
+    PHA
+    LDA.l $7ED908,x    ;\
+    AND #$00FF         ;} If the map station in this area has not been used, then the objective is incomplete
+    BEQ .incomplete    ;/
+    PLA
+.next:
+    INX
+    CMP #$0000
+    BNE .loop
+    LDA #$0004
+    JSR set_flag
+    RTS
+.incomplete:
+    PLA
+    RTS
+
+check_bosses:
+    LDY #$0000         ; Y = number of major bosses defeated
+    LDX #$0003
+.loop:
+    LDA.l $7ED829,x    ; boss bits: Kraid ($7ED829), Ridley ($7ED82A), Phantoon ($7ED82B), Draygon ($7ED82C)
+    AND #$0001
+    BEQ .next
+    INY
+.next:
+    DEX
+    BPL .loop
+    TYA
+    CMP.l boss_count_target
+    BCC .done
+    LDA #$0008
+    JSR set_flag
+.done:
+    RTS
+
+warnpc !bank_82_free_space_end
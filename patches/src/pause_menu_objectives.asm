@@ -376,8 +376,7 @@ check_objective: ; X = index
     TXA
     ASL
     TAX
-    LDA.w #$007E
-    STA.b $02
+    JSL !update_objective_flags : NOP  ; update derived objective flags, and set $02 = $7E
     LDA.l !objectives_addrs, X
     STA.b $00
     LDA.l !objectives_bitmasks, X
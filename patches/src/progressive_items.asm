@@ -0,0 +1,124 @@
+; Progressive item PLMs: each one gives whichever item is next in its progression
+; (Charge -> Ice -> Wave -> Spazer -> Plasma for beams, Varia -> Gravity for suits),
+; regardless of the order in which the progressive items are collected.
+;
+; During PLM setup, the progressive PLM is replaced by the vanilla item PLM for the selected item,
+; so the item graphics, message box, and collection behavior are all the same as for vanilla items.
+lorom
+
+!bank_84_free_space_start = $84F630
+!bank_84_free_space_end = $84F700
+
+org !bank_84_free_space_start
+
+; These PLM entries must be at an address matching what is in `patch.rs`, starting here at $84F630.
+; The instruction lists are never used, since the setup replaces the PLM before it runs.
+dw setup, $0000    ; PLM $F630 (progressive beam)
+dw setup, $0000    ; PLM $F634 (progressive beam, chozo orb)
+dw setup, $0000    ; PLM $F638 (progressive beam, scenery shot block)
+dw setup, $0000    ; PLM $F63C (progressive suit)
+dw setup, $0000    ; PLM $F640 (progressive suit, chozo orb)
+dw setup, $0000    ; PLM $F644 (progressive suit, scenery shot block)
+
+; Y = PLM index
+setup:
+    PHB
+    PHK
+    PLB
+    LDA $1C37,y        ;\
+    SEC                ;} A = offset of PLM ID from the first progressive PLM
+    SBC #$F630         ;/
+    LDX #beam_list
+    CMP #$000C
+    BCC .container
+    SBC #$000C         ; (carry is already set)
+    LDX #suit_list
+.container:
+    LSR                ;\
+    PHX                ;|
+    TAX                ;|
+    LDA container_offset,x ;} $12 = offset from the item's plain PLM ID to its PLM ID in this container
+    PLX                ;|
+    STA $12            ;/
+    LDA $0000,x        ;\
+    STA $14            ;} $14 = address of collected items/beams
+    INX                ;|
+    INX                ;/
+
+    ; If the item was already collected, the PLM will delete itself, so it doesn't matter which
+    ; item it becomes. Use the first item in the list: it will always have been collected already
+    ; (by the first progressive pickup), so this doesn't interfere with other PLMs in the room.
+    PHX
+    LDA $1DC7,y
+    JSL $80818E        ; X = item byte index, [$05E7] = item bitmask
+    LDA $7ED870,x
+    PLX
+    AND $05E7
+    BNE .found
+
+.loop:
+    LDA ($14)          ;\
+    AND $0000,x        ;} If the item is already collected, skip it
+    BNE .next          ;/
+    LDA $0002,x        ;\
+    STA $16            ;} $16 = plain PLM ID for the item
+    PHX
+    LDX #$004E
+.check_plm:
+    ; Skip the item if another PLM in the room is already set to give it (in any container):
+    LDA $1C37,x
+    SEC
+    SBC $16
+    BEQ .taken
+    CMP #$0054
+    BEQ .taken
+    CMP #$00A8
+    BEQ .taken
+    DEX
+    DEX
+    BPL .check_plm
+    PLX
+    BRA .found
+.taken:
+    PLX
+.next:
+    LDA $0004,x        ;\
+    BEQ .found         ;} If there are no more items in the list, use the last one
+    INX
+    INX
+    INX
+    INX
+    BRA .loop
+
+.found:
+    LDA $0002,x        ;\
+    CLC                ;|
+    ADC $12            ;} PLM ID = vanilla item PLM for the selected item and container
+    STA $1C37,y        ;/
+    TAX
+    LDA $0002,x        ;\
+    STA $1D27,y        ;} Instruction list pointer = vanilla item PLM instruction list
+    PLB
+    JMP ($0000,x)      ; Run the vanilla item PLM setup
+
+container_offset:
+    dw $0000           ; No container
+    dw $0054           ; Chozo orb
+    dw $00A8           ; Scenery shot block
+
+beam_list:
+    dw $09A8           ; Collected beams
+    dw $1000, $EEEB    ; Charge
+    dw $0002, $EEEF    ; Ice
+    dw $0001, $EEFB    ; Wave
+    dw $0004, $EEFF    ; Spazer
+    dw $0008, $EF13    ; Plasma
+    dw $0000
+
+suit_list:
+    dw $09A4           ; Collected items
+    dw $0001, $EF07    ; Varia
+    dw $0020, $EF0B    ; Gravity
+    dw $0000
+
+warnpc !bank_84_free_space_end
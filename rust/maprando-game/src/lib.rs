@@ -4,6 +4,8 @@
 #![allow(clippy::too_many_arguments)]
 
 pub mod glowpatch;
+pub mod map_validation;
+pub mod requirement_render;
 pub mod smart_xml;
 
 use crate::glowpatch::GlowPatch;
@@ -17,7 +19,6 @@ use num_enum::TryFromPrimitive;
 use serde::{Deserialize, Serialize};
 use std::borrow::ToOwned;
 use std::fmt::{self, Debug, Formatter};
-use std::fs::File;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 use strum::VariantNames;
@@ -112,6 +113,8 @@ pub type StepTrailId = i32;
 pub type LinkIdx = i32;
 pub type TraversalId = usize; // Index into Traversal.past_steps
 
+pub const NUM_AREAS: usize = 6;
+
 #[derive(Default, Clone)]
 pub struct IndexedVec<T: Hash + Eq> {
     pub keys: Vec<T>,
@@ -159,6 +162,9 @@ pub enum Item {
     ReserveTank,  // 20
     WallJump,     // 21
     Nothing,      // 22
+    // Progressive pseudo-items, granting the next item in their sequence:
+    ProgressiveBeam, // 23
+    ProgressiveSuit, // 24
 }
 
 impl Item {
@@ -173,6 +179,39 @@ impl Item {
         ]
         .contains(&self)
     }
+
+    // For a progressive pseudo-item, the sequence of items that it grants:
+    pub fn progressive_sequence(self) -> Option<&'static [Item]> {
+        match self {
+            Item::ProgressiveBeam => Some(&[
+                Item::Charge,
+                Item::Ice,
+                Item::Wave,
+                Item::Spazer,
+                Item::Plasma,
+            ]),
+            Item::ProgressiveSuit => Some(&[Item::Varia, Item::Gravity]),
+            _ => None,
+        }
+    }
+
+    pub fn is_progressive(self) -> bool {
+        self.progressive_sequence().is_some()
+    }
+
+    // Determine the item actually granted when collecting this item: for a progressive pseudo-item,
+    // this is the first item in its sequence that is not yet collected (or the last item,
+    // if all of them are); other items are returned unchanged.
+    pub fn resolve_progressive(self, is_collected: impl Fn(Item) -> bool) -> Item {
+        match self.progressive_sequence() {
+            Some(seq) => seq
+                .iter()
+                .copied()
+                .find(|&x| !is_collected(x))
+                .unwrap_or(seq[seq.len() - 1]),
+            None => self,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -344,6 +383,13 @@ pub enum Requirement {
         can_be_very_patient_tech_id: usize,
         r_mode: bool,
     },
+    KraidFight,
+    CrocomireFight,
+    SporeSpawnFight,
+    GoldenTorizoFight {
+        can_be_very_patient_tech_idx: usize,
+    },
+    MotherBrain1Fight,
     DoorType {
         room_id: RoomId,
         node_id: NodeId,
@@ -415,6 +461,29 @@ impl Requirement {
         }
     }
 
+    // Checks which never modify the local state and don't depend on it. These can be reordered,
+    // deduplicated, or dropped without changing the result of applying the requirement.
+    pub fn is_pure(&self) -> bool {
+        matches!(
+            self,
+            Requirement::Free
+                | Requirement::Never
+                | Requirement::Tech(_)
+                | Requirement::Notable(_)
+                | Requirement::Item(_)
+                | Requirement::Flag(_)
+                | Requirement::NotFlag(_)
+                | Requirement::MotherBrainBarrierClear(_)
+                | Requirement::DisableableETank
+                | Requirement::Walljump
+                | Requirement::MissilesCapacity(_)
+                | Requirement::SupersCapacity(_)
+                | Requirement::PowerBombsCapacity(_)
+                | Requirement::RegularEnergyCapacity(_)
+                | Requirement::ReserveEnergyCapacity(_)
+        )
+    }
+
     pub fn make_shinecharge(tiles: f32, heated: bool) -> Requirement {
         if tiles < 11.0 {
             // An effective runway length of 11 is the minimum possible length of shortcharge supported in the logic.
@@ -447,37 +516,9 @@ impl Requirement {
 
     pub fn print_pretty(&self, indent: usize, game_data: &GameData) {
         let spaces = " ".repeat(indent);
-        print!("{spaces}");
-        match self {
-            &Requirement::Tech(tech_idx) => {
-                let tech_id = game_data.tech_isv.keys[tech_idx];
-                print!("Tech({})", game_data.tech_names[&tech_id]);
-            }
-            &Requirement::Item(item_idx) => {
-                print!("Item({})", game_data.item_isv.keys[item_idx]);
-            }
-            &Requirement::Flag(flag_idx) => {
-                print!("Flag({})", game_data.flag_isv.keys[flag_idx]);
-            }
-            Requirement::And(reqs) => {
-                println!("And(");
-                for r in reqs {
-                    r.print_pretty(indent + 2, game_data);
-                    println!(",");
-                }
-                print!("{spaces})")
-            }
-            Requirement::Or(reqs) => {
-                println!("Or(");
-                for r in reqs {
-                    r.print_pretty(indent + 2, game_data);
-                    println!(",");
-                }
-                print!("{spaces})")
-            }
-            other => {
-                print!("{other:?}");
-            }
+        let text = self.render(game_data, requirement_render::RenderFormat::Text);
+        for line in text.lines() {
+            println!("{spaces}{line}");
         }
     }
 }
@@ -605,6 +646,21 @@ pub struct StartLocation {
     pub requires_parsed: Option<Requirement>,
 }
 
+impl StartLocation {
+    // The vanilla start location, at the ship in Landing Site.
+    pub fn ship() -> Self {
+        StartLocation {
+            name: "Ship".to_string(),
+            room_id: 8,
+            node_id: 5,
+            door_load_node_id: Some(2),
+            x: 72.0,
+            y: 69.5,
+            ..StartLocation::default()
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct HubLocation {
     pub room_id: usize,
@@ -1423,6 +1479,31 @@ struct MapTileDataFile {
     rooms: Vec<MapTileData>,
 }
 
+// Parse the map tile data (map_tiles.json), applying the room-level heat and liquid properties to
+// the individual tiles.
+pub fn parse_map_tile_data(map_tile_data_str: &str) -> Result<Vec<MapTileData>> {
+    let map_tile_data_file: MapTileDataFile = serde_json::from_str(map_tile_data_str)?;
+    let mut map_tile_data = map_tile_data_file.rooms;
+    for room in &mut map_tile_data {
+        for tile in &mut room.map_tiles {
+            tile.heated = room.heated;
+            if let Some(liquid_level) = room.liquid_level {
+                if (tile.coords.1 as f32) <= liquid_level - 1.0 {
+                    tile.liquid_level = None;
+                } else if (tile.coords.1 as f32) >= liquid_level {
+                    tile.liquid_level = Some(0.0);
+                } else {
+                    tile.liquid_level = Some(liquid_level.fract());
+                }
+            }
+            if tile.liquid_level.is_some() {
+                tile.liquid_type = room.liquid_type;
+            }
+        }
+    }
+    Ok(map_tile_data)
+}
+
 type GfxTile1Bpp = [u8; 8];
 
 #[derive(Default)]
@@ -1448,6 +1529,7 @@ pub struct ExitInfo {
 #[derive(Default)]
 pub struct GameData {
     sm_json_data_path: PathBuf,
+    data_files: Option<HashMap<String, String>>, // In-memory data files, used in place of the filesystem during loading
     pub tech_isv: IndexedVec<TechId>,
     pub notable_isv: IndexedVec<(RoomId, NotableId)>,
     pub notable_info: Vec<NotableInfo>,
@@ -1491,6 +1573,8 @@ pub struct GameData {
     pub flag_ids: Vec<FlagId>,
     pub flag_vertex_ids: Vec<Vec<VertexId>>,
     pub save_locations: Vec<(RoomId, NodeId)>,
+    pub map_station_locations: Vec<(RoomId, NodeId)>,
+    pub map_station_flag_ids: Vec<FlagId>,
     pub links: Vec<Link>,
     pub base_links_data: LinksDataGroup,
     pub room_geometry: Vec<RoomGeometry>,
@@ -1541,13 +1625,45 @@ impl<T: Hash + Eq> IndexedVec<T> {
     }
 }
 
-fn read_json(path: &Path) -> Result<JsonValue> {
-    let file = File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
-    let json_str = std::io::read_to_string(file)
-        .with_context(|| format!("unable to read {}", path.display()))?;
-    let json_data =
-        json::parse(&json_str).with_context(|| format!("unable to parse {}", path.display()))?;
-    Ok(json_data)
+// Data files needed by `GameData::load_from_files`, as glob patterns relative to the working
+// directory used by `GameData::load`. Graphics (title screens and the room name font) are excluded.
+pub const LOGIC_DATA_FILES: [&str; 14] = [
+    "../sm-json-data/tech.json",
+    "../sm-json-data/items.json",
+    "../sm-json-data/helpers.json",
+    "../sm-json-data/weapons/main.json",
+    "../sm-json-data/enemies/main.json",
+    "../sm-json-data/enemies/bosses/main.json",
+    "../sm-json-data/region/**/*.json",
+    "../sm-json-data/connection/**/*.json",
+    "../room_geometry.json",
+    "data/escape_timings.json",
+    "data/start_locations.json",
+    "data/reduced_flashing.json",
+    "data/strat_videos.json",
+    "data/map_tiles.json",
+];
+
+// Read the files listed in `LOGIC_DATA_FILES` into a bundle keyed by path, which can be passed
+// to `GameData::load_from_files` somewhere without access to the filesystem (e.g. in the browser).
+pub fn read_logic_data_files() -> Result<HashMap<String, String>> {
+    let mut files: HashMap<String, String> = HashMap::new();
+    for pattern in LOGIC_DATA_FILES {
+        for entry in glob::glob(pattern)? {
+            let path = entry?;
+            let path_str = path
+                .to_str()
+                .with_context(|| format!("Unable to convert path to string: {}", path.display()))?;
+            // Ceres rooms are skipped when loading, so leave them out of the bundle:
+            if path_str.contains("ceres") {
+                continue;
+            }
+            let data = std::fs::read_to_string(&path)
+                .with_context(|| format!("unable to read {}", path.display()))?;
+            files.insert(path_str.to_string(), data);
+        }
+    }
+    Ok(files)
 }
 
 // TODO: Take steep slopes into account here:
@@ -1569,8 +1685,53 @@ struct RequirementContext<'a> {
 }
 
 impl GameData {
+    fn read_data_file(&self, path: &Path) -> Result<String> {
+        if let Some(files) = &self.data_files {
+            let data = path
+                .to_str()
+                .and_then(|p| files.get(p))
+                .with_context(|| format!("missing data file {}", path.display()))?;
+            Ok(data.clone())
+        } else {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("unable to read {}", path.display()))
+        }
+    }
+
+    fn read_json(&self, path: &Path) -> Result<JsonValue> {
+        let json_str = self.read_data_file(path)?;
+        let json_data = json::parse(&json_str)
+            .with_context(|| format!("unable to parse {}", path.display()))?;
+        Ok(json_data)
+    }
+
+    // Paths of the JSON files (recursively) in the given subdirectory of sm-json-data, in sorted order.
+    fn list_json_files(&self, subdir: &str) -> Result<Vec<PathBuf>> {
+        let dir = self.sm_json_data_path.join(subdir);
+        let dir_str = dir.to_str().unwrap().to_string();
+        if let Some(files) = &self.data_files {
+            let prefix = dir_str + "/";
+            let mut paths: Vec<PathBuf> = files
+                .keys()
+                .filter(|p| p.starts_with(&prefix) && p.ends_with(".json"))
+                .map(PathBuf::from)
+                .collect();
+            paths.sort();
+            Ok(paths)
+        } else {
+            let mut paths = vec![];
+            for entry in glob::glob(&(dir_str + "/**/*.json"))? {
+                match entry {
+                    Ok(path) => paths.push(path),
+                    Err(e) => bail!("Error processing {subdir} path: {e}"),
+                }
+            }
+            Ok(paths)
+        }
+    }
+
     fn load_tech(&mut self) -> Result<()> {
-        let mut full_tech_json = read_json(&self.sm_json_data_path.join("tech.json"))?;
+        let mut full_tech_json = self.read_json(&self.sm_json_data_path.join("tech.json"))?;
         ensure!(full_tech_json["techCategories"].is_array());
         full_tech_json["techCategories"].members_mut().find(|x| x["name"] == "Shots").unwrap()["techs"].push(json::object!{
             "id": 10001,
@@ -1759,7 +1920,7 @@ impl GameData {
     }
 
     fn load_items_and_flags(&mut self) -> Result<()> {
-        let item_json = read_json(&self.sm_json_data_path.join("items.json"))?;
+        let item_json = self.read_json(&self.sm_json_data_path.join("items.json"))?;
 
         for item_name in Item::VARIANTS {
             self.item_isv.add(&item_name.to_string());
@@ -1779,11 +1940,17 @@ impl GameData {
         self.flag_isv.add("f_ClearedPlasmaRoom");
         self.flag_isv.add("f_ClearedMetalPiratesRoom");
 
+        // Flags for objectives derived from the overall game state (set by the randomizer):
+        self.flag_isv.add("f_CollectedItemPercentage");
+        self.flag_isv.add("f_CollectedAllEnergyTanks");
+        self.flag_isv.add("f_UsedAllMapStations");
+        self.flag_isv.add("f_DefeatedBossCount");
+
         Ok(())
     }
 
     fn load_weapons(&mut self) -> Result<()> {
-        let weapons_json = read_json(&self.sm_json_data_path.join("weapons/main.json"))?;
+        let weapons_json = self.read_json(&self.sm_json_data_path.join("weapons/main.json"))?;
         ensure!(weapons_json["weapons"].is_array());
         for weapon_json in weapons_json["weapons"].members() {
             let name = weapon_json["name"].as_str().unwrap();
@@ -1830,7 +1997,8 @@ impl GameData {
             (8, 0x23, 0x5F, 0x3C, 0x05, 0x28, 0x14), // Kago
         ];
         for file in ["main.json", "bosses/main.json"] {
-            let enemies_json = read_json(&self.sm_json_data_path.join("enemies").join(file))?;
+            let enemies_json =
+                self.read_json(&self.sm_json_data_path.join("enemies").join(file))?;
             ensure!(enemies_json["enemies"].is_array());
             for enemy_json in enemies_json["enemies"].members() {
                 let enemy_name = enemy_json["name"].as_str().unwrap();
@@ -1942,7 +2110,7 @@ impl GameData {
     }
 
     fn load_helpers(&mut self) -> Result<()> {
-        let helpers_json = read_json(&self.sm_json_data_path.join("helpers.json"))?;
+        let helpers_json = self.read_json(&self.sm_json_data_path.join("helpers.json"))?;
         ensure!(helpers_json["helperCategories"].is_array());
         for category_json in helpers_json["helperCategories"].members() {
             ensure!(category_json["helpers"].is_array());
@@ -2673,6 +2841,31 @@ impl GameData {
                     });
                 }
 
+                // The boss fight models replace the generic enemy kill requirement, except in strats
+                // which restrict the weapons used (e.g. a Kraid kill using Power Bombs only).
+                let boss_req =
+                    if value.has_key("explicitWeapons") || value.has_key("excludedWeapons") {
+                        None
+                    } else if enemy_set.contains("Kraid") {
+                        Some(Requirement::KraidFight)
+                    } else if enemy_set.contains("Crocomire") {
+                        Some(Requirement::CrocomireFight)
+                    } else if enemy_set.contains("Spore Spawn") {
+                        Some(Requirement::SporeSpawnFight)
+                    } else if enemy_set.contains("Golden Torizo") {
+                        Some(Requirement::GoldenTorizoFight {
+                            can_be_very_patient_tech_idx: self.tech_isv.index_by_key
+                                [&TECH_ID_CAN_BE_VERY_PATIENT],
+                        })
+                    } else if enemy_set.contains("Mother Brain 1") {
+                        Some(Requirement::MotherBrain1Fight)
+                    } else {
+                        None
+                    };
+                if let Some(boss_req) = boss_req {
+                    return Ok(boss_req);
+                }
+
                 let mut allowed_weapons: WeaponMask = if value.has_key("explicitWeapons") {
                     ensure!(value["explicitWeapons"].is_array());
                     let mut weapon_mask = 0;
@@ -2864,23 +3057,17 @@ impl GameData {
     }
 
     fn load_regions(&mut self) -> Result<()> {
-        let region_pattern =
-            self.sm_json_data_path.to_str().unwrap().to_string() + "/region/**/*.json";
         let mut room_json_map: HashMap<usize, JsonValue> = HashMap::new();
-        for entry in glob::glob(&region_pattern).unwrap() {
-            if let Ok(path) = entry {
-                let path_str = path.to_str().with_context(|| {
-                    format!("Unable to convert path to string: {}", path.display())
-                })?;
-                if path_str.contains("ceres") || path_str.contains("roomDiagrams") {
-                    continue;
-                }
-
-                let room_json = read_json(&path)?;
-                room_json_map.insert(room_json["id"].as_usize().unwrap(), room_json);
-            } else {
-                bail!("Error processing region path: {}", entry.err().unwrap());
+        for path in self.list_json_files("region")? {
+            let path_str = path
+                .to_str()
+                .with_context(|| format!("Unable to convert path to string: {}", path.display()))?;
+            if path_str.contains("ceres") || path_str.contains("roomDiagrams") {
+                continue;
             }
+
+            let room_json = self.read_json(&path)?;
+            room_json_map.insert(room_json["id"].as_usize().unwrap(), room_json);
         }
 
         let mut room_id_vec: Vec<usize> = room_json_map.keys().cloned().collect();
@@ -3043,11 +3230,11 @@ impl GameData {
     }
 
     fn override_tourian_save_room(&mut self, room_json: &mut JsonValue) {
-        // Remove the "save" utility, as we have a map here instead.
+        // Replace the "save" utility, as we have a map here instead.
         let mut found = false;
         for node_json in room_json["nodes"].members_mut() {
             if node_json["id"].as_i32().unwrap() == 2 {
-                node_json.remove("utility");
+                node_json["utility"] = json::array!["map"];
                 found = true;
             }
         }
@@ -3299,6 +3486,7 @@ impl GameData {
             _ => {}
         }
 
+        let new_room_name = new_room_json["name"].as_str().unwrap().to_string();
         let logical_gray_door_node_ids: Vec<(RoomId, NodeId)> = get_logical_gray_door_node_ids();
         let flagged_gray_door_node_ids: Vec<(RoomId, NodeId)> = get_flagged_gray_door_node_ids();
         let mut extra_obstacles: Vec<String> = vec![];
@@ -3356,6 +3544,24 @@ impl GameData {
                 });
             }
 
+            if node_json.has_key("utility") && node_json["utility"].members().any(|x| x == "map") {
+                // Add a flag for using the map station, used for the "AllMapStations" objective:
+                let area_name = new_room_name
+                    .strip_suffix(" Map Room")
+                    .context("Unexpected map station room name")?
+                    .replace(' ', "");
+                let flag_name = format!("f_Used{area_name}MapStation");
+                let flag_id = self.flag_isv.add(&flag_name);
+                self.map_station_locations.push((room_id, node_id));
+                self.map_station_flag_ids.push(flag_id);
+                extra_strats.push(json::object! {
+                    "link": [node_id, node_id],
+                    "name": "Base (Use Map Station)",
+                    "requires": [],
+                    "setsFlags": [flag_name],
+                });
+            }
+
             if node_type == "item" && !node_json.has_key("locks") {
                 node_json["locks"] = json::array![
                     {
@@ -4708,15 +4914,10 @@ impl GameData {
     }
 
     fn load_connections(&mut self) -> Result<()> {
-        let connection_pattern =
-            self.sm_json_data_path.to_str().unwrap().to_string() + "/connection/**/*.json";
-        for entry in glob::glob(&connection_pattern)? {
-            if let Ok(path) = entry {
-                if !path.to_str().unwrap().contains("ceres") {
-                    self.process_connections(&read_json(&path)?)?;
-                }
-            } else {
-                bail!("Error processing connection path: {}", entry.err().unwrap());
+        for path in self.list_json_files("connection")? {
+            if !path.to_str().unwrap().contains("ceres") {
+                let connection_json = self.read_json(&path)?;
+                self.process_connections(&connection_json)?;
             }
         }
         Ok(())
@@ -4787,6 +4988,8 @@ impl GameData {
         .into_iter()
         .map(|x| self.flag_isv.index_by_key[x])
         .collect();
+        self.flag_ids
+            .extend(self.map_station_flag_ids.iter().copied());
 
         let mut node_pair_vec: Vec<(RoomId, NodeId)> = self.node_json_map.keys().cloned().collect();
         node_pair_vec.sort();
@@ -4904,8 +5107,37 @@ impl GameData {
         weapon_mask
     }
 
+    // For each weapon (indexed as in a `WeaponMask`), the items needed to use it, or None if it
+    // requires tech which is not enabled.
+    pub fn get_weapon_item_requirements(&self, tech: &[bool]) -> Vec<Option<Vec<ItemId>>> {
+        let mut out = vec![];
+        'weapon: for weapon_name in &self.weapon_isv.keys {
+            let weapon = &self.weapon_json_map[weapon_name];
+            let mut items = vec![];
+            for req_json in weapon["useRequires"].members() {
+                let req_name = req_json.as_str().unwrap();
+                if req_name == "PowerBeam" {
+                    continue;
+                }
+                if let Some(&item_idx) = self.item_isv.index_by_key.get(req_name) {
+                    items.push(item_idx);
+                } else if let Some(tech_id) = self.tech_id_by_name.get(req_name) {
+                    if !tech[self.tech_isv.index_by_key[tech_id]] {
+                        out.push(None);
+                        continue 'weapon;
+                    }
+                } else {
+                    panic!("Unrecognized weapon requirement: {req_name}");
+                }
+            }
+            out.push(Some(items));
+        }
+        out
+    }
+
     fn load_escape_timings(&mut self, path: &Path) -> Result<()> {
-        let escape_timings_str = std::fs::read_to_string(path)
+        let escape_timings_str = self
+            .read_data_file(path)
             .with_context(|| format!("Unable to load escape timings at {}", path.display()))?;
         self.escape_timings = serde_json::from_str(&escape_timings_str)?;
         assert_eq!(self.escape_timings.len(), self.room_geometry.len());
@@ -4913,7 +5145,8 @@ impl GameData {
     }
 
     fn load_start_locations(&mut self, path: &Path) -> Result<()> {
-        let start_locations_str = std::fs::read_to_string(path)
+        let start_locations_str = self
+            .read_data_file(path)
             .with_context(|| format!("Unable to load start locations at {}", path.display()))?;
         let mut start_locations: Vec<StartLocation> = serde_json::from_str(&start_locations_str)?;
         let mut start_location_id_map: HashMap<(usize, usize), usize> = HashMap::new();
@@ -4999,7 +5232,8 @@ impl GameData {
     }
 
     fn load_room_geometry(&mut self, path: &Path) -> Result<()> {
-        let room_geometry_str = std::fs::read_to_string(path)
+        let room_geometry_str = self
+            .read_data_file(path)
             .with_context(|| format!("Unable to load room geometry at {}", path.display()))?;
         let room_geometry: Vec<RoomGeometry> = serde_json::from_str(&room_geometry_str)?;
         for (room_idx, room) in room_geometry.iter().enumerate() {
@@ -5261,7 +5495,7 @@ impl GameData {
     }
 
     fn load_reduced_flashing_patch(&mut self, path: &Path) -> Result<()> {
-        let reduced_flashing_str = std::fs::read_to_string(path).with_context(|| {
+        let reduced_flashing_str = self.read_data_file(path).with_context(|| {
             format!(
                 "Unable to load reduced flashing patch at {}",
                 path.display()
@@ -5272,7 +5506,8 @@ impl GameData {
     }
 
     fn load_strat_videos(&mut self, path: &Path) -> Result<()> {
-        let strat_videos_str = std::fs::read_to_string(path)
+        let strat_videos_str = self
+            .read_data_file(path)
             .with_context(|| format!("Unable to load strat videos at {}", path.display()))?;
         let strat_videos: Vec<StratVideo> = serde_json::from_str(&strat_videos_str)?;
         for video in strat_videos {
@@ -5285,43 +5520,61 @@ impl GameData {
     }
 
     fn load_map_tile_data(&mut self, path: &Path) -> Result<()> {
-        let map_tile_data_str = std::fs::read_to_string(path)
+        let map_tile_data_str = self
+            .read_data_file(path)
             .with_context(|| format!("Unable to load map tile data at {}", path.display()))?;
-        let map_tile_data_file: MapTileDataFile = serde_json::from_str(&map_tile_data_str)?;
-        self.map_tile_data = map_tile_data_file.rooms;
-        for room in &mut self.map_tile_data {
-            for tile in &mut room.map_tiles {
-                tile.heated = room.heated;
-                if let Some(liquid_level) = room.liquid_level {
-                    if (tile.coords.1 as f32) <= liquid_level - 1.0 {
-                        tile.liquid_level = None;
-                    } else if (tile.coords.1 as f32) >= liquid_level {
-                        tile.liquid_level = Some(0.0);
-                    } else {
-                        tile.liquid_level = Some(liquid_level.fract());
-                    }
-                }
-                if tile.liquid_level.is_some() {
-                    tile.liquid_type = room.liquid_type;
-                }
-            }
-        }
+        self.map_tile_data = parse_map_tile_data(&map_tile_data_str)?;
         Ok(())
     }
 
     pub fn load() -> Result<GameData> {
-        let sm_json_data_path = Path::new("../sm-json-data");
-        let room_geometry_path = Path::new("../room_geometry.json");
-        let escape_timings_path = Path::new("data/escape_timings.json");
-        let start_locations_path = Path::new("data/start_locations.json");
-        let title_screen_path = Path::new("../TitleScreen/Images");
-        let room_name_font_path = Path::new("data/room_name_font.png");
-        let reduced_flashing_path = Path::new("data/reduced_flashing.json");
-        let strat_videos_path = Path::new("data/strat_videos.json");
-        let map_tile_path = Path::new("data/map_tiles.json");
+        Self::load_from_dir(Path::new(""))
+    }
+
+    // Load the game data with the data paths relative to the given workspace directory (`rust/`)
+    // rather than the current directory, e.g. for tests, which can't rely on where they are run.
+    pub fn load_from_dir(base_path: &Path) -> Result<GameData> {
+        let title_screen_path = base_path.join("../TitleScreen/Images");
+        let room_name_font_path = base_path.join("data/room_name_font.png");
+
+        let mut game_data = Self::load_logic(base_path, &base_path.join("../sm-json-data"), None)?;
+        game_data.load_title_screens(&title_screen_path)?;
+        game_data.load_room_name_font(&room_name_font_path)?;
+        Ok(game_data)
+    }
+
+    // Load the game data from a bundle of data files produced by `read_logic_data_files`, without
+    // accessing the filesystem. Graphics are not loaded, so the result is only suitable for logic.
+    pub fn load_from_files(data_files: HashMap<String, String>) -> Result<GameData> {
+        Self::load_logic(
+            Path::new(""),
+            Path::new("../sm-json-data"),
+            Some(data_files),
+        )
+    }
+
+    // Load the game data using a different checkout of sm-json-data, e.g. to compare the logic
+    // between two revisions. Graphics are not loaded.
+    pub fn load_with_sm_json_data(sm_json_data_path: &Path) -> Result<GameData> {
+        Self::load_logic(Path::new(""), sm_json_data_path, None)
+    }
+
+    fn load_logic(
+        base_path: &Path,
+        sm_json_data_path: &Path,
+        data_files: Option<HashMap<String, String>>,
+    ) -> Result<GameData> {
+        // The file paths here should be kept consistent with `LOGIC_DATA_FILES`.
+        let room_geometry_path = &base_path.join("../room_geometry.json");
+        let escape_timings_path = &base_path.join("data/escape_timings.json");
+        let start_locations_path = &base_path.join("data/start_locations.json");
+        let reduced_flashing_path = &base_path.join("data/reduced_flashing.json");
+        let strat_videos_path = &base_path.join("data/strat_videos.json");
+        let map_tile_path = &base_path.join("data/map_tiles.json");
 
         let mut game_data = GameData {
             sm_json_data_path: sm_json_data_path.to_owned(),
+            data_files,
             ..GameData::default()
         };
 
@@ -5615,8 +5868,7 @@ impl GameData {
             0x1AC000, // Maridia
             0x1AD000, // Tourian
         ];
-        game_data.load_title_screens(title_screen_path)?;
-        game_data.load_room_name_font(room_name_font_path)?;
+        game_data.data_files = None;
 
         // for link in &game_data.links {
         //     let from_vertex_id = link.from_vertex_id;
@@ -0,0 +1,554 @@
+use crate::{
+    AreaIdx, DoorPtrPair, GameData, Map, NUM_AREAS, RoomGeometryDoor, RoomGeometryRoomIdx, RoomId,
+};
+use hashbrown::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+// Size of the grid that rooms are placed on:
+pub const MAP_GRID_SIZE: usize = 72;
+// Size of an area's pause map, in tiles:
+pub const AREA_MAP_WIDTH: usize = 64;
+pub const AREA_MAP_HEIGHT: usize = 32;
+pub const LANDING_SITE_ROOM_ID: RoomId = 8;
+pub const MOTHER_BRAIN_ROOM_ID: RoomId = 238;
+// Rooms which every map must include: Landing Site (where the game starts, and where it ends after
+// the escape) and Mother Brain Room:
+const REQUIRED_ROOM_IDS: [RoomId; 2] = [LANDING_SITE_ROOM_ID, MOTHER_BRAIN_ROOM_ID];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapValidationError {
+    // A per-room field which doesn't have one entry per room in the game:
+    WrongLength {
+        field: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    MissingRequiredRoom {
+        room: String,
+    },
+    RoomOutOfBounds {
+        room: String,
+        x: usize,
+        y: usize,
+    },
+    RoomOverlap {
+        room1: String,
+        room2: String,
+        x: usize,
+        y: usize,
+    },
+    // Area, subarea, or subsubarea number out of range:
+    InvalidArea {
+        room: String,
+        field: &'static str,
+        value: usize,
+    },
+    AreaTooLarge {
+        area: AreaIdx,
+        width: usize,
+        height: usize,
+    },
+    UnknownDoor {
+        door: DoorPtrPair,
+    },
+    DoorInMissingRoom {
+        room: String,
+        door: DoorPtrPair,
+    },
+    DuplicateDoor {
+        room: String,
+        door: DoorPtrPair,
+    },
+    // Doors with directions that don't face each other, different subtypes, or sand transitions
+    // which only work with their vanilla partner:
+    IncompatibleDoors {
+        src_room: String,
+        src_direction: String,
+        dst_room: String,
+        dst_direction: String,
+    },
+    // Sand connections must go one-way from the down door; all others must be bidirectional.
+    WrongDirectionality {
+        src_room: String,
+        dst_room: String,
+        bidirectional: bool,
+    },
+    NonAdjacentDoors {
+        src_room: String,
+        dst_room: String,
+    },
+    MisalignedElevator {
+        src_room: String,
+        dst_room: String,
+        src_x: usize,
+        dst_x: usize,
+    },
+    // Sand, elevator, and Toilet doors can't be replaced with walls:
+    UnconnectedDoor {
+        room: String,
+        door: DoorPtrPair,
+    },
+    ToiletAreaMismatch {
+        room1: String,
+        room2: String,
+    },
+}
+
+impl Display for MapValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MapValidationError::WrongLength {
+                field,
+                len,
+                expected,
+            } => write!(f, "{field} has {len} entries, expected {expected}"),
+            MapValidationError::MissingRequiredRoom { room } => {
+                write!(f, "{room} is required but is not in the map")
+            }
+            MapValidationError::RoomOutOfBounds { room, x, y } => {
+                write!(f, "{room} at ({x}, {y}) extends outside the map grid")
+            }
+            MapValidationError::RoomOverlap { room1, room2, x, y } => {
+                write!(f, "{room1} overlaps {room2} at ({x}, {y})")
+            }
+            MapValidationError::InvalidArea { room, field, value } => {
+                write!(f, "{room} has invalid {field} {value}")
+            }
+            MapValidationError::AreaTooLarge {
+                area,
+                width,
+                height,
+            } => write!(
+                f,
+                "area {area} spans {width} x {height} tiles, which doesn't fit the pause map"
+            ),
+            MapValidationError::UnknownDoor { door } => {
+                write!(f, "unknown door {door:?}")
+            }
+            MapValidationError::DoorInMissingRoom { room, door } => {
+                write!(f, "door {door:?} is in {room}, which is not in the map")
+            }
+            MapValidationError::DuplicateDoor { room, door } => {
+                write!(f, "door {door:?} in {room} is connected more than once")
+            }
+            MapValidationError::IncompatibleDoors {
+                src_room,
+                src_direction,
+                dst_room,
+                dst_direction,
+            } => write!(
+                f,
+                "incompatible doors: {src_direction} door in {src_room} connected to {dst_direction} door in {dst_room}"
+            ),
+            MapValidationError::WrongDirectionality {
+                src_room,
+                dst_room,
+                bidirectional,
+            } => write!(
+                f,
+                "connection from {src_room} to {dst_room} should {}be bidirectional",
+                if *bidirectional { "not " } else { "" }
+            ),
+            MapValidationError::NonAdjacentDoors { src_room, dst_room } => write!(
+                f,
+                "connected doors in {src_room} and {dst_room} are not adjacent"
+            ),
+            MapValidationError::MisalignedElevator {
+                src_room,
+                dst_room,
+                src_x,
+                dst_x,
+            } => write!(
+                f,
+                "elevator from {src_room} (x = {src_x}) to {dst_room} (x = {dst_x}) is not vertically aligned"
+            ),
+            MapValidationError::UnconnectedDoor { room, door } => {
+                write!(f, "door {door:?} in {room} must be connected")
+            }
+            MapValidationError::ToiletAreaMismatch { room1, room2 } => write!(
+                f,
+                "Toilet intersects {room1} and {room2}, which are in different areas/subareas"
+            ),
+        }
+    }
+}
+
+// Position of the boundary between two tiles where the door connects: doors which are connected
+// to each other must have the same position.
+fn door_position(door: &RoomGeometryDoor, (room_x, room_y): (usize, usize)) -> (usize, usize) {
+    let x = room_x + door.x;
+    let y = room_y + door.y;
+    match door.direction.as_str() {
+        "right" => (x + 1, y),
+        "down" => (x, y + 1),
+        _ => (x, y),
+    }
+}
+
+fn is_opposite(a: &str, b: &str) -> bool {
+    matches!(
+        (a, b),
+        ("left", "right") | ("right", "left") | ("up", "down") | ("down", "up")
+    )
+}
+
+impl Map {
+    // Check that the map is well-formed, returning the errors found (empty if the map is valid).
+    // Hand-made or third-party maps otherwise tend to fail with panics deep inside randomization
+    // or patching.
+    pub fn validate(&self, game_data: &GameData) -> Vec<MapValidationError> {
+        let mut errors: Vec<MapValidationError> = vec![];
+        let num_rooms = game_data.room_geometry.len();
+        for (field, len) in [
+            ("room_mask", self.room_mask.len()),
+            ("rooms", self.rooms.len()),
+            ("area", self.area.len()),
+            ("subarea", self.subarea.len()),
+            ("subsubarea", self.subsubarea.len()),
+        ] {
+            if len != num_rooms {
+                errors.push(MapValidationError::WrongLength {
+                    field,
+                    len,
+                    expected: num_rooms,
+                });
+            }
+        }
+        if !errors.is_empty() {
+            // The remaining checks would index out of bounds.
+            return errors;
+        }
+        let room_name =
+            |room_idx: RoomGeometryRoomIdx| game_data.room_geometry[room_idx].name.clone();
+
+        for room_id in REQUIRED_ROOM_IDS {
+            let room_idx = game_data.room_idx_by_id[&room_id];
+            if !self.room_mask[room_idx] {
+                errors.push(MapValidationError::MissingRequiredRoom {
+                    room: room_name(room_idx),
+                });
+            }
+        }
+
+        // Room positions and areas:
+        let mut occupied: HashMap<(usize, usize), RoomGeometryRoomIdx> = HashMap::new();
+        let mut overlapping_pairs: HashSet<(RoomGeometryRoomIdx, RoomGeometryRoomIdx)> =
+            HashSet::new();
+        let mut area_min = [(usize::MAX, usize::MAX); NUM_AREAS];
+        let mut area_max = [(0, 0); NUM_AREAS];
+        for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+            if !self.room_mask[room_idx] {
+                continue;
+            }
+            for (field, value, limit) in [
+                ("area", self.area[room_idx], NUM_AREAS),
+                ("subarea", self.subarea[room_idx], 2),
+                ("subsubarea", self.subsubarea[room_idx], 2),
+            ] {
+                if value >= limit {
+                    errors.push(MapValidationError::InvalidArea {
+                        room: room_name(room_idx),
+                        field,
+                        value,
+                    });
+                }
+            }
+            let (x, y) = self.rooms[room_idx];
+            if x + room.map[0].len() > MAP_GRID_SIZE || y + room.map.len() > MAP_GRID_SIZE {
+                errors.push(MapValidationError::RoomOutOfBounds {
+                    room: room_name(room_idx),
+                    x,
+                    y,
+                });
+                continue;
+            }
+            let area = self.area[room_idx];
+            for (ty, row) in room.map.iter().enumerate() {
+                for (tx, &c) in row.iter().enumerate() {
+                    if c != 1 {
+                        continue;
+                    }
+                    let (x1, y1) = (x + tx, y + ty);
+                    // Rooms in different areas may overlap, since areas are drawn separately
+                    // (as in the vanilla map, with the Tourian elevator):
+                    if let Some(other_idx) = occupied.insert((x1, y1), room_idx)
+                        && self.area[other_idx] == area
+                        && overlapping_pairs.insert((other_idx, room_idx))
+                    {
+                        errors.push(MapValidationError::RoomOverlap {
+                            room1: room_name(other_idx),
+                            room2: room_name(room_idx),
+                            x: x1,
+                            y: y1,
+                        });
+                    }
+                    if area < NUM_AREAS {
+                        area_min[area] = (area_min[area].0.min(x1), area_min[area].1.min(y1));
+                        area_max[area] = (area_max[area].0.max(x1), area_max[area].1.max(y1));
+                    }
+                }
+            }
+        }
+        for area in 0..NUM_AREAS {
+            if area_min[area].0 == usize::MAX {
+                continue;
+            }
+            let width = area_max[area].0 - area_min[area].0 + 1;
+            let height = area_max[area].1 - area_min[area].1 + 1;
+            if width > AREA_MAP_WIDTH || height > AREA_MAP_HEIGHT {
+                errors.push(MapValidationError::AreaTooLarge {
+                    area,
+                    width,
+                    height,
+                });
+            }
+        }
+
+        // Door connections:
+        let mut connected: HashSet<DoorPtrPair> = HashSet::new();
+        for &(src, dst, bidirectional) in &self.doors {
+            let mut idxs = vec![];
+            for door in [src, dst] {
+                let Some(&(room_idx, door_idx)) =
+                    game_data.room_and_door_idxs_by_door_ptr_pair.get(&door)
+                else {
+                    errors.push(MapValidationError::UnknownDoor { door });
+                    continue;
+                };
+                if !self.room_mask[room_idx] {
+                    errors.push(MapValidationError::DoorInMissingRoom {
+                        room: room_name(room_idx),
+                        door,
+                    });
+                    continue;
+                }
+                if !connected.insert(door) {
+                    errors.push(MapValidationError::DuplicateDoor {
+                        room: room_name(room_idx),
+                        door,
+                    });
+                }
+                idxs.push((room_idx, door_idx));
+            }
+            let &[(src_room_idx, src_door_idx), (dst_room_idx, dst_door_idx)] = idxs.as_slice()
+            else {
+                continue;
+            };
+            let src_door = &game_data.room_geometry[src_room_idx].doors[src_door_idx];
+            let dst_door = &game_data.room_geometry[dst_room_idx].doors[dst_door_idx];
+            let ptrs = [src.0, src.1, dst.0, dst.1];
+            let vanilla_only = ptrs.iter().any(|p| p.is_none());
+            if !is_opposite(&src_door.direction, &dst_door.direction)
+                || src_door.subtype != dst_door.subtype
+                || (vanilla_only && (src.0 != dst.1 || src.1 != dst.0))
+            {
+                errors.push(MapValidationError::IncompatibleDoors {
+                    src_room: room_name(src_room_idx),
+                    src_direction: src_door.direction.clone(),
+                    dst_room: room_name(dst_room_idx),
+                    dst_direction: dst_door.direction.clone(),
+                });
+                continue;
+            }
+            let is_sand = src_door.subtype == "sand";
+            if bidirectional == is_sand || (is_sand && src_door.direction != "down") {
+                errors.push(MapValidationError::WrongDirectionality {
+                    src_room: room_name(src_room_idx),
+                    dst_room: room_name(dst_room_idx),
+                    bidirectional,
+                });
+            }
+            let src_pos = door_position(src_door, self.rooms[src_room_idx]);
+            let dst_pos = door_position(dst_door, self.rooms[dst_room_idx]);
+            if src_door.subtype == "elevator" {
+                // Elevators may span between areas, which are drawn separately, so only the
+                // horizontal position has to match.
+                if src_pos.0 != dst_pos.0 {
+                    errors.push(MapValidationError::MisalignedElevator {
+                        src_room: room_name(src_room_idx),
+                        dst_room: room_name(dst_room_idx),
+                        src_x: src_pos.0,
+                        dst_x: dst_pos.0,
+                    });
+                }
+            } else if src_pos != dst_pos {
+                errors.push(MapValidationError::NonAdjacentDoors {
+                    src_room: room_name(src_room_idx),
+                    dst_room: room_name(dst_room_idx),
+                });
+            }
+        }
+        for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+            if !self.room_mask[room_idx] {
+                continue;
+            }
+            for door in &room.doors {
+                let ptr_pair = (door.exit_ptr, door.entrance_ptr);
+                let must_connect =
+                    door.subtype != "normal" || room_idx == game_data.toilet_room_idx;
+                if must_connect && !connected.contains(&ptr_pair) {
+                    errors.push(MapValidationError::UnconnectedDoor {
+                        room: room_name(room_idx),
+                        door: ptr_pair,
+                    });
+                }
+            }
+        }
+
+        // Rooms intersecting the Toilet must agree on area/subarea/subsubarea, since the Toilet
+        // takes these from them:
+        let toilet_idx = game_data.toilet_room_idx;
+        if self.room_mask[toilet_idx] {
+            let (x, y) = self.rooms[toilet_idx];
+            let mut intersections: Vec<RoomGeometryRoomIdx> = vec![];
+            for i in 2..8 {
+                if let Some(&room_idx) = occupied.get(&(x, y + i))
+                    && !intersections.contains(&room_idx)
+                {
+                    intersections.push(room_idx);
+                }
+            }
+            let get_areas =
+                |r: RoomGeometryRoomIdx| (self.area[r], self.subarea[r], self.subsubarea[r]);
+            for &room_idx in intersections.iter().skip(1) {
+                if get_areas(room_idx) != get_areas(intersections[0]) {
+                    errors.push(MapValidationError::ToiletAreaMismatch {
+                        room1: room_name(intersections[0]),
+                        room2: room_name(room_idx),
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{path::Path, sync::OnceLock};
+
+    fn game_data() -> &'static GameData {
+        static GAME_DATA: OnceLock<GameData> = OnceLock::new();
+        GAME_DATA.get_or_init(|| {
+            GameData::load_from_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("..")).unwrap()
+        })
+    }
+
+    fn vanilla_map(game_data: &GameData) -> Map {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../maps/vanilla/vanilla_map.json");
+        let mut map: Map = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        // The vanilla map JSON predates the room mask and subsubareas:
+        let num_rooms = game_data.room_geometry.len();
+        map.room_mask = vec![true; num_rooms];
+        map.subsubarea = vec![0; num_rooms];
+        map
+    }
+
+    // Subtype of the door, or None for doors of the Toilet (which must always be connected):
+    fn door_subtype(game_data: &GameData, door: DoorPtrPair) -> Option<&str> {
+        let (room_idx, door_idx) = game_data.room_and_door_idxs_by_door_ptr_pair[&door];
+        if room_idx == game_data.toilet_room_idx {
+            return None;
+        }
+        Some(
+            game_data.room_geometry[room_idx].doors[door_idx]
+                .subtype
+                .as_str(),
+        )
+    }
+
+    #[test]
+    fn vanilla_map_is_valid() {
+        let game_data = game_data();
+        let errors = vanilla_map(game_data).validate(game_data);
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn overlapping_rooms_are_reported() {
+        let game_data = game_data();
+        let mut map = vanilla_map(game_data);
+        // Move a single-tile room onto a tile of the Landing Site:
+        let landing_site_idx = game_data
+            .room_geometry
+            .iter()
+            .position(|r| r.name == "Landing Site")
+            .unwrap();
+        let landing_site = &game_data.room_geometry[landing_site_idx];
+        let (tx, ty) = landing_site
+            .map
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|&c| c == 1).map(|x| (x, y)))
+            .unwrap();
+        let room_idx = game_data
+            .room_geometry
+            .iter()
+            .position(|r| r.map == vec![vec![1]])
+            .unwrap();
+        let (x, y) = map.rooms[landing_site_idx];
+        map.rooms[room_idx] = (x + tx, y + ty);
+        map.area[room_idx] = map.area[landing_site_idx];
+
+        let errors = map.validate(game_data);
+        assert!(
+            errors
+                .iter()
+                .any(|e| matches!(e, MapValidationError::RoomOverlap { .. }))
+        );
+    }
+
+    #[test]
+    fn unconnected_doors_are_reported() {
+        let game_data = game_data();
+        let mut map = vanilla_map(game_data);
+
+        // Normal doors may be left unconnected (becoming walls):
+        let normal_idx = map
+            .doors
+            .iter()
+            .position(|&(src, dst, _)| {
+                door_subtype(game_data, src) == Some("normal")
+                    && door_subtype(game_data, dst) == Some("normal")
+            })
+            .unwrap();
+        map.doors.remove(normal_idx);
+        assert_eq!(map.validate(game_data), vec![]);
+
+        // Elevators can't:
+        map.doors
+            .retain(|&(src, _, _)| door_subtype(game_data, src) != Some("elevator"));
+        let errors = map.validate(game_data);
+        assert!(
+            errors
+                .iter()
+                .any(|e| matches!(e, MapValidationError::UnconnectedDoor { .. }))
+        );
+    }
+
+    #[test]
+    fn missing_required_rooms_are_reported() {
+        let game_data = game_data();
+        for (room_id, room_name) in [(8, "Landing Site"), (238, "Mother Brain Room")] {
+            let mut map = vanilla_map(game_data);
+            let room_idx = game_data.room_idx_by_id[&room_id];
+            map.room_mask[room_idx] = false;
+            // Leave out the doors of the room too, so that only the room itself is reported:
+            map.doors.retain(|&(src, dst, _)| {
+                [src, dst]
+                    .iter()
+                    .all(|door| game_data.room_and_door_idxs_by_door_ptr_pair[door].0 != room_idx)
+            });
+            let errors = map.validate(game_data);
+            assert!(
+                errors.contains(&MapValidationError::MissingRequiredRoom {
+                    room: room_name.to_string()
+                }),
+                "{errors:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,466 @@
+// Human-readable rendering of processed requirements, with tech, notables, items, and flags
+// given by name. Leaves mostly use the same vocabulary as sm-json-data (e.g. "heatFrames: 120").
+
+use crate::{GameData, Requirement, RidleyStuck};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+enum RenderNode {
+    Leaf(String),
+    All(Vec<RenderNode>),
+    Any(Vec<RenderNode>),
+    Labeled(String, Vec<RenderNode>),
+}
+
+fn push_unique(out: &mut Vec<Requirement>, req: Requirement) {
+    if !out.contains(&req) {
+        out.push(req);
+    }
+}
+
+// Within an And, repeated resource terms (e.g. Missiles, HeatFrames) add up, so only pure checks
+// can be deduplicated.
+fn push_and_term(out: &mut Vec<Requirement>, req: Requirement) {
+    if req.is_pure() {
+        push_unique(out, req);
+    } else {
+        out.push(req);
+    }
+}
+
+impl Requirement {
+    // Flatten nested And/Or, remove Free terms of an And and Never alternatives of an Or (and
+    // the reverse, collapsing the whole And/Or), and remove duplicate alternatives of an Or and
+    // duplicate pure checks of an And.
+    pub fn simplify(&self) -> Requirement {
+        match self {
+            Requirement::And(reqs) => {
+                let mut out: Vec<Requirement> = vec![];
+                for r in reqs {
+                    match r.simplify() {
+                        Requirement::Free => {}
+                        Requirement::Never => return Requirement::Never,
+                        Requirement::And(sub_reqs) => {
+                            for s in sub_reqs {
+                                push_and_term(&mut out, s);
+                            }
+                        }
+                        s => push_and_term(&mut out, s),
+                    }
+                }
+                match out.len() {
+                    0 => Requirement::Free,
+                    1 => out.pop().unwrap(),
+                    _ => Requirement::And(out),
+                }
+            }
+            Requirement::Or(reqs) => {
+                let mut out: Vec<Requirement> = vec![];
+                for r in reqs {
+                    match r.simplify() {
+                        Requirement::Never => {}
+                        Requirement::Free => return Requirement::Free,
+                        Requirement::Or(sub_reqs) => {
+                            for s in sub_reqs {
+                                push_unique(&mut out, s);
+                            }
+                        }
+                        s => push_unique(&mut out, s),
+                    }
+                }
+                match out.len() {
+                    0 => Requirement::Never,
+                    1 => out.pop().unwrap(),
+                    _ => Requirement::Or(out),
+                }
+            }
+            Requirement::Farm {
+                requirement,
+                enemy_drops,
+                enemy_drops_buffed,
+                full_energy,
+                full_missiles,
+                full_supers,
+                full_power_bombs,
+            } => Requirement::Farm {
+                requirement: Box::new(requirement.simplify()),
+                enemy_drops: enemy_drops.clone(),
+                enemy_drops_buffed: enemy_drops_buffed.clone(),
+                full_energy: *full_energy,
+                full_missiles: *full_missiles,
+                full_supers: *full_supers,
+                full_power_bombs: *full_power_bombs,
+            },
+            Requirement::UnlockDoor {
+                room_id,
+                node_id,
+                requirement_red,
+                requirement_green,
+                requirement_yellow,
+                requirement_charge,
+            } => Requirement::UnlockDoor {
+                room_id: *room_id,
+                node_id: *node_id,
+                requirement_red: Box::new(requirement_red.simplify()),
+                requirement_green: Box::new(requirement_green.simplify()),
+                requirement_yellow: Box::new(requirement_yellow.simplify()),
+                requirement_charge: Box::new(requirement_charge.simplify()),
+            },
+            other => other.clone(),
+        }
+    }
+
+    // Render the (simplified) requirement as indented text, a nested Markdown list, or HTML.
+    pub fn render(&self, game_data: &GameData, format: RenderFormat) -> String {
+        let node = make_node(&self.simplify(), game_data);
+        let mut out = String::new();
+        match format {
+            RenderFormat::Text => render_text(&node, 0, &mut out),
+            RenderFormat::Markdown => render_markdown(&node, 0, &mut out),
+            RenderFormat::Html => {
+                out.push_str("<div class=\"requirement\">");
+                render_html(&node, &mut out);
+                out.push_str("</div>");
+            }
+        }
+        out
+    }
+}
+
+fn make_node(req: &Requirement, game_data: &GameData) -> RenderNode {
+    match req {
+        Requirement::And(reqs) => {
+            RenderNode::All(reqs.iter().map(|r| make_node(r, game_data)).collect())
+        }
+        Requirement::Or(reqs) => {
+            RenderNode::Any(reqs.iter().map(|r| make_node(r, game_data)).collect())
+        }
+        Requirement::Farm { requirement, .. } => {
+            RenderNode::Labeled("farm".to_string(), vec![make_node(requirement, game_data)])
+        }
+        Requirement::UnlockDoor {
+            requirement_red,
+            requirement_green,
+            requirement_yellow,
+            requirement_charge,
+            ..
+        } => {
+            let mut children = vec![];
+            for (name, r) in [
+                ("red", requirement_red),
+                ("green", requirement_green),
+                ("yellow", requirement_yellow),
+                ("charge", requirement_charge),
+            ] {
+                if **r != Requirement::Free {
+                    children.push(RenderNode::Labeled(
+                        name.to_string(),
+                        vec![make_node(r, game_data)],
+                    ));
+                }
+            }
+            if children.is_empty() {
+                RenderNode::Leaf("unlockDoor".to_string())
+            } else {
+                RenderNode::Labeled("unlockDoor".to_string(), children)
+            }
+        }
+        _ => RenderNode::Leaf(leaf_text(req, game_data)),
+    }
+}
+
+fn leaf_text(req: &Requirement, game_data: &GameData) -> String {
+    let heated = |h: bool| if h { ", heated" } else { "" };
+    match req {
+        Requirement::Free => "free".to_string(),
+        Requirement::Never => "never".to_string(),
+        Requirement::Tech(tech_idx) => {
+            let tech_id = game_data.tech_isv.keys[*tech_idx];
+            game_data
+                .tech_names
+                .get(&tech_id)
+                .cloned()
+                .unwrap_or_else(|| format!("tech {tech_id}"))
+        }
+        Requirement::Notable(notable_idx) => {
+            format!("notable: {}", game_data.notable_info[*notable_idx].name)
+        }
+        Requirement::Item(item_idx) => game_data.item_isv.keys[*item_idx].clone(),
+        Requirement::Flag(flag_idx) => game_data.flag_isv.keys[*flag_idx].clone(),
+        Requirement::NotFlag(flag_idx) => format!("not {}", game_data.flag_isv.keys[*flag_idx]),
+        Requirement::MotherBrainBarrierClear(obj_id) => {
+            format!("Mother Brain barrier {} cleared", obj_id + 1)
+        }
+        Requirement::DisableableETank => "disableableETank".to_string(),
+        Requirement::Walljump => "canWalljump".to_string(),
+        Requirement::ShineCharge {
+            used_tiles,
+            heated: h,
+        } => {
+            format!("shineCharge: {} tiles{}", used_tiles.get(), heated(*h))
+        }
+        Requirement::SpeedBall {
+            used_tiles,
+            heated: h,
+        } => {
+            format!("speedBall: {} tiles{}", used_tiles.get(), heated(*h))
+        }
+        Requirement::GetBlueSpeed {
+            used_tiles,
+            heated: h,
+        } => {
+            format!("getBlueSpeed: {} tiles{}", used_tiles.get(), heated(*h))
+        }
+        Requirement::ShineChargeFrames(n) => format!("shineChargeFrames: {n}"),
+        Requirement::Shinespark {
+            frames,
+            excess_frames,
+            ..
+        } => format!("shinespark: {frames} frames ({excess_frames} excess)"),
+        Requirement::HeatFrames(n) | Requirement::SimpleHeatFrames(n) => {
+            format!("heatFrames: {n}")
+        }
+        Requirement::HeatFramesWithEnergyDrops(n, ..) => {
+            format!("heatFramesWithEnergyDrops: {n}")
+        }
+        Requirement::LavaFrames(n) => format!("lavaFrames: {n}"),
+        Requirement::LavaFramesWithEnergyDrops(n, ..) => {
+            format!("lavaFramesWithEnergyDrops: {n}")
+        }
+        Requirement::GravitylessLavaFrames(n) => format!("gravitylessLavaFrames: {n}"),
+        Requirement::AcidFrames(n) => format!("acidFrames: {n}"),
+        Requirement::GravitylessAcidFrames(n) => format!("gravitylessAcidFrames: {n}"),
+        Requirement::MetroidFrames(n) => format!("metroidFrames: {n}"),
+        Requirement::CycleFrames(n) | Requirement::SimpleCycleFrames(n) => {
+            format!("cycleFrames: {n}")
+        }
+        Requirement::Damage(n) => format!("damage: {n}"),
+        Requirement::Energy(n) => format!("energy: {n}"),
+        Requirement::RegularEnergy(n) => format!("regularEnergy: {n}"),
+        Requirement::ReserveEnergy(n) => format!("reserveEnergy: {n}"),
+        Requirement::Missiles(n) => format!("missiles: {n}"),
+        Requirement::Supers(n) => format!("supers: {n}"),
+        Requirement::PowerBombs(n) => format!("powerBombs: {n}"),
+        Requirement::EnergyAvailable(n) => format!("energyAvailable: {n}"),
+        Requirement::RegularEnergyAvailable(n) => format!("regularEnergyAvailable: {n}"),
+        Requirement::ReserveEnergyAvailable(n) => format!("reserveEnergyAvailable: {n}"),
+        Requirement::MissilesAvailable(n) => format!("missilesAvailable: {n}"),
+        Requirement::SupersAvailable(n) => format!("supersAvailable: {n}"),
+        Requirement::PowerBombsAvailable(n) => format!("powerBombsAvailable: {n}"),
+        Requirement::RegularEnergyCapacity(n) => format!("regularEnergyCapacity: {n}"),
+        Requirement::ReserveEnergyCapacity(n) => format!("reserveEnergyCapacity: {n}"),
+        Requirement::MissilesCapacity(n) => format!("missilesCapacity: {n}"),
+        Requirement::SupersCapacity(n) => format!("supersCapacity: {n}"),
+        Requirement::PowerBombsCapacity(n) => format!("powerBombsCapacity: {n}"),
+        Requirement::EnergyMissingAtMost(n) => format!("energyMissingAtMost: {n}"),
+        Requirement::RegularEnergyMissingAtMost(n) => format!("regularEnergyMissingAtMost: {n}"),
+        Requirement::ReserveEnergyMissingAtMost(n) => format!("reserveEnergyMissingAtMost: {n}"),
+        Requirement::MissilesMissingAtMost(n) => format!("missilesMissingAtMost: {n}"),
+        Requirement::SupersMissingAtMost(n) => format!("supersMissingAtMost: {n}"),
+        Requirement::PowerBombsMissingAtMost(n) => format!("powerBombsMissingAtMost: {n}"),
+        Requirement::EnergyRefill(n) => format!("refill: energy (up to {n})"),
+        Requirement::RegularEnergyRefill(n) => format!("refill: regular energy (up to {n})"),
+        Requirement::ReserveRefill(n) => format!("refill: reserves (up to {n})"),
+        Requirement::MissileRefill(n) => format!("refill: missiles (up to {n})"),
+        Requirement::SuperRefill(n) => format!("refill: supers (up to {n})"),
+        Requirement::PowerBombRefill(n) => format!("refill: power bombs (up to {n})"),
+        Requirement::RegularEnergyDrain(n) => format!("regularEnergyDrain: {n}"),
+        Requirement::ReserveEnergyDrain(n) => format!("reserveEnergyDrain: {n}"),
+        Requirement::MissileDrain(n) => format!("missileDrain: {n}"),
+        Requirement::AmmoStationRefill => "ammo station refill".to_string(),
+        Requirement::AmmoStationRefillAll => "ammo station refill (all)".to_string(),
+        Requirement::EnergyStationRefill => "energy station refill".to_string(),
+        Requirement::ReserveTrigger {
+            min_reserve_energy,
+            max_reserve_energy,
+            heated: h,
+        } => format!(
+            "autoReserveTrigger: {min_reserve_energy} to {max_reserve_energy} reserve energy{}",
+            heated(*h)
+        ),
+        Requirement::EnemyKill { count, vul } => {
+            format!("enemyKill: {count} enemies with {} HP", vul.hp)
+        }
+        Requirement::PhantoonFight {} => "Phantoon fight".to_string(),
+        Requirement::DraygonFight { .. } => "Draygon fight".to_string(),
+        Requirement::RidleyFight {
+            power_bombs,
+            g_mode,
+            stuck,
+            ..
+        } => {
+            let mut details = vec![];
+            if !*power_bombs {
+                details.push("no Power Bombs".to_string());
+            }
+            if *g_mode {
+                details.push("G-mode".to_string());
+            }
+            if *stuck != RidleyStuck::None {
+                details.push(format!("stuck {stuck:?}").to_lowercase());
+            }
+            if details.is_empty() {
+                "Ridley fight".to_string()
+            } else {
+                format!("Ridley fight ({})", details.join(", "))
+            }
+        }
+        Requirement::BotwoonFight { second_phase } => {
+            if *second_phase {
+                "Botwoon fight (second phase)".to_string()
+            } else {
+                "Botwoon fight".to_string()
+            }
+        }
+        Requirement::MotherBrain2Fight { r_mode, .. } => {
+            if *r_mode {
+                "Mother Brain 2 fight (R-mode)".to_string()
+            } else {
+                "Mother Brain 2 fight".to_string()
+            }
+        }
+        Requirement::KraidFight => "Kraid fight".to_string(),
+        Requirement::CrocomireFight => "Crocomire fight".to_string(),
+        Requirement::SporeSpawnFight => "Spore Spawn fight".to_string(),
+        Requirement::GoldenTorizoFight { .. } => "Golden Torizo fight".to_string(),
+        Requirement::MotherBrain1Fight => "Mother Brain 1 fight".to_string(),
+        Requirement::DoorType {
+            node_id, door_type, ..
+        } => format!("door type at node {node_id}: {door_type:?}"),
+        Requirement::DoorUnlocked { node_id, .. } => format!("door unlocked at node {node_id}"),
+        Requirement::ResetRoom { node_id, .. } => format!("resetRoom: node {node_id}"),
+        other => format!("{other:?}"),
+    }
+}
+
+fn render_text(node: &RenderNode, indent: usize, out: &mut String) {
+    let spaces = " ".repeat(indent);
+    let (label, children) = match node {
+        RenderNode::Leaf(s) => {
+            out.push_str(&format!("{spaces}{s}\n"));
+            return;
+        }
+        RenderNode::All(children) => ("all of", children),
+        RenderNode::Any(children) => ("any of", children),
+        RenderNode::Labeled(label, children) => (label.as_str(), children),
+    };
+    out.push_str(&format!("{spaces}{label}:\n"));
+    for c in children {
+        render_text(c, indent + 2, out);
+    }
+}
+
+fn render_markdown(node: &RenderNode, indent: usize, out: &mut String) {
+    let spaces = " ".repeat(indent);
+    let (label, children) = match node {
+        RenderNode::Leaf(s) => {
+            out.push_str(&format!("{spaces}- {s}\n"));
+            return;
+        }
+        RenderNode::All(children) => ("all of", children),
+        RenderNode::Any(children) => ("any of", children),
+        RenderNode::Labeled(label, children) => (label.as_str(), children),
+    };
+    out.push_str(&format!("{spaces}- **{label}:**\n"));
+    for c in children {
+        render_markdown(c, indent + 2, out);
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(node: &RenderNode, out: &mut String) {
+    let (class, label, children) = match node {
+        RenderNode::Leaf(s) => {
+            out.push_str(&escape_html(s));
+            return;
+        }
+        RenderNode::All(children) => ("req-and", "all of", children),
+        RenderNode::Any(children) => ("req-or", "any of", children),
+        RenderNode::Labeled(label, children) => ("req-labeled", label.as_str(), children),
+    };
+    out.push_str(&format!(
+        "<span class=\"req-op\">{}:</span><ul class=\"{class}\">",
+        escape_html(label)
+    ));
+    for c in children {
+        out.push_str("<li>");
+        render_html(c, out);
+        out.push_str("</li>");
+    }
+    out.push_str("</ul>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify() {
+        let req = Requirement::And(vec![
+            Requirement::Free,
+            Requirement::Item(1),
+            Requirement::And(vec![Requirement::Item(2), Requirement::Item(1)]),
+            Requirement::Or(vec![
+                Requirement::Never,
+                Requirement::Or(vec![Requirement::Flag(3)]),
+            ]),
+        ]);
+        assert_eq!(
+            req.simplify(),
+            Requirement::And(vec![
+                Requirement::Item(1),
+                Requirement::Item(2),
+                Requirement::Flag(3),
+            ])
+        );
+        let req = Requirement::And(vec![
+            Requirement::Missiles(2),
+            Requirement::HeatFrames(60),
+            Requirement::Item(1),
+            Requirement::And(vec![
+                Requirement::Missiles(2),
+                Requirement::HeatFrames(60),
+                Requirement::Item(1),
+            ]),
+        ]);
+        assert_eq!(
+            req.simplify(),
+            Requirement::And(vec![
+                Requirement::Missiles(2),
+                Requirement::HeatFrames(60),
+                Requirement::Item(1),
+                Requirement::Missiles(2),
+                Requirement::HeatFrames(60),
+            ])
+        );
+        let req = Requirement::Or(vec![Requirement::Item(1), Requirement::Free]);
+        assert_eq!(req.simplify(), Requirement::Free);
+        let req = Requirement::And(vec![Requirement::Item(1), Requirement::Never]);
+        assert_eq!(req.simplify(), Requirement::Never);
+    }
+
+    #[test]
+    fn test_render_html_escapes() {
+        let node = RenderNode::Any(vec![
+            RenderNode::Leaf("a < b".to_string()),
+            RenderNode::Leaf("c".to_string()),
+        ]);
+        let mut out = String::new();
+        render_html(&node, &mut out);
+        assert_eq!(
+            out,
+            "<span class=\"req-op\">any of:</span><ul class=\"req-or\"><li>a &lt; b</li><li>c</li></ul>"
+        );
+    }
+}
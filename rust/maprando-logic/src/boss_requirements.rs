@@ -588,3 +588,368 @@ pub fn apply_mother_brain_2_requirement(
 
     validate_energy(local, inventory, can_manage_reserves)
 }
+
+// Use up to `available` shots of a weapon dealing `damage` per hit, stopping once the boss is dead.
+// Returns the number of shots used.
+fn use_shots(
+    available: Capacity,
+    damage: f32,
+    shot_time: f32,
+    boss_hp: &mut f32,
+    time: &mut f32,
+) -> Capacity {
+    if *boss_hp <= 0.0 || damage <= 0.0 {
+        return 0;
+    }
+    let shots = max(0, min(available, f32::ceil(*boss_hp / damage) as Capacity));
+    *boss_hp -= shots as f32 * damage;
+    *time += shots as f32 * shot_time;
+    shots
+}
+
+// Use as many beam shots (charged or uncharged) as needed to kill the boss.
+fn use_beam_shots(damage: f32, shot_time: f32, boss_hp: &mut f32, time: &mut f32) {
+    use_shots(Capacity::MAX, damage, shot_time, boss_hp, time);
+}
+
+pub fn apply_kraid_requirement(
+    inventory: &Inventory,
+    mut local: LocalState,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> Option<LocalState> {
+    let mut boss_hp: f32 = 1000.0;
+    let mut time: f32 = 0.0; // Cumulative time in seconds for the fight
+    let charge_damage = get_charge_damage(inventory);
+
+    // Assume an accuracy of between 50% (on lowest difficulty) to 100% (on highest).
+    // Shots are only effective while Kraid's mouth is open.
+    let accuracy = 0.5 + 0.5 * proficiency;
+
+    // Assume a firing rate of between 40% (on lowest difficulty) to 100% (on highest):
+    let firing_rate = 0.4 + 0.6 * proficiency;
+
+    // Kraid is slow to damage Samus, so we prefer conserving ammo: use Charge if available,
+    // otherwise Missiles followed by Supers.
+    if charge_damage > 0.0 {
+        // Assume max average rate of one charge shot per 2.5 seconds:
+        use_beam_shots(
+            charge_damage * accuracy,
+            2.5 / firing_rate,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+    local.missiles_used += use_shots(
+        inventory.max_missiles - local.missiles_used,
+        100.0 * accuracy,
+        0.5 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+    local.supers_used += use_shots(
+        inventory.max_supers - local.supers_used,
+        300.0 * accuracy,
+        1.0 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Finish with uncharged shots if needed, assuming a max average rate of 3 shots per second:
+    use_beam_shots(
+        get_uncharged_damage(inventory) * accuracy,
+        (1.0 / 3.0) / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Assumed rate of damage to Samus per second, from Kraid's claws and belly spikes:
+    let base_hit_dps = 8.0 * (1.0 - proficiency);
+    let damage = base_hit_dps * time / suit_damage_factor(inventory) as f32;
+    // Overflow safeguard - bail here if Samus takes calamitous damage.
+    if damage > 10000.0 {
+        return None;
+    }
+    local.energy_used += damage as Capacity;
+
+    validate_energy(local, inventory, can_manage_reserves)
+}
+
+pub fn apply_crocomire_requirement(
+    inventory: &Inventory,
+    mut local: LocalState,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> Option<LocalState> {
+    // Crocomire doesn't die from damage, but is pushed back into the spikes by hits to his
+    // open mouth. We model this as the amount of damage needed to push him all the way back,
+    // which also accounts for him regaining ground between hits.
+    let mut boss_hp: f32 = 4000.0;
+    let mut time: f32 = 0.0; // Cumulative time in seconds for the fight
+    let charge_damage = get_charge_damage(inventory);
+
+    // Assume an accuracy of between 50% (on lowest difficulty) to 100% (on highest):
+    let accuracy = 0.5 + 0.5 * proficiency;
+
+    // Assume a firing rate of between 40% (on lowest difficulty) to 100% (on highest):
+    let firing_rate = 0.4 + 0.6 * proficiency;
+
+    // Prioritize conserving ammo by using Charge if available, then Missiles, then Supers:
+    if charge_damage > 0.0 {
+        // Assume max average rate of one charge shot per 1.5 seconds:
+        use_beam_shots(
+            charge_damage * accuracy,
+            1.5 / firing_rate,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+    local.missiles_used += use_shots(
+        inventory.max_missiles - local.missiles_used,
+        100.0 * accuracy,
+        0.5 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+    local.supers_used += use_shots(
+        inventory.max_supers - local.supers_used,
+        300.0 * accuracy,
+        1.0 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Finish with uncharged shots if needed, assuming a max average rate of 3 shots per second:
+    use_beam_shots(
+        get_uncharged_damage(inventory) * accuracy,
+        (1.0 / 3.0) / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Assumed rate of damage to Samus per second, from Crocomire's projectiles and from
+    // being pushed back into the spike wall:
+    let base_hit_dps = 10.0 * (1.0 - 0.9 * proficiency);
+    let damage = base_hit_dps * time / suit_damage_factor(inventory) as f32;
+    // Overflow safeguard - bail here if Samus takes calamitous damage.
+    if damage > 10000.0 {
+        return None;
+    }
+    local.energy_used += damage as Capacity;
+
+    validate_energy(local, inventory, can_manage_reserves)
+}
+
+pub fn apply_spore_spawn_requirement(
+    inventory: &Inventory,
+    mut local: LocalState,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> Option<LocalState> {
+    let mut boss_hp: f32 = 960.0;
+    let mut time: f32 = 0.0; // Cumulative time in seconds for the fight
+    let charge_damage = get_charge_damage(inventory);
+
+    // Assume an accuracy of between 60% (on lowest difficulty) to 100% (on highest):
+    let accuracy = 0.6 + 0.4 * proficiency;
+
+    // Assume a firing rate of between 50% (on lowest difficulty) to 100% (on highest).
+    // Spore Spawn can only be damaged while its core is exposed, which limits the rate
+    // at which shots can land.
+    let firing_rate = 0.5 + 0.5 * proficiency;
+
+    // Prioritize conserving ammo by using Charge if available, then Missiles, then Supers:
+    if charge_damage > 0.0 {
+        // Assume max average rate of one charge shot per 4 seconds:
+        use_beam_shots(
+            charge_damage * accuracy,
+            4.0 / firing_rate,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+    local.missiles_used += use_shots(
+        inventory.max_missiles - local.missiles_used,
+        100.0 * accuracy,
+        2.0 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+    local.supers_used += use_shots(
+        inventory.max_supers - local.supers_used,
+        300.0 * accuracy,
+        4.0 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Finish with uncharged shots if needed, assuming a max average rate of 3 shots per second:
+    use_beam_shots(
+        get_uncharged_damage(inventory) * accuracy,
+        (1.0 / 3.0) / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Assumed rate of damage to Samus per second, from contact with Spore Spawn and its spores:
+    let base_hit_dps = 6.0 * (1.0 - 0.8 * proficiency);
+
+    // Assumed average energy per second gained from farming spores:
+    let farm_rate = 2.0 * (0.25 + 0.75 * proficiency);
+
+    let mut net_dps = base_hit_dps / suit_damage_factor(inventory) as f32 - farm_rate;
+    if net_dps < 0.0 {
+        net_dps = 0.0;
+    }
+    // Overflow safeguard - bail here if Samus takes calamitous damage.
+    if net_dps * time > 10000.0 {
+        return None;
+    }
+    local.energy_used += (net_dps * time) as Capacity;
+
+    validate_energy(local, inventory, can_manage_reserves)
+}
+
+pub fn apply_golden_torizo_requirement(
+    inventory: &Inventory,
+    mut local: LocalState,
+    proficiency: f32,
+    can_manage_reserves: bool,
+    can_be_very_patient: bool,
+) -> Option<LocalState> {
+    // Golden Torizo catches Supers, so only Missiles and beams are considered.
+    let mut boss_hp: f32 = 8000.0;
+    let mut time: f32 = 0.0; // Cumulative time in seconds for the fight
+    let charge_damage = get_charge_damage(inventory);
+
+    // Assume an accuracy of between 60% (on lowest difficulty) to 100% (on highest):
+    let accuracy = 0.6 + 0.4 * proficiency;
+
+    // Assume a firing rate of between 30% (on lowest difficulty) to 100% (on highest):
+    let firing_rate = 0.3 + 0.7 * proficiency;
+
+    let charge_time = 1.5 / firing_rate; // minimum of 1.5 seconds between charge shots
+    let missile_time = 0.5 / firing_rate; // minimum of 0.5 seconds between Missile shots
+
+    // Use Charge first if it is higher DPS than Missiles (which happens with Charge + Plasma):
+    if charge_damage / charge_time >= 100.0 / missile_time {
+        use_beam_shots(
+            charge_damage * accuracy,
+            charge_time,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+    local.missiles_used += use_shots(
+        inventory.max_missiles - local.missiles_used,
+        100.0 * accuracy,
+        missile_time,
+        &mut boss_hp,
+        &mut time,
+    );
+    if charge_damage > 0.0 {
+        use_beam_shots(
+            charge_damage * accuracy,
+            charge_time,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+
+    // Finish with uncharged shots if needed, assuming a max average rate of 3 shots per second:
+    use_beam_shots(
+        get_uncharged_damage(inventory) * accuracy,
+        (1.0 / 3.0) / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    if time * firing_rate >= 180.0 && !can_be_very_patient {
+        // We don't have enough patience to finish the fight:
+        return None;
+    }
+
+    // Assumed rate of Golden Torizo damage to Samus (per second), given minimal dodging skill:
+    let base_attack_dps = 25.0;
+    // We assume that at the highest proficiency it is possible to avoid all damage with
+    // Screw Attack (which also destroys the eggs and bombs).
+    let hit_rate = if inventory.items[Item::ScrewAttack as usize] {
+        1.0 - proficiency
+    } else {
+        1.0 - 0.8 * proficiency
+    };
+    let damage = base_attack_dps * hit_rate * time / suit_damage_factor(inventory) as f32;
+    // Overflow safeguard - bail here if Samus takes calamitous damage.
+    if damage > 10000.0 {
+        return None;
+    }
+    local.energy_used += damage as Capacity;
+
+    validate_energy(local, inventory, can_manage_reserves)
+}
+
+pub fn apply_mother_brain_1_requirement(
+    inventory: &Inventory,
+    mut local: LocalState,
+    proficiency: f32,
+    supers_double: bool,
+    can_manage_reserves: bool,
+) -> Option<LocalState> {
+    // The Zebetites and turrets are not modeled here; this covers damaging Mother Brain's
+    // brain until the glass breaks and the first phase ends.
+    let mut boss_hp: f32 = 3000.0;
+    let mut time: f32 = 0.0; // Cumulative time in seconds for the fight
+    let charge_damage = get_charge_damage(inventory);
+
+    // Assume an accuracy of between 75% (on lowest difficulty) to 100% (on highest):
+    let accuracy = 0.75 + 0.25 * proficiency;
+
+    // Assume a firing rate of between 50% (on lowest difficulty) to 100% (on highest):
+    let firing_rate = 0.5 + 0.5 * proficiency;
+
+    // Prioritize conserving ammo for the second phase by using Charge if available,
+    // then Missiles, then Supers:
+    if charge_damage > 0.0 {
+        // Assume max average rate of one charge shot per 1.5 seconds:
+        use_beam_shots(
+            charge_damage * accuracy,
+            1.5 / firing_rate,
+            &mut boss_hp,
+            &mut time,
+        );
+    }
+    local.missiles_used += use_shots(
+        inventory.max_missiles - local.missiles_used,
+        100.0 * accuracy,
+        0.5 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+    let super_damage = if supers_double { 600.0 } else { 300.0 };
+    local.supers_used += use_shots(
+        inventory.max_supers - local.supers_used,
+        super_damage * accuracy,
+        1.0 / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Finish with uncharged shots if needed, assuming a max average rate of 3 shots per second:
+    use_beam_shots(
+        get_uncharged_damage(inventory) * accuracy,
+        (1.0 / 3.0) / firing_rate,
+        &mut boss_hp,
+        &mut time,
+    );
+
+    // Assumed rate of damage to Samus per second, from the Rinkas and turrets:
+    let base_hit_dps = 10.0 * (1.0 - proficiency);
+    let damage = base_hit_dps * time / suit_damage_factor(inventory) as f32;
+    // Overflow safeguard - bail here if Samus takes calamitous damage.
+    if damage > 10000.0 {
+        return None;
+    }
+    local.energy_used += damage as Capacity;
+
+    validate_energy(local, inventory, can_manage_reserves)
+}
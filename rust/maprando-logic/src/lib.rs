@@ -46,6 +46,8 @@ impl GlobalState {
         tech: &[bool],
         starting_local_state: &mut LocalState,
     ) {
+        // A progressive pseudo-item grants the next item in its sequence that isn't collected yet:
+        let item = item.resolve_progressive(|x| self.inventory.items[x as usize]);
         self.inventory.items[item as usize] = true;
         match item {
             Item::Missile => {
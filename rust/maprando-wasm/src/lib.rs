@@ -1,4 +1,6 @@
-use maprando_game::RidleyStuck;
+use hashbrown::HashMap;
+use maprando::tracker::{Tracker, TrackerData, TrackerProgress};
+use maprando_game::{GameData, RidleyStuck};
 use maprando_logic::{Inventory, LocalState, boss_requirements::*};
 use wasm_bindgen::prelude::*;
 
@@ -134,3 +136,153 @@ pub fn can_defeat_mother_brain_2(
         None => JsValue::null(),
     }
 }
+
+#[wasm_bindgen]
+pub fn can_defeat_kraid(
+    inventory: JsValue,
+    local: JsValue,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> JsValue {
+    let inventory: Inventory = serde_wasm_bindgen::from_value(inventory).unwrap();
+    let local = serde_wasm_bindgen::from_value(local).unwrap_or_else(|_| LocalState::full());
+
+    match apply_kraid_requirement(&inventory, local, proficiency, can_manage_reserves) {
+        Some(local) => serde_wasm_bindgen::to_value(&local).unwrap(),
+        None => JsValue::null(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn can_defeat_crocomire(
+    inventory: JsValue,
+    local: JsValue,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> JsValue {
+    let inventory: Inventory = serde_wasm_bindgen::from_value(inventory).unwrap();
+    let local = serde_wasm_bindgen::from_value(local).unwrap_or_else(|_| LocalState::full());
+
+    match apply_crocomire_requirement(&inventory, local, proficiency, can_manage_reserves) {
+        Some(local) => serde_wasm_bindgen::to_value(&local).unwrap(),
+        None => JsValue::null(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn can_defeat_spore_spawn(
+    inventory: JsValue,
+    local: JsValue,
+    proficiency: f32,
+    can_manage_reserves: bool,
+) -> JsValue {
+    let inventory: Inventory = serde_wasm_bindgen::from_value(inventory).unwrap();
+    let local = serde_wasm_bindgen::from_value(local).unwrap_or_else(|_| LocalState::full());
+
+    match apply_spore_spawn_requirement(&inventory, local, proficiency, can_manage_reserves) {
+        Some(local) => serde_wasm_bindgen::to_value(&local).unwrap(),
+        None => JsValue::null(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn can_defeat_golden_torizo(
+    inventory: JsValue,
+    local: JsValue,
+    proficiency: f32,
+    can_manage_reserves: bool,
+    can_be_very_patient: bool,
+) -> JsValue {
+    let inventory: Inventory = serde_wasm_bindgen::from_value(inventory).unwrap();
+    let local = serde_wasm_bindgen::from_value(local).unwrap_or_else(|_| LocalState::full());
+
+    match apply_golden_torizo_requirement(
+        &inventory,
+        local,
+        proficiency,
+        can_manage_reserves,
+        can_be_very_patient,
+    ) {
+        Some(local) => serde_wasm_bindgen::to_value(&local).unwrap(),
+        None => JsValue::null(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn can_defeat_mother_brain_1(
+    inventory: JsValue,
+    local: JsValue,
+    proficiency: f32,
+    supers_double: bool,
+    can_manage_reserves: bool,
+) -> JsValue {
+    let inventory: Inventory = serde_wasm_bindgen::from_value(inventory).unwrap();
+    let local = serde_wasm_bindgen::from_value(local).unwrap_or_else(|_| LocalState::full());
+
+    match apply_mother_brain_1_requirement(
+        &inventory,
+        local,
+        proficiency,
+        supers_double,
+        can_manage_reserves,
+    ) {
+        Some(local) => serde_wasm_bindgen::to_value(&local).unwrap(),
+        None => JsValue::null(),
+    }
+}
+
+fn to_js_error(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&format!("{e:#}"))
+}
+
+// Logic-aware tracker for a seed. This is constructed from the bundle of logic data files
+// (served at /logic/data_files.json) and the seed's tracker data (tracker.json).
+#[wasm_bindgen]
+pub struct SeedTracker {
+    game_data: GameData,
+    tracker: Tracker,
+}
+
+#[wasm_bindgen]
+impl SeedTracker {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data_files_json: &str, tracker_data_json: &str) -> Result<SeedTracker, JsValue> {
+        let data_files: HashMap<String, String> =
+            serde_json::from_str(data_files_json).map_err(to_js_error)?;
+        let game_data = GameData::load_from_files(data_files).map_err(to_js_error)?;
+        let tracker_data: TrackerData =
+            serde_json::from_str(tracker_data_json).map_err(to_js_error)?;
+        let tracker = Tracker::new(tracker_data, &game_data).map_err(to_js_error)?;
+        Ok(SeedTracker { game_data, tracker })
+    }
+
+    // Given the player's progress (collected items, set flags, and opened doors), return which
+    // item locations, doors, and flags are reachable and bireachable.
+    pub fn update(&self, progress: JsValue) -> Result<JsValue, JsValue> {
+        let progress: TrackerProgress =
+            serde_wasm_bindgen::from_value(progress).map_err(to_js_error)?;
+        let status = self
+            .tracker
+            .get_status(&progress, &self.game_data)
+            .map_err(to_js_error)?;
+        serde_wasm_bindgen::to_value(&status).map_err(to_js_error)
+    }
+
+    // Explain why a node is out of logic: which links out of the nearest reachable vertices are
+    // blocked, and by what. With `reverse`, explain why it's not possible to return from it.
+    pub fn explain(
+        &self,
+        progress: JsValue,
+        room_id: usize,
+        node_id: usize,
+        reverse: bool,
+    ) -> Result<JsValue, JsValue> {
+        let progress: TrackerProgress =
+            serde_wasm_bindgen::from_value(progress).map_err(to_js_error)?;
+        let explanation = self
+            .tracker
+            .explain(&progress, room_id, node_id, reverse, &self.game_data)
+            .map_err(to_js_error)?;
+        serde_wasm_bindgen::to_value(&explanation).map_err(to_js_error)
+    }
+}
@@ -1,3 +1,4 @@
+use actix_web::web::Bytes;
 use anyhow::Result;
 use askama::Template;
 use glob::glob;
@@ -7,7 +8,7 @@ use json::JsonValue;
 use log::warn;
 use maprando::{
     preset::PresetData,
-    randomize::{EssentialSpoilerData, Randomization},
+    randomize::{EssentialSpoilerData, Randomization, normalize::normalize_requirement},
     settings::{Objective, RandomizerSettings},
     spoiler_map::{self, image::RgbaImage},
     traverse::{LockedDoorData, apply_requirement},
@@ -26,7 +27,8 @@ use maprando_game::{
     TECH_ID_CAN_SIDE_PLATFORM_CROSS_ROOM_JUMP, TECH_ID_CAN_SKIP_DOOR_LOCK, TECH_ID_CAN_SPEEDBALL,
     TECH_ID_CAN_SPRING_BALL_BOUNCE, TECH_ID_CAN_STATIONARY_SPIN_JUMP,
     TECH_ID_CAN_STUTTER_WATER_SHINECHARGE, TECH_ID_CAN_SUPER_SINK, TECH_ID_CAN_TEMPORARY_BLUE,
-    TECH_ID_CAN_WALLJUMP, TechId, VertexAction, VertexKey,
+    TECH_ID_CAN_WALLJUMP, TechId, VertexAction, VertexKey, read_logic_data_files,
+    requirement_render::RenderFormat,
 };
 use maprando_logic::{GlobalState, Inventory, LocalState};
 use std::{io::Cursor, path::PathBuf};
@@ -55,7 +57,8 @@ struct RoomStrat {
     detail_note: String,
     dev_note: String,
     entrance_condition: Option<String>,
-    requires: String, // new-line separated requirements
+    requires: String,           // new-line separated requirements
+    requires_processed: String, // HTML rendering of the requirements used by the randomizer
     exit_condition: Option<String>,
     clears_obstacles: Vec<String>,
     resets_obstacles: Vec<String>,
@@ -159,6 +162,7 @@ pub struct LogicData {
     pub notable_strat_counts: HashMap<(RoomId, NotableId), usize>, // Map from tech ID to strat count using that tech.
     pub strat_html: HashMap<(RoomId, NodeId, NodeId, StratId), String>, // Map from (room ID, from node ID, to node ID, strat ID) to rendered HTML.
     pub vanilla_map_png: Vec<u8>, // PNG of vanilla map, to show on logic index page
+    pub data_files_json: Bytes, // Bundle of logic data files, for loading game data in the browser (e.g. by the tracker)
 }
 
 fn list_room_diagram_files() -> HashMap<usize, String> {
@@ -1104,6 +1108,18 @@ fn make_room_template<'a>(
         };
 
         let strat_name = strat_json["name"].as_str().unwrap().to_string();
+        // Requirements of the processed links for this strat (across obstacle states, etc.):
+        let link_key = (room_id, from_node_id, to_node_id, strat_name.clone());
+        let requires_processed = match links_by_ids.get(&link_key) {
+            Some(links) => {
+                let reqs: Vec<Requirement> = links.iter().map(|x| x.requirement.clone()).collect();
+                match normalize_requirement(&Requirement::Or(reqs), None) {
+                    Requirement::Free => String::new(),
+                    req => req.render(game_data, RenderFormat::Html),
+                }
+            }
+            None => String::new(),
+        };
         let strat = RoomStrat {
             room_id,
             room_name: room_name.clone(),
@@ -1120,6 +1136,7 @@ fn make_room_template<'a>(
             dev_note: game_data.parse_note(&strat_json["devNote"]).join(" "),
             entrance_condition,
             requires: make_requires(&strat_json["requires"]),
+            requires_processed,
             unlocks_doors,
             exit_condition,
             clears_obstacles,
@@ -1379,6 +1396,7 @@ impl LogicData {
         let vanilla_map_data =
             get_vanilla_map_data(vanilla_map, game_data, &preset_data.default_preset)?;
         out.vanilla_map_png = vanilla_map_data.png;
+        out.data_files_json = Bytes::from(serde_json::to_vec(&read_logic_data_files()?)?);
         let room_diagram_listing = list_room_diagram_files();
         let mut room_templates: Vec<RoomTemplate> = vec![];
 
@@ -201,6 +201,7 @@ async fn main() {
             .service(seed::scope())
             .service(logic::scope())
             .service(upgrade::upgrade_settings)
+            .service(upgrade::settings_code)
             .service(actix_files::Files::new(
                 "/static/sm-json-data",
                 "../sm-json-data",
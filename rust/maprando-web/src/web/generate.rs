@@ -2,8 +2,9 @@ use crate::web::{AppData, PresetData, VersionInfo};
 use actix_web::{HttpResponse, Responder, get, web};
 use askama::Template;
 use hashbrown::HashMap;
-use maprando::settings::{ObjectiveGroup, get_objective_groups};
+use maprando::settings::{ObjectiveGroup, decode_settings_code, get_objective_groups};
 use maprando_game::{NotableId, RoomId, StartLocation, TechId};
+use serde_derive::Deserialize;
 
 #[derive(Template)]
 #[template(path = "generate/main.html")]
@@ -30,10 +31,31 @@ struct GenerateTemplate<'a> {
     notable_strat_counts: &'a HashMap<(RoomId, NotableId), usize>,
     video_storage_url: &'a str,
     start_locations_by_area: Vec<(String, Vec<StartLocation>)>,
+    settings_code_json: String,
+    settings_code_error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GenerateQuery {
+    settings: Option<String>,
 }
 
 #[get("/generate")]
-async fn generate(app_data: web::Data<AppData>) -> impl Responder {
+async fn generate(
+    query: web::Query<GenerateQuery>,
+    app_data: web::Data<AppData>,
+) -> impl Responder {
+    // Settings shared by a code in the URL are applied in place of the saved settings:
+    let mut settings_code_json = "null".to_string();
+    let mut settings_code_error: Option<String> = None;
+    if let Some(code) = &query.settings {
+        match decode_settings_code(code, &app_data.preset_data) {
+            // Escape "</" so the JSON can't close the script tag it is embedded in:
+            Ok((settings_str, _)) => settings_code_json = settings_str.replace("</", "<\\/"),
+            Err(e) => settings_code_error = Some(format!("Invalid settings code: {e:#}")),
+        }
+    }
+
     let item_pool_multiple: Vec<String> = ["Missile", "ETank", "ReserveTank", "Super", "PowerBomb"]
         .into_iter()
         .map(|x| x.to_string())
@@ -177,6 +199,8 @@ async fn generate(app_data: web::Data<AppData>) -> impl Responder {
         notable_strat_counts: &app_data.logic_data.notable_strat_counts,
         video_storage_url: &app_data.video_storage_url,
         start_locations_by_area,
+        settings_code_json,
+        settings_code_error,
     };
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
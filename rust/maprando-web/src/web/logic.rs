@@ -1,4 +1,5 @@
 mod logic_boss_calculator;
+mod logic_data_files;
 mod logic_main;
 mod logic_notable;
 mod logic_room;
@@ -21,4 +22,5 @@ pub fn scope() -> actix_web::Scope {
         .service(logic_notable::logic_notable)
         .service(logic_boss_calculator::logic_boss_calculator)
         .service(logic_vanilla_map::logic_vanilla_map)
+        .service(logic_data_files::logic_data_files)
 }
@@ -0,0 +1,10 @@
+use crate::web::AppData;
+use actix_web::{HttpResponse, Responder, get, web};
+
+#[get("/data_files.json")]
+async fn logic_data_files(app_data: web::Data<AppData>) -> impl Responder {
+    // Cloning the bytes only copies a reference to the shared buffer:
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(app_data.logic_data.data_files_json.clone())
+}
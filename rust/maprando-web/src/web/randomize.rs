@@ -6,11 +6,17 @@ use actix_web::{HttpRequest, HttpResponse, Responder, post, web};
 use helpers::*;
 use log::info;
 use maprando::{
+    area_assignment::balance_map_areas,
+    map_repository::{map_matches_filter, parse_map_json},
+    mystery::{generate_mystery_settings, parse_mystery_weights},
     randomize::{
         DifficultyConfig, Randomization, Randomizer, filter_links, get_difficulty_tiers,
         get_objectives, order_map_areas, randomize_doors, randomize_map_areas,
     },
-    settings::{AreaAssignment, RandomizerSettings, StartLocationMode, try_upgrade_settings},
+    settings::{
+        AreaAssignment, CUSTOM_MAP_LAYOUT, RandomizerSettings, StartLocationMode,
+        try_upgrade_settings,
+    },
     spoiler_log::SpoilerLog,
 };
 use maprando_game::{LinksDataGroup, Map};
@@ -30,6 +36,9 @@ struct SeedData {
     door_randomization_seed: usize,
     item_placement_seed: usize,
     settings: RandomizerSettings,
+    // Whether the settings were sampled from mystery weights (and hidden until the spoiler is unlocked):
+    #[serde(default)]
+    mystery: bool,
     // TODO: get rid of all the redundant stuff below:
     race_mode: bool,
     preset: Option<String>,
@@ -69,6 +78,9 @@ struct SeedData {
 struct RandomizeRequest {
     spoiler_token: Text<String>,
     settings: Text<String>,
+    mystery_weights: Option<Text<String>>,
+    // Map JSON, used when the map layout is "Custom":
+    custom_map: Option<Text<String>>,
 }
 
 #[derive(Serialize)]
@@ -90,11 +102,64 @@ async fn randomize(
             }
         };
 
-    if settings.other_settings.random_seed == Some(0) {
-        return HttpResponse::BadRequest().body("Invalid random seed: 0");
+    let mystery = req.mystery_weights.is_some();
+    if let Some(weights_str) = &req.mystery_weights {
+        let weights = match parse_mystery_weights(&weights_str.0) {
+            Ok(w) => w,
+            Err(e) => {
+                return HttpResponse::BadRequest().body(format!("{e:#}"));
+            }
+        };
+        let mut mystery_rng = rand::rngs::StdRng::from_entropy();
+        settings = match generate_mystery_settings(
+            &weights,
+            &app_data.preset_data,
+            &app_data.game_data,
+            &mut mystery_rng,
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                return HttpResponse::BadRequest().body(format!("{e:#}"));
+            }
+        };
+        // The settings stay hidden until the spoiler is unlocked, so the seed is always locked:
+        settings.other_settings.race_mode = true;
+        settings.other_settings.random_seed = None;
     }
 
-    let mut validated_preset = false;
+    let errors = settings.validate(&app_data.preset_data, &app_data.game_data);
+    if !errors.is_empty() {
+        let error_strs: Vec<String> = errors.iter().map(|x| x.to_string()).collect();
+        return HttpResponse::BadRequest()
+            .body(format!("Invalid settings:\n{}", error_strs.join("\n")));
+    }
+
+    let custom_map: Option<Map> = if settings.map_layout == CUSTOM_MAP_LAYOUT {
+        let Some(map_str) = &req.custom_map else {
+            return HttpResponse::BadRequest()
+                .body("No map was uploaded for the custom map layout");
+        };
+        let map = match parse_map_json(&map_str.0, &app_data.game_data) {
+            Ok(m) => m,
+            Err(e) => {
+                return HttpResponse::BadRequest().body(format!("{e:#}"));
+            }
+        };
+        if !map_matches_filter(&map, &settings.map_filter, &app_data.game_data) {
+            return HttpResponse::BadRequest()
+                .body("The uploaded map does not match the map filter");
+        }
+        Some(map)
+    } else if !app_data.map_repositories.contains_key(&settings.map_layout) {
+        return HttpResponse::BadRequest().body(format!(
+            "Unrecognized map layout option: {}",
+            settings.map_layout
+        ));
+    } else {
+        None
+    };
+
+    let mut validated_preset = mystery;
     for s in &app_data.preset_data.full_presets {
         if s == &settings {
             validated_preset = true;
@@ -121,9 +186,6 @@ async fn randomize(
         random_seed
     };
 
-    if skill_settings.ridley_proficiency < 0.0 || skill_settings.ridley_proficiency > 1.0 {
-        return HttpResponse::BadRequest().body("Invalid Ridley proficiency");
-    }
     let mut rng_seed = [0u8; 32];
     rng_seed[..8].copy_from_slice(&random_seed.to_le_bytes());
     let mut rng = rand::rngs::StdRng::from_seed(rng_seed);
@@ -181,15 +243,19 @@ async fn randomize(
         let map_seed = (rng.next_u64() & 0xFFFFFFFF) as usize;
         let door_randomization_seed = (rng.next_u64() & 0xFFFFFFFF) as usize;
 
-        if !app_data.map_repositories.contains_key(&map_layout) {
-            // TODO: it doesn't make sense to panic on things like this.
-            panic!("Unrecognized map layout option: {map_layout}");
-        }
-
-        if map_batch.is_empty() {
-            map_batch = app_data.map_repositories[&map_layout]
-                .get_map_batch(map_seed, &app_data.game_data)
-                .unwrap();
+        if let Some(m) = &custom_map {
+            map_batch = vec![m.clone()];
+        } else if map_batch.is_empty() {
+            map_batch = match app_data.map_repositories[&map_layout].get_filtered_map_batch(
+                map_seed,
+                &settings.map_filter,
+                &app_data.game_data,
+            ) {
+                Ok(x) => x,
+                Err(e) => {
+                    return HttpResponse::BadRequest().body(e.to_string());
+                }
+            };
         }
 
         let mut map = map_batch.pop().unwrap();
@@ -201,6 +267,13 @@ async fn randomize(
                 randomize_map_areas(&mut map, map_seed);
             }
             AreaAssignment::Standard => {}
+            AreaAssignment::Balanced => {
+                let pins = &settings.other_settings.area_pins;
+                if let Err(e) = balance_map_areas(&mut map, pins, map_seed, &app_data.game_data) {
+                    info!("Map seed={map_seed}: Area assignment failed: {e}");
+                    continue;
+                }
+            }
         }
         let objectives = get_objectives(&settings, Some(&map), &app_data.game_data, &mut rng);
         let locked_door_data = randomize_doors(
@@ -278,6 +351,7 @@ async fn randomize(
         door_randomization_seed: output.door_randomization_seed,
         item_placement_seed: output.item_placement_seed,
         settings: settings.clone(),
+        mystery,
         race_mode,
         preset: skill_settings.preset.clone(),
         item_progression_preset: item_settings.preset.clone(),
@@ -336,6 +410,8 @@ async fn randomize(
         seed_name,
         &seed_data,
         &req.settings.0,
+        req.mystery_weights.as_ref().map(|x| x.0.as_str()),
+        custom_map.as_ref(),
         &req.spoiler_token.0,
         &settings,
         &output.randomization,
@@ -1,9 +1,10 @@
 use super::SeedData;
 use crate::web::{AppData, VersionInfo};
-use actix_web::HttpRequest;
+use actix_web::{HttpRequest, web};
 use anyhow::{Result, bail};
 use askama::Template;
 use hashbrown::{HashMap, HashSet};
+use log::{error, info};
 use maprando::{
     helpers::get_item_priorities,
     preset::PresetData,
@@ -15,8 +16,9 @@ use maprando::{
     },
     spoiler_log::SpoilerLog,
     spoiler_map,
+    tracker::TrackerData,
 };
-use maprando_game::{GameData, NotableId, RoomId, TechId};
+use maprando_game::{GameData, Map, NotableId, RoomId, TechId};
 use rand::{RngCore, SeedableRng};
 use serde::Serialize;
 
@@ -31,6 +33,7 @@ pub struct SeedHeaderTemplate<'a> {
     item_priority_groups: Vec<ItemPriorityGroup>,
     objective_names: HashMap<String, String>,
     race_mode: bool,
+    mystery: bool,
     preset: String,
     item_progression_preset: String,
     progression_rate: String,
@@ -136,6 +139,9 @@ impl SeedHeaderTemplate<'_> {
             AreaAssignment::Random => {
                 game_variations.push("Random area assignment");
             }
+            AreaAssignment::Balanced => {
+                game_variations.push("Balanced area assignment");
+            }
             AreaAssignment::Standard => {}
         }
         if self.settings.other_settings.door_locks_size == DoorLocksSize::Small {
@@ -164,6 +170,7 @@ impl SeedHeaderTemplate<'_> {
 #[template(path = "seed/seed_footer.html")]
 pub struct SeedFooterTemplate {
     race_mode: bool,
+    mystery: bool,
     all_items_spawn: bool,
     supers_double: bool,
     ultra_low_qol: bool,
@@ -178,11 +185,13 @@ pub async fn save_seed(
     seed_name: &str,
     seed_data: &SeedData,
     input_settings: &str,
+    mystery_weights: Option<&str>,
+    custom_map: Option<&Map>,
     spoiler_token: &str,
     settings: &RandomizerSettings,
     randomization: &Randomization,
     spoiler_log: &SpoilerLog,
-    app_data: &AppData,
+    app_data: &web::Data<AppData>,
 ) -> Result<()> {
     if check_seed_exists(seed_name, app_data).await {
         bail!("Seed name already exists: {}", seed_name);
@@ -200,8 +209,15 @@ pub async fn save_seed(
         input_settings.as_bytes().to_owned(),
     ));
 
+    let prefix = if seed_data.race_mode {
+        "locked"
+    } else {
+        "public"
+    };
+
     // Write the seed header HTML and footer HTML
-    let (seed_header_html, seed_footer_html) = render_seed(seed_name, seed_data, app_data)?;
+    let (seed_header_html, seed_footer_html) =
+        render_seed(seed_name, seed_data, seed_data.mystery, app_data)?;
     files.push(SeedFile::new(
         "seed_header.html",
         seed_header_html.into_bytes(),
@@ -210,12 +226,20 @@ pub async fn save_seed(
         "seed_footer.html",
         seed_footer_html.into_bytes(),
     ));
-
-    let prefix = if seed_data.race_mode {
-        "locked"
-    } else {
-        "public"
-    };
+    if seed_data.mystery {
+        // The settings are hidden until the spoiler is unlocked, at which point the seed page
+        // switches to these versions of the header and footer showing the full settings:
+        let (seed_header_html, seed_footer_html) =
+            render_seed(seed_name, seed_data, false, app_data)?;
+        files.push(SeedFile::new(
+            &format!("{prefix}/seed_header.html"),
+            seed_header_html.into_bytes(),
+        ));
+        files.push(SeedFile::new(
+            &format!("{prefix}/seed_footer.html"),
+            seed_footer_html.into_bytes(),
+        ));
+    }
 
     if seed_data.race_mode {
         files.push(SeedFile::new(
@@ -231,30 +255,64 @@ pub async fn save_seed(
             .as_bytes()
             .to_vec(),
     ));
+    if let Some(map) = custom_map {
+        // Keep the uploaded map as given (before any area reassignment), so the seed can be
+        // regenerated from its inputs like those using a map repository:
+        files.push(SeedFile::new("custom_map.json", serde_json::to_vec(map)?));
+    }
 
     // Write the randomizer settings:
     let mut buf = Vec::new();
     let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
     let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
     settings.serialize(&mut ser).unwrap();
-    files.push(SeedFile::new("public/settings.json", buf));
+    if seed_data.mystery {
+        // Keep a private copy of the settings, for use in patching the ROM while the
+        // public copy is still locked:
+        files.push(SeedFile::new("settings.json", buf.clone()));
+        files.push(SeedFile::new(&format!("{prefix}/settings.json"), buf));
+    } else {
+        files.push(SeedFile::new("public/settings.json", buf));
+    }
+    if let Some(weights) = mystery_weights {
+        files.push(SeedFile::new(
+            &format!("{prefix}/mystery_weights.json"),
+            weights.as_bytes().to_vec(),
+        ));
+    }
+
+    // Write the data used by the logic-aware tracker. It contains no item placements, but it
+    // does contain the settings, so for mystery seeds it is locked along with them:
+    let difficulty = DifficultyConfig::new(
+        &settings.skill_assumption_settings,
+        &app_data.game_data,
+        &app_data.preset_data.tech_by_difficulty["Implicit"],
+        &app_data.preset_data.notables_by_difficulty["Implicit"],
+    );
+    let tracker_data = TrackerData::new(randomization, settings, &difficulty);
+    let tracker_prefix = if seed_data.mystery { prefix } else { "public" };
+    files.push(SeedFile::new(
+        &format!("{tracker_prefix}/tracker.json"),
+        serde_json::to_vec(&tracker_data)?,
+    ));
 
     // Write the Randomization struct:
+    let randomization_bytes = serde_json::to_vec(&randomization)?;
     files.push(SeedFile::new(
         "randomization.json",
-        serde_json::to_string(&randomization)?.as_bytes().to_vec(),
+        randomization_bytes.clone(),
     ));
 
     // Write the spoiler log
     let spoiler_bytes = serde_json::to_vec(&spoiler_log).unwrap();
     files.push(SeedFile::new(
         &format!("{prefix}/spoiler.json"),
-        spoiler_bytes,
+        spoiler_bytes.clone(),
     ));
 
     // Write the spoiler maps
     let spoiler_maps =
-        spoiler_map::get_spoiler_map(randomization, &app_data.game_data, settings, false).unwrap();
+        spoiler_map::get_spoiler_map(randomization, &app_data.game_data, settings, false)?;
     files.push(SeedFile::new(
         &format!("{prefix}/map-explored.png"),
         spoiler_maps.explored,
@@ -263,6 +321,12 @@ pub async fn save_seed(
         &format!("{prefix}/map-outline.png"),
         spoiler_maps.outline,
     ));
+    let spoiler_svg =
+        spoiler_map::get_spoiler_svg(randomization, spoiler_log, &app_data.game_data, settings)?;
+    files.push(SeedFile::new(
+        &format!("{prefix}/map.svg"),
+        spoiler_svg.into_bytes(),
+    ));
 
     // Write the spoiler visualizer
     for (filename, data) in &app_data.visualizer_files {
@@ -275,9 +339,57 @@ pub async fn save_seed(
         files,
     };
     app_data.seed_repository.put_seed(seed).await?;
+
+    // The route animation is slow to encode, so it is generated in the background, after the
+    // seed is stored. It is kept private and served only once the spoiler is public (see
+    // `get_seed_file`), so that unlocking a race seed doesn't need to wait for it.
+    let seed_name = seed_name.to_string();
+    let settings = settings.clone();
+    let app_data = app_data.clone();
+    actix_web::rt::spawn(async move {
+        if let Err(e) = save_route_animation(
+            &seed_name,
+            randomization_bytes,
+            spoiler_bytes,
+            settings,
+            &app_data,
+        )
+        .await
+        {
+            error!("Error generating route animation for seed {seed_name}: {e:#}");
+        }
+    });
     Ok(())
 }
 
+async fn save_route_animation(
+    seed_name: &str,
+    randomization_bytes: Vec<u8>,
+    spoiler_bytes: Vec<u8>,
+    settings: RandomizerSettings,
+    app_data: &web::Data<AppData>,
+) -> Result<()> {
+    info!("Generating route animation for seed {seed_name}");
+    let data = web::block({
+        let app_data = app_data.clone();
+        move || -> Result<Vec<u8>> {
+            let randomization: Randomization = serde_json::from_slice(&randomization_bytes)?;
+            let spoiler_log: SpoilerLog = serde_json::from_slice(&spoiler_bytes)?;
+            spoiler_map::get_spoiler_route_animation(
+                &randomization,
+                &spoiler_log,
+                &app_data.game_data,
+                &settings,
+            )
+        }
+    })
+    .await??;
+    app_data
+        .seed_repository
+        .put_file(seed_name, "map-route.gif".to_string(), data)
+        .await
+}
+
 pub fn format_http_headers(req: &HttpRequest) -> serde_json::Map<String, serde_json::Value> {
     let map: serde_json::Map<String, serde_json::Value> = req
         .headers()
@@ -323,6 +435,7 @@ fn get_enabled_notables(notables: &[bool], game_data: &GameData) -> HashSet<(Roo
 pub fn render_seed(
     seed_name: &str,
     seed_data: &SeedData,
+    mystery: bool,
     app_data: &AppData,
 ) -> Result<(String, String)> {
     let enabled_tech: HashSet<TechId> =
@@ -346,6 +459,7 @@ pub fn render_seed(
         ),
         objective_names,
         race_mode: seed_data.race_mode,
+        mystery,
         timestamp: seed_data.timestamp,
         preset: seed_data.preset.clone().unwrap_or("Custom".to_string()),
         item_progression_preset: seed_data
@@ -433,6 +547,7 @@ pub fn render_seed(
 
     let seed_footer_template = SeedFooterTemplate {
         race_mode: seed_data.race_mode,
+        mystery,
         all_items_spawn: seed_data.all_items_spawn,
         supers_double: seed_data.supers_double,
         ultra_low_qol: seed_data.ultra_low_qol,
@@ -100,11 +100,19 @@ async fn customize_seed(
     .unwrap();
     let seed_data = json::parse(&seed_data_str).unwrap();
 
-    let settings_bytes = app_data
+    let mut settings_bytes = app_data
         .seed_repository
         .get_file(seed_name, "public/settings.json")
         .await
         .unwrap_or(vec![]);
+    if settings_bytes.is_empty() {
+        // Mystery seeds keep a private copy of the settings, since the public one is locked:
+        settings_bytes = app_data
+            .seed_repository
+            .get_file(seed_name, "settings.json")
+            .await
+            .unwrap_or(vec![]);
+    }
     let settings: Option<RandomizerSettings> = if settings_bytes.is_empty() {
         None
     } else {
@@ -1,6 +1,6 @@
 use crate::{VISUALIZER_PATH, web::AppData};
 use actix_web::{HttpResponse, Responder, get, web};
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use askama::Template;
 use log::error;
 use std::path::Path;
@@ -9,6 +9,23 @@ use std::path::Path;
 #[template(path = "errors/file_not_found.html")]
 struct FileNotFoundTemplate {}
 
+// Get the step-by-step route animation, which is generated in the background when the seed is
+// saved (see `save_seed`). It is stored privately, and only available once the spoiler is public:
+// either the seed is not a race seed, or it has been unlocked.
+async fn get_route_animation(seed_name: &str, app_data: &AppData) -> Result<Vec<u8>> {
+    let repo = &app_data.seed_repository;
+    let (spoiler_token, unlocked_timestamp) = futures::join!(
+        repo.get_file(seed_name, "spoiler_token.txt"),
+        repo.get_file(seed_name, "unlocked_timestamp.txt")
+    );
+    if spoiler_token.is_ok() && unlocked_timestamp.is_err() {
+        bail!("Spoiler is not unlocked for seed {seed_name}");
+    }
+    repo.get_file(seed_name, "map-route.gif")
+        .await
+        .context("Route animation is not available (yet)")
+}
+
 #[get("/{name}/data/{filename:.*}")]
 async fn get_seed_file(
     info: web::Path<(String, String)>,
@@ -25,6 +42,10 @@ async fn get_seed_file(
         std::fs::read(&path)
             .map_err(anyhow::Error::from)
             .with_context(|| format!("Error reading static file: {}", path.display()))
+    } else if filename == "map-route.gif" {
+        get_route_animation(seed_name, &app_data)
+            .await
+            .with_context(|| format!("Error getting route animation for seed {seed_name}"))
     } else {
         app_data
             .seed_repository
@@ -50,6 +50,27 @@ async fn view_seed(info: web::Path<(String,)>, app_data: web::Data<AppData>) ->
             .seed_repository
             .get_file(seed_name, "spoiler_token.txt"),
     );
+    let unlocked_timestamp_str =
+        String::from_utf8(unlocked_timestamp_str.unwrap_or(vec![])).unwrap();
+
+    // For mystery seeds, the header and footer showing the settings become public once the
+    // spoiler is unlocked:
+    let (seed_header, seed_footer) = if unlocked_timestamp_str.is_empty() {
+        (seed_header, seed_footer)
+    } else {
+        let (public_header, public_footer) = futures::join!(
+            app_data
+                .seed_repository
+                .get_file(seed_name, "public/seed_header.html"),
+            app_data
+                .seed_repository
+                .get_file(seed_name, "public/seed_footer.html"),
+        );
+        match (public_header, public_footer) {
+            (Ok(header), Ok(footer)) => (Ok(header), Ok(footer)),
+            _ => (seed_header, seed_footer),
+        }
+    };
     let spoiler_token = String::from_utf8(spoiler_token.unwrap_or(vec![])).unwrap();
     let spoiler_token_prefix = if spoiler_token.is_empty() {
         "".to_string()
@@ -60,8 +81,7 @@ async fn view_seed(info: web::Path<(String,)>, app_data: web::Data<AppData>) ->
         (Ok(header), Ok(footer)) => {
             let customize_template = CustomizeSeedTemplate {
                 version_info: app_data.version_info.clone(),
-                unlocked_timestamp_str: String::from_utf8(unlocked_timestamp_str.unwrap_or(vec![]))
-                    .unwrap(),
+                unlocked_timestamp_str,
                 spoiler_token_prefix: spoiler_token_prefix.to_string(),
                 seed_header: String::from_utf8(header.to_vec()).unwrap(),
                 seed_footer: String::from_utf8(footer.to_vec()).unwrap(),
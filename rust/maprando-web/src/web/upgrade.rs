@@ -1,7 +1,7 @@
 use crate::web::AppData;
 use actix_web::{HttpResponse, Responder, post, web};
 use log::error;
-use maprando::settings::try_upgrade_settings;
+use maprando::settings::{encode_settings_code, try_upgrade_settings};
 
 #[post("/upgrade-settings")]
 async fn upgrade_settings(settings_str: String, app_data: web::Data<AppData>) -> impl Responder {
@@ -15,3 +15,20 @@ async fn upgrade_settings(settings_str: String, app_data: web::Data<AppData>) ->
         }
     }
 }
+
+#[post("/settings-code")]
+async fn settings_code(settings_str: String, app_data: web::Data<AppData>) -> impl Responder {
+    let settings = match try_upgrade_settings(settings_str, &app_data.preset_data, false) {
+        Ok((_, s)) => s,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(e.to_string());
+        }
+    };
+    match encode_settings_code(&settings) {
+        Ok(code) => HttpResponse::Ok().content_type("text/plain").body(code),
+        Err(e) => {
+            error!("Failed to encode settings code: {e}");
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
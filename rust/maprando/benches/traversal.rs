@@ -0,0 +1,200 @@
+// Benchmarks of traversal and seed generation, over representative maps and skill presets.
+// Run with `cargo bench -p maprando`. This needs the same data as the randomizer (sm-json-data
+// and the map repositories); map repositories which aren't present are skipped.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use maprando::{
+    map_repository::MapRepository,
+    preset::PresetData,
+    randomize::{
+        DifficultyConfig, Randomizer, filter_links, get_difficulty_tiers, get_objectives,
+        normalize::{normalize_links, requirement_size},
+        randomize_doors,
+    },
+    settings::RandomizerSettings,
+    tracker::{Tracker, TrackerData},
+};
+use maprando_game::{GameData, Link, LinksDataGroup, Map, StartLocation};
+use rand::SeedableRng;
+use std::{hint::black_box, path::Path, sync::OnceLock};
+
+const MAP_REPOS: [(&str, &str); 3] = [
+    ("vanilla", "../maps/vanilla"),
+    ("standard", "../maps/v119-standard-avro"),
+    ("wild", "../maps/v119-wild-avro"),
+];
+const SKILL_PRESETS: [&str; 3] = ["Basic", "Hard", "Very Hard"];
+
+struct BenchData {
+    game_data: GameData,
+    preset_data: PresetData,
+    maps: Vec<(&'static str, Map)>,
+}
+
+fn get_bench_data() -> &'static BenchData {
+    static DATA: OnceLock<BenchData> = OnceLock::new();
+    DATA.get_or_init(|| {
+        // Data paths are relative to the workspace directory:
+        let base_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let game_data = GameData::load_from_dir(&base_path).unwrap();
+        let preset_data = PresetData::load(
+            &base_path.join("data/tech_data.json"),
+            &base_path.join("data/notable_data.json"),
+            &base_path.join("data/presets"),
+            &game_data,
+        )
+        .unwrap();
+        let mut maps = vec![];
+        for (name, path) in MAP_REPOS {
+            let path = base_path.join(path);
+            if !path.exists() {
+                continue;
+            }
+            let repo = MapRepository::new(name, &path).unwrap();
+            maps.push((
+                name,
+                repo.get_map_batch(0, &game_data).unwrap().pop().unwrap(),
+            ));
+        }
+        BenchData {
+            game_data,
+            preset_data,
+            maps,
+        }
+    })
+}
+
+fn get_settings(data: &BenchData, skill_preset: &str) -> RandomizerSettings {
+    let mut settings = data.preset_data.default_preset.clone();
+    settings.skill_assumption_settings = data
+        .preset_data
+        .skill_presets
+        .iter()
+        .find(|p| p.preset.as_deref() == Some(skill_preset))
+        .unwrap()
+        .clone();
+    settings
+}
+
+fn get_difficulty(data: &BenchData, settings: &RandomizerSettings) -> DifficultyConfig {
+    DifficultyConfig::new(
+        &settings.skill_assumption_settings,
+        &data.game_data,
+        &data.preset_data.tech_by_difficulty["Implicit"],
+        &data.preset_data.notables_by_difficulty["Implicit"],
+    )
+}
+
+// Reachability with all items from the ship, which repeatedly traverses forward and in reverse
+// until no more flags are obtained.
+fn bench_full_traversal(c: &mut Criterion) {
+    let data = get_bench_data();
+    let game_data = &data.game_data;
+    let mut group = c.benchmark_group("full_traversal");
+    group.sample_size(20);
+    for (map_name, map) in &data.maps {
+        for skill_preset in SKILL_PRESETS {
+            let settings = get_settings(data, skill_preset);
+            let difficulty = get_difficulty(data, &settings);
+            let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+            let objectives = get_objectives(&settings, Some(map), game_data, &mut rng);
+            let tracker_data = TrackerData {
+                settings,
+                difficulty,
+                map: map.clone(),
+                objectives,
+                locked_doors: vec![],
+                start_location: StartLocation::ship(),
+            };
+            let tracker = Tracker::new(tracker_data, game_data).unwrap();
+            group.bench_function(BenchmarkId::new(*map_name, skill_preset), |b| {
+                b.iter(|| black_box(tracker.get_full_status(game_data).unwrap()))
+            });
+        }
+    }
+    group.finish();
+}
+
+// Item placement on a fixed map and seed, which is dominated by the incremental traversals
+// after each placement step.
+fn bench_seed_generation(c: &mut Criterion) {
+    let data = get_bench_data();
+    let game_data = &data.game_data;
+    let mut group = c.benchmark_group("seed_generation");
+    group.sample_size(10);
+    for (map_name, map) in &data.maps {
+        for skill_preset in SKILL_PRESETS {
+            let settings = get_settings(data, skill_preset);
+            let difficulty = get_difficulty(data, &settings);
+            let difficulty_tiers = get_difficulty_tiers(
+                &settings,
+                &data.preset_data.difficulty_tiers,
+                game_data,
+                &data.preset_data.tech_by_difficulty["Implicit"],
+                &data.preset_data.notables_by_difficulty["Implicit"],
+            );
+            let base_links_data = LinksDataGroup::new(
+                filter_links(&game_data.links, game_data, &difficulty),
+                game_data.vertex_isv.keys.len(),
+                0,
+            );
+            let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+            let objectives = get_objectives(&settings, Some(map), game_data, &mut rng);
+            let locked_door_data = randomize_doors(game_data, map, &settings, &objectives, 0);
+            group.bench_function(BenchmarkId::new(*map_name, skill_preset), |b| {
+                b.iter(|| {
+                    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+                    let randomizer = Randomizer::new(
+                        map,
+                        &locked_door_data,
+                        objectives.clone(),
+                        &settings,
+                        &difficulty_tiers,
+                        game_data,
+                        &base_links_data,
+                        &mut rng,
+                    );
+                    black_box(randomizer.randomize(0, 0, 1).is_ok())
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn total_requirement_size(links: &[Link]) -> usize {
+    links.iter().map(|x| requirement_size(&x.requirement)).sum()
+}
+
+// Requirement normalization of the filtered links, which is done when a `Randomizer` is created.
+// The reduction in the number of links and requirement nodes is printed for each preset.
+fn bench_normalization(c: &mut Criterion) {
+    let data = get_bench_data();
+    let game_data = &data.game_data;
+    let mut group = c.benchmark_group("normalize_links");
+    for skill_preset in SKILL_PRESETS {
+        let settings = get_settings(data, skill_preset);
+        let difficulty = get_difficulty(data, &settings);
+        let filtered_links = filter_links(&game_data.links, game_data, &difficulty);
+        let normalized_links = normalize_links(&filtered_links, &difficulty);
+        println!(
+            "{skill_preset}: links {} -> {}, requirement nodes {} -> {}",
+            filtered_links.len(),
+            normalized_links.len(),
+            total_requirement_size(&filtered_links),
+            total_requirement_size(&normalized_links)
+        );
+        group.bench_function(skill_preset, |b| {
+            b.iter(|| black_box(normalize_links(&filtered_links, &difficulty)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_full_traversal,
+    bench_seed_generation,
+    bench_normalization
+);
+criterion_main!(benches);
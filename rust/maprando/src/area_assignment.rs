@@ -0,0 +1,572 @@
+// Balanced area assignment. Starting from the map's own partition into areas (as with Standard
+// area assignment), the areas are relabeled to satisfy as many room-to-area pins as possible, the
+// remaining pinned rooms are moved into their area along with the rooms connecting them to it,
+// and then rooms on the boundary between two areas are moved from the larger area into the
+// smaller one until the area sizes are close to the average. Areas are kept connected, and each
+// move is checked to keep the map valid: the moved rooms can't overlap rooms of their new area,
+// which must still fit on its pause map. The Landing Site always stays in Crateria.
+
+use anyhow::{Result, bail};
+use hashbrown::{HashMap, HashSet};
+use maprando_game::{
+    AreaIdx, GameData, Map, RoomGeometryRoomIdx,
+    map_validation::{AREA_MAP_HEIGHT, AREA_MAP_WIDTH, LANDING_SITE_ROOM_ID},
+};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use std::collections::VecDeque;
+
+use crate::{patch::NUM_AREAS, randomize::Randomizer, settings::AreaPin};
+
+// Areas are rebalanced until each one is within this fraction of the average size (in map tiles),
+// or no more rooms can be moved:
+const BALANCE_TOLERANCE: f32 = 0.25;
+const CRATERIA_AREA: AreaIdx = 0;
+
+// The tiles of each room in the map, in map grid coordinates, and the rooms occupying each tile.
+// Room positions don't change during area assignment, so these are computed once.
+struct MapTiles {
+    room_tiles: Vec<Vec<(usize, usize)>>,
+    occupants: HashMap<(usize, usize), Vec<RoomGeometryRoomIdx>>,
+}
+
+fn get_map_tiles(map: &Map, game_data: &GameData) -> MapTiles {
+    let mut room_tiles: Vec<Vec<(usize, usize)>> = vec![vec![]; map.rooms.len()];
+    let mut occupants: HashMap<(usize, usize), Vec<RoomGeometryRoomIdx>> = HashMap::new();
+    for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+        if !map.room_mask[room_idx] {
+            continue;
+        }
+        let (x, y) = map.rooms[room_idx];
+        for (ty, row) in room.map.iter().enumerate() {
+            for (tx, &c) in row.iter().enumerate() {
+                if c == 1 {
+                    room_tiles[room_idx].push((x + tx, y + ty));
+                    occupants
+                        .entry((x + tx, y + ty))
+                        .or_default()
+                        .push(room_idx);
+                }
+            }
+        }
+    }
+    MapTiles {
+        room_tiles,
+        occupants,
+    }
+}
+
+fn get_room_neighbors(map: &Map, game_data: &GameData) -> Vec<Vec<RoomGeometryRoomIdx>> {
+    let mut neighbors: Vec<Vec<RoomGeometryRoomIdx>> = vec![vec![]; map.rooms.len()];
+    for (src, dst, _) in &map.doors {
+        let (Some(&(src_room_idx, _)), Some(&(dst_room_idx, _))) = (
+            game_data.room_and_door_idxs_by_door_ptr_pair.get(src),
+            game_data.room_and_door_idxs_by_door_ptr_pair.get(dst),
+        ) else {
+            continue;
+        };
+        if src_room_idx == dst_room_idx || neighbors[src_room_idx].contains(&dst_room_idx) {
+            continue;
+        }
+        neighbors[src_room_idx].push(dst_room_idx);
+        neighbors[dst_room_idx].push(src_room_idx);
+    }
+    neighbors
+}
+
+// Get the rooms reachable from `start` through neighboring rooms for which `in_region` holds, in
+// breadth-first order (beginning with `start` itself). This is shared with the map generator,
+// which grows and splits regions of rooms in the same way.
+pub(crate) fn get_connected_rooms(
+    neighbors: &[Vec<RoomGeometryRoomIdx>],
+    start: RoomGeometryRoomIdx,
+    in_region: impl Fn(RoomGeometryRoomIdx) -> bool,
+) -> Vec<RoomGeometryRoomIdx> {
+    let mut visited: HashSet<RoomGeometryRoomIdx> = HashSet::new();
+    visited.insert(start);
+    let mut rooms = vec![start];
+    let mut i = 0;
+    while i < rooms.len() {
+        for &n in &neighbors[rooms[i]] {
+            if in_region(n) && visited.insert(n) {
+                rooms.push(n);
+            }
+        }
+        i += 1;
+    }
+    rooms
+}
+
+fn get_room_tile_counts(game_data: &GameData) -> Vec<usize> {
+    game_data
+        .room_geometry
+        .iter()
+        .map(|room| room.map.iter().flatten().filter(|&&c| c == 1).count())
+        .collect()
+}
+
+fn get_area_sizes(map: &Map, tile_counts: &[usize]) -> [usize; NUM_AREAS] {
+    let mut sizes = [0; NUM_AREAS];
+    for (room_idx, &area) in map.area.iter().enumerate() {
+        if map.room_mask[room_idx] {
+            sizes[area] += tile_counts[room_idx];
+        }
+    }
+    sizes
+}
+
+// Get the rooms which would move along with a room leaving its area, so that the area doesn't get
+// split: the room itself, plus the pieces of the area that would be cut off by its removal (all
+// those next to the room except the largest). Returns None if the whole area would move.
+fn get_move_group(
+    map: &Map,
+    neighbors: &[Vec<RoomGeometryRoomIdx>],
+    tile_counts: &[usize],
+    room_idx: RoomGeometryRoomIdx,
+) -> Option<Vec<RoomGeometryRoomIdx>> {
+    let area = map.area[room_idx];
+    let in_area = |r: RoomGeometryRoomIdx| map.room_mask[r] && map.area[r] == area && r != room_idx;
+    let mut visited: HashSet<RoomGeometryRoomIdx> = HashSet::new();
+    let mut pieces: Vec<Vec<RoomGeometryRoomIdx>> = vec![];
+    for &start in &neighbors[room_idx] {
+        if !in_area(start) || visited.contains(&start) {
+            continue;
+        }
+        let piece = get_connected_rooms(neighbors, start, in_area);
+        visited.extend(piece.iter().copied());
+        pieces.push(piece);
+    }
+    let piece_size =
+        |piece: &Vec<RoomGeometryRoomIdx>| -> usize { piece.iter().map(|&r| tile_counts[r]).sum() };
+    let largest_idx = (0..pieces.len()).max_by_key(|&i| piece_size(&pieces[i]));
+    let mut group = vec![room_idx];
+    for (i, piece) in pieces.into_iter().enumerate() {
+        if Some(i) != largest_idx {
+            group.extend(piece);
+        }
+    }
+    let area_room_count = (0..map.rooms.len())
+        .filter(|&r| map.room_mask[r] && map.area[r] == area)
+        .count();
+    if group.len() == area_room_count {
+        return None;
+    }
+    Some(group)
+}
+
+fn get_area_permutations() -> Vec<Vec<AreaIdx>> {
+    let mut out: Vec<Vec<AreaIdx>> = vec![vec![]];
+    for _ in 0..NUM_AREAS {
+        let mut next = vec![];
+        for p in &out {
+            for a in 0..NUM_AREAS {
+                if !p.contains(&a) {
+                    let mut p1 = p.clone();
+                    p1.push(a);
+                    next.push(p1);
+                }
+            }
+        }
+        out = next;
+    }
+    out
+}
+
+// Relabel the areas to satisfy as many pins as possible, keeping the existing labels if no
+// relabeling satisfies more of them. The Landing Site's area is always labeled as Crateria.
+fn relabel_areas(map: &mut Map, pins: &[AreaPin], game_data: &GameData) {
+    let landing_site_idx = game_data.room_idx_by_id[&LANDING_SITE_ROOM_ID];
+    let landing_site_area = map.area[landing_site_idx];
+    let num_satisfied = |mapping: &[AreaIdx]| {
+        pins.iter()
+            .filter(|pin| {
+                let room_idx = game_data.room_idx_by_id[&pin.room_id];
+                map.room_mask[room_idx] && mapping[map.area[room_idx]] == pin.area
+            })
+            .count()
+    };
+    let mut best_mapping: Vec<AreaIdx> = (0..NUM_AREAS).collect();
+    best_mapping.swap(landing_site_area, CRATERIA_AREA);
+    let mut best_score = num_satisfied(&best_mapping);
+    for mapping in get_area_permutations() {
+        if mapping[landing_site_area] != CRATERIA_AREA {
+            continue;
+        }
+        let score = num_satisfied(&mapping);
+        if score > best_score {
+            best_mapping = mapping;
+            best_score = score;
+        }
+    }
+    for area in map.area.iter_mut() {
+        *area = best_mapping[*area];
+    }
+}
+
+// Move a group of rooms into the area (and subarea) of a room neighboring the group, if the map
+// stays valid. Only the destination area needs checking: the moved rooms must not overlap its
+// rooms, and it must still fit on its pause map. This is equivalent to `Map::validate` here, since
+// door connections don't depend on areas and the rooms around the Toilet are never moved.
+fn try_move_rooms(
+    map: &mut Map,
+    group: &[RoomGeometryRoomIdx],
+    neighbor_idx: RoomGeometryRoomIdx,
+    map_tiles: &MapTiles,
+) -> bool {
+    let dst_area = map.area[neighbor_idx];
+    let in_dst_area = |r: RoomGeometryRoomIdx| map.area[r] == dst_area && !group.contains(&r);
+    for &r in group {
+        for tile in &map_tiles.room_tiles[r] {
+            if map_tiles.occupants[tile].iter().any(|&o| in_dst_area(o)) {
+                return false;
+            }
+        }
+    }
+    let (mut min_x, mut min_y) = (usize::MAX, usize::MAX);
+    let (mut max_x, mut max_y) = (0, 0);
+    for r in (0..map.rooms.len()).filter(|&r| in_dst_area(r) || group.contains(&r)) {
+        for &(x, y) in &map_tiles.room_tiles[r] {
+            (min_x, min_y) = (min_x.min(x), min_y.min(y));
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+        }
+    }
+    if max_x - min_x + 1 > AREA_MAP_WIDTH || max_y - min_y + 1 > AREA_MAP_HEIGHT {
+        return false;
+    }
+    for &r in group {
+        map.area[r] = map.area[neighbor_idx];
+        map.subarea[r] = map.subarea[neighbor_idx];
+        map.subsubarea[r] = map.subsubarea[neighbor_idx];
+    }
+    true
+}
+
+fn apply_pins(
+    map: &mut Map,
+    pins: &[AreaPin],
+    neighbors: &[Vec<RoomGeometryRoomIdx>],
+    tile_counts: &[usize],
+    locked: &HashSet<RoomGeometryRoomIdx>,
+    map_tiles: &MapTiles,
+    game_data: &GameData,
+) -> Result<()> {
+    for pin in pins {
+        let room_idx = game_data.room_idx_by_id[&pin.room_id];
+        if !map.room_mask[room_idx] || map.area[room_idx] == pin.area {
+            continue;
+        }
+        let room_name = &game_data.room_geometry[room_idx].name;
+
+        // Find the shortest path to a room already in the area, not passing through other locked
+        // rooms (pinned rooms, the Landing Site, or the Toilet and the rooms it passes through):
+        let mut prev: Vec<Option<RoomGeometryRoomIdx>> = vec![None; map.rooms.len()];
+        let mut queue: VecDeque<RoomGeometryRoomIdx> = VecDeque::new();
+        let mut end: Option<RoomGeometryRoomIdx> = None;
+        prev[room_idx] = Some(room_idx);
+        queue.push_back(room_idx);
+        while let Some(r) = queue.pop_front() {
+            if map.area[r] == pin.area {
+                end = Some(r);
+                break;
+            }
+            if r != room_idx && locked.contains(&r) {
+                continue;
+            }
+            for &n in &neighbors[r] {
+                if prev[n].is_none() {
+                    prev[n] = Some(r);
+                    queue.push_back(n);
+                }
+            }
+        }
+        let Some(mut neighbor_idx) = end else {
+            bail!("Unable to move {room_name} into area {}", pin.area);
+        };
+
+        // Move the rooms along the path, starting from the end next to the area:
+        while map.area[room_idx] != pin.area {
+            let r = prev[neighbor_idx].unwrap();
+            let Some(group) = get_move_group(map, neighbors, tile_counts, r) else {
+                bail!("Unable to move {room_name} into area {}", pin.area);
+            };
+            if group.iter().any(|&x| x != room_idx && locked.contains(&x))
+                || !try_move_rooms(map, &group, neighbor_idx, map_tiles)
+            {
+                bail!("Unable to move {room_name} into area {}", pin.area);
+            }
+            neighbor_idx = r;
+        }
+    }
+    Ok(())
+}
+
+fn rebalance_areas(
+    map: &mut Map,
+    neighbors: &[Vec<RoomGeometryRoomIdx>],
+    tile_counts: &[usize],
+    locked: &HashSet<RoomGeometryRoomIdx>,
+    map_tiles: &MapTiles,
+    rng: &mut StdRng,
+) {
+    // Moves which failed, by group and destination area. These aren't tried again, since they
+    // typically fail because the group overlaps a room of the destination area, which doesn't
+    // change.
+    let mut failed_moves: HashSet<(Vec<RoomGeometryRoomIdx>, AreaIdx)> = HashSet::new();
+    loop {
+        let sizes = get_area_sizes(map, tile_counts);
+        let num_areas = sizes.iter().filter(|&&x| x > 0).count();
+        let avg = sizes.iter().sum::<usize>() as f32 / num_areas.max(1) as f32;
+        let max_size = avg * (1.0 + BALANCE_TOLERANCE);
+        let min_size = avg * (1.0 - BALANCE_TOLERANCE);
+
+        // Candidate moves of a room (with any rooms that it would cut off) into the area of a
+        // neighboring room, where the move brings the two areas closer in size:
+        let mut candidates: Vec<(Vec<RoomGeometryRoomIdx>, RoomGeometryRoomIdx)> = vec![];
+        for room_idx in 0..map.rooms.len() {
+            if !map.room_mask[room_idx] || locked.contains(&room_idx) {
+                continue;
+            }
+            let src_area = map.area[room_idx];
+            let mut dst_areas: Vec<AreaIdx> = vec![];
+            for &n in &neighbors[room_idx] {
+                let dst_area = map.area[n];
+                if dst_area == src_area || dst_areas.contains(&dst_area) {
+                    continue;
+                }
+                dst_areas.push(dst_area);
+                if sizes[src_area] as f32 <= max_size && sizes[dst_area] as f32 >= min_size {
+                    continue;
+                }
+                let Some(group) = get_move_group(map, neighbors, tile_counts, room_idx) else {
+                    break;
+                };
+                let group_size: usize = group.iter().map(|&r| tile_counts[r]).sum();
+                if sizes[src_area] > sizes[dst_area] + group_size
+                    && !group.iter().any(|r| locked.contains(r))
+                {
+                    candidates.push((group, n));
+                }
+            }
+        }
+        candidates.shuffle(rng);
+        candidates.sort_by_key(|(group, n)| {
+            sizes[map.area[*n]] as isize - sizes[map.area[group[0]]] as isize
+        });
+
+        let mut moved = false;
+        for (group, neighbor_idx) in candidates {
+            let dst_area = map.area[neighbor_idx];
+            if failed_moves.contains(&(group.clone(), dst_area)) {
+                continue;
+            }
+            if try_move_rooms(map, &group, neighbor_idx, map_tiles) {
+                moved = true;
+                break;
+            }
+            failed_moves.insert((group, dst_area));
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+pub fn balance_map_areas(
+    map: &mut Map,
+    pins: &[AreaPin],
+    seed: usize,
+    game_data: &GameData,
+) -> Result<()> {
+    let mut rng_seed = [0u8; 32];
+    rng_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = StdRng::from_seed(rng_seed);
+
+    relabel_areas(map, pins, game_data);
+
+    let neighbors = get_room_neighbors(map, game_data);
+    let tile_counts = get_room_tile_counts(game_data);
+    let map_tiles = get_map_tiles(map, game_data);
+    // The Toilet has to stay in the same area as the rooms it passes through, so none of them
+    // are moved, and neither are pinned rooms once they are in their area, nor the Landing Site:
+    let mut locked: HashSet<RoomGeometryRoomIdx> = HashSet::new();
+    locked.insert(game_data.room_idx_by_id[&LANDING_SITE_ROOM_ID]);
+    if map.room_mask[game_data.toilet_room_idx] {
+        locked.insert(game_data.toilet_room_idx);
+        locked.extend(Randomizer::get_toilet_intersections(map, game_data));
+    }
+    for pin in pins {
+        let room_idx = game_data.room_idx_by_id[&pin.room_id];
+        if locked.contains(&room_idx) && map.area[room_idx] != pin.area {
+            bail!(
+                "Unable to move {} into area {}",
+                game_data.room_geometry[room_idx].name,
+                pin.area
+            );
+        }
+    }
+    for pin in pins {
+        locked.insert(game_data.room_idx_by_id[&pin.room_id]);
+    }
+
+    apply_pins(
+        map,
+        pins,
+        &neighbors,
+        &tile_counts,
+        &locked,
+        &map_tiles,
+        game_data,
+    )?;
+    rebalance_areas(map, &neighbors, &tile_counts, &locked, &map_tiles, &mut rng);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map_repository::read_map_file, test_data};
+    use maprando_game::map_validation::MOTHER_BRAIN_ROOM_ID;
+
+    // A map with no doors, of which only the per-room fields are used:
+    fn make_map(area: Vec<AreaIdx>) -> Map {
+        let num_rooms = area.len();
+        Map {
+            room_mask: vec![true; num_rooms],
+            rooms: vec![(0, 0); num_rooms],
+            doors: vec![],
+            area,
+            subarea: vec![0; num_rooms],
+            subsubarea: vec![0; num_rooms],
+        }
+    }
+
+    fn make_neighbors(num_rooms: usize, edges: &[(usize, usize)]) -> Vec<Vec<RoomGeometryRoomIdx>> {
+        let mut neighbors = vec![vec![]; num_rooms];
+        for &(a, b) in edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+        neighbors
+    }
+
+    fn is_area_connected(map: &Map, neighbors: &[Vec<RoomGeometryRoomIdx>], area: AreaIdx) -> bool {
+        let rooms: Vec<RoomGeometryRoomIdx> = (0..map.rooms.len())
+            .filter(|&r| map.room_mask[r] && map.area[r] == area)
+            .collect();
+        let Some(&start) = rooms.first() else {
+            return true;
+        };
+        let connected = get_connected_rooms(neighbors, start, |r| {
+            map.room_mask[r] && map.area[r] == area
+        });
+        connected.len() == rooms.len()
+    }
+
+    #[test]
+    fn move_group_keeps_area_connected() {
+        // Area 0 is the chain 0-1-2-3 with room 2 also leading to room 4; area 1 is room 5 alone.
+        let map = make_map(vec![0, 0, 0, 0, 0, 1]);
+        let neighbors = make_neighbors(6, &[(0, 1), (1, 2), (2, 3), (2, 4), (0, 5)]);
+        let tile_counts = vec![1, 1, 1, 1, 1, 1];
+
+        // A room at the end of a chain moves alone:
+        assert_eq!(
+            get_move_group(&map, &neighbors, &tile_counts, 3),
+            Some(vec![3])
+        );
+        // Other rooms take along the smaller pieces which they would cut off:
+        let mut group = get_move_group(&map, &neighbors, &tile_counts, 1).unwrap();
+        group.sort();
+        assert_eq!(group, vec![0, 1]);
+        let mut group = get_move_group(&map, &neighbors, &tile_counts, 2).unwrap();
+        group.sort();
+        assert_eq!(group, vec![2, 3, 4]);
+        // The only room of an area can't move:
+        assert_eq!(get_move_group(&map, &neighbors, &tile_counts, 5), None);
+
+        // Larger pieces are weighted by their tiles rather than their number of rooms:
+        let tile_counts = vec![10, 1, 1, 1, 1, 1];
+        let mut group = get_move_group(&map, &neighbors, &tile_counts, 1).unwrap();
+        group.sort();
+        assert_eq!(group, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn relabel_satisfies_pins() {
+        // Room 0 is the Landing Site, and the others have room IDs 101, 102, etc.
+        let mut game_data = GameData::default();
+        game_data.room_idx_by_id.insert(LANDING_SITE_ROOM_ID, 0);
+        for room_idx in 1..NUM_AREAS {
+            game_data.room_idx_by_id.insert(100 + room_idx, room_idx);
+        }
+        let mut map = make_map((0..NUM_AREAS).collect());
+        let pins = [
+            AreaPin {
+                room_id: 101,
+                area: 2,
+            },
+            AreaPin {
+                room_id: 102,
+                area: 1,
+            },
+        ];
+        relabel_areas(&mut map, &pins, &game_data);
+        assert_eq!(map.area[1], 2);
+        assert_eq!(map.area[2], 1);
+        assert_eq!(map.area[0], CRATERIA_AREA);
+        // The areas are only relabeled, so each room is still in its own area:
+        let mut areas = map.area.clone();
+        areas.sort();
+        assert_eq!(areas, (0..NUM_AREAS).collect::<Vec<_>>());
+
+        // Without pins, the labels are unchanged:
+        let mut map = make_map((0..NUM_AREAS).collect());
+        relabel_areas(&mut map, &[], &game_data);
+        assert_eq!(map.area, (0..NUM_AREAS).collect::<Vec<_>>());
+
+        // The Landing Site isn't relabeled out of Crateria, even to satisfy a pin:
+        let mut map = make_map((0..NUM_AREAS).collect());
+        let pins = [AreaPin {
+            room_id: 101,
+            area: CRATERIA_AREA,
+        }];
+        relabel_areas(&mut map, &pins, &game_data);
+        assert_eq!(map.area, (0..NUM_AREAS).collect::<Vec<_>>());
+
+        // If the Landing Site starts out in another area, that area becomes Crateria:
+        let mut map = make_map(vec![3, 1, 2, 0, 4, 5]);
+        relabel_areas(&mut map, &[], &game_data);
+        assert_eq!(map.area[0], CRATERIA_AREA);
+        assert_eq!(map.area[3], 3);
+    }
+
+    #[test]
+    fn balance_vanilla_map() {
+        let game_data = test_data::game_data();
+        let stored_maps =
+            read_map_file(&test_data::workspace_path("../maps/vanilla/maps-0.avro")).unwrap();
+        let mut map = stored_maps[0].to_map(game_data).unwrap();
+        let neighbors = get_room_neighbors(&map, game_data);
+        let tile_counts = get_room_tile_counts(game_data);
+        let old_sizes = get_area_sizes(&map, &tile_counts);
+
+        // Pin Mother Brain Room into Maridia, which can be done by relabeling:
+        let pins = [AreaPin {
+            room_id: MOTHER_BRAIN_ROOM_ID,
+            area: 4,
+        }];
+        balance_map_areas(&mut map, &pins, 0, game_data).unwrap();
+
+        // The incremental checks of each move keep the map valid:
+        assert_eq!(map.validate(game_data), vec![]);
+        assert_eq!(map.area[game_data.room_idx_by_id[&MOTHER_BRAIN_ROOM_ID]], 4);
+        let landing_site_idx = game_data.room_idx_by_id[&LANDING_SITE_ROOM_ID];
+        assert_eq!(map.area[landing_site_idx], CRATERIA_AREA);
+        for area in 0..NUM_AREAS {
+            assert!(is_area_connected(&map, &neighbors, area), "area {area}");
+        }
+        // Each move brings two areas closer in size, reducing the sum of squared sizes:
+        let new_sizes = get_area_sizes(&map, &tile_counts);
+        let sum_squares = |sizes: &[usize; NUM_AREAS]| sizes.iter().map(|&x| x * x).sum::<usize>();
+        assert!(sum_squares(&new_sizes) < sum_squares(&old_sizes));
+    }
+}
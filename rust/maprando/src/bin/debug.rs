@@ -1,21 +1,139 @@
 // TODO: consider removing this later. It's not a bad lint but I don't want to deal with it now.
 #![allow(clippy::too_many_arguments)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::Parser;
 use hashbrown::HashMap;
 use maprando::{
     preset::PresetData,
     randomize::{DifficultyConfig, get_objectives},
-    settings::RandomizerSettings,
-    traverse::{LockedDoorData, apply_requirement},
+    settings::{ItemCount, RandomizerSettings},
+    tracker::{Tracker, TrackerData, TrackerProgress},
+    traverse::{
+        LockedDoorData, apply_requirement, explain::format_explanation,
+        item_sets::format_location_item_sets,
+    },
 };
 use maprando_game::{
-    Capacity, GameData, Item, NodeId, Requirement, RidleyStuck, RoomId,
+    Capacity, GameData, Item, Map, NodeId, Requirement, RidleyStuck, RoomId, StartLocation,
     TECH_ID_CAN_BE_EXTREMELY_PATIENT, TECH_ID_CAN_BE_PATIENT, TECH_ID_CAN_BE_VERY_PATIENT,
 };
 use maprando_logic::{GlobalState, Inventory, LocalState};
 use rand::SeedableRng;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Parser)]
+struct Args {
+    // Map to use for explaining why a node is unreachable, or for the item sets report. Without
+    // this, the boss fight scenarios are run instead.
+    #[arg(long)]
+    map: Option<PathBuf>,
+
+    #[arg(long)]
+    room_id: Option<RoomId>,
+
+    #[arg(long)]
+    node_id: Option<NodeId>,
+
+    // Collected items, e.g. "Morph,Missile,Missile,Bombs"
+    #[arg(long, value_delimiter = ',')]
+    items: Vec<String>,
+
+    // Flags which are set, e.g. "f_DefeatedKraid"
+    #[arg(long, value_delimiter = ',')]
+    flags: Vec<String>,
+
+    #[arg(long, default_value = "Hard")]
+    skill_preset: String,
+
+    // Explain why it's not possible to return from the node, rather than reach it.
+    #[arg(long)]
+    reverse: bool,
+
+    // Report the minimal item/flag combinations needed for each item location (or only those
+    // in the given room and node), instead of explaining why a node is unreachable.
+    #[arg(long)]
+    item_sets: bool,
+
+    #[arg(long, default_value_t = 16)]
+    max_sets: usize,
+
+    // File to write the item sets report to as JSON, in addition to printing it.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+// Set up a tracker for the given map, starting from the ship with no locked doors.
+fn build_tracker(
+    args: &Args,
+    map_path: &Path,
+    preset_data: &PresetData,
+    game_data: &GameData,
+) -> Result<Tracker> {
+    let map_str = std::fs::read_to_string(map_path)
+        .with_context(|| format!("Unable to read map file at {}", map_path.display()))?;
+    let map: Map = serde_json::from_str(&map_str)
+        .with_context(|| format!("Unable to parse map file at {}", map_path.display()))?;
+
+    let mut settings = preset_data.default_preset.clone();
+    settings.skill_assumption_settings = preset_data
+        .skill_presets
+        .iter()
+        .find(|p| p.preset.as_deref() == Some(args.skill_preset.as_str()))
+        .with_context(|| format!("Unknown skill preset: {}", args.skill_preset))?
+        .clone();
+    let difficulty = DifficultyConfig::new(
+        &settings.skill_assumption_settings,
+        game_data,
+        &preset_data.tech_by_difficulty["Implicit"],
+        &preset_data.notables_by_difficulty["Implicit"],
+    );
+    let mut rng = rand::rngs::StdRng::from_seed([0u8; 32]);
+    let objectives = get_objectives(&settings, Some(&map), game_data, &mut rng);
+    let tracker_data = TrackerData {
+        settings,
+        difficulty,
+        map,
+        objectives,
+        locked_doors: vec![],
+        start_location: StartLocation::ship(),
+    };
+    Tracker::new(tracker_data, game_data)
+}
+
+// Explain why a node is out of logic, starting from the ship with the given items and flags.
+fn run_explain(args: &Args, tracker: &Tracker, game_data: &GameData) -> Result<()> {
+    let room_id = args.room_id.context("--room-id is required with --map")?;
+    let node_id = args.node_id.context("--node-id is required with --map")?;
+    let mut progress = TrackerProgress {
+        flags: args.flags.clone(),
+        ..TrackerProgress::default()
+    };
+    for name in &args.items {
+        let item = Item::from_str(name).with_context(|| format!("Unknown item: {name}"))?;
+        match progress.items.iter_mut().find(|x| x.item == item) {
+            Some(x) => x.count += 1,
+            None => progress.items.push(ItemCount { item, count: 1 }),
+        }
+    }
+    let explanation = tracker.explain(&progress, room_id, node_id, args.reverse, game_data)?;
+    print!("{}", format_explanation(&explanation));
+    Ok(())
+}
+
+fn run_item_sets(args: &Args, tracker: &Tracker, game_data: &GameData) -> Result<()> {
+    let mut locations = tracker.get_item_sets(game_data, args.max_sets);
+    if let Some(room_id) = args.room_id {
+        locations.retain(|x| x.room_id == room_id && args.node_id.is_none_or(|n| x.node_id == n));
+    }
+    print!("{}", format_location_item_sets(&locations));
+    if let Some(output_path) = &args.output {
+        std::fs::write(output_path, serde_json::to_string_pretty(&locations)?)
+            .with_context(|| format!("Unable to write {}", output_path.display()))?;
+    }
+    Ok(())
+}
 
 fn run_scenario(
     proficiency: f32,
@@ -147,9 +265,18 @@ fn main() -> Result<()> {
     let notable_path = Path::new("data/notable_data.json");
     let presets_path = Path::new("data/presets");
 
+    let args = Args::parse();
     let game_data = GameData::load()?;
 
     let preset_data = PresetData::load(tech_path, notable_path, presets_path, &game_data)?;
+    if let Some(map_path) = &args.map {
+        let tracker = build_tracker(&args, map_path, &preset_data, &game_data)?;
+        if args.item_sets {
+            return run_item_sets(&args, &tracker, &game_data);
+        }
+        return run_explain(&args, &tracker, &game_data);
+    }
+
     let mut settings = preset_data.default_preset.clone();
     settings.skill_assumption_settings = preset_data.skill_presets.last().unwrap().clone();
     let difficulty = preset_data.difficulty_tiers.last().unwrap();
@@ -0,0 +1,421 @@
+// Compare the logic between two sm-json-data checkouts: added/removed/changed strats, changes to
+// the processed requirements of each link, added/removed tech and notables, and which item
+// locations became easier or harder, by the easiest skill preset under which they are bireachable
+// on the vanilla map with all items.
+//
+// Example: cargo run --bin logic-diff -- --old ../sm-json-data-old --new ../sm-json-data
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use hashbrown::{HashMap, HashSet};
+use json::JsonValue;
+use maprando::{
+    map_repository::MapRepository,
+    preset::PresetData,
+    randomize::{DifficultyConfig, get_objectives},
+    tracker::{Tracker, TrackerData},
+};
+use maprando_game::{
+    GameData, Map, NodeId, RoomId, StartLocation, requirement_render::RenderFormat,
+};
+use rand::SeedableRng;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+struct Args {
+    // sm-json-data checkout with the old revision of the logic
+    #[arg(long)]
+    old: PathBuf,
+
+    // sm-json-data checkout with the new revision of the logic
+    #[arg(long, default_value = "../sm-json-data")]
+    new: PathBuf,
+
+    #[arg(long, default_value = "../maps/vanilla")]
+    vanilla_map_repo: PathBuf,
+
+    // Skip comparing item location difficulty, which requires traversals for each skill preset.
+    #[arg(long)]
+    skip_locations: bool,
+
+    // File to write the results to, as JSON.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct StratChange {
+    room: String,
+    strat: String,
+    fields: Vec<String>, // Top-level strat properties which differ, e.g. "requires"
+}
+
+#[derive(Serialize)]
+struct LinkChange {
+    room: String,
+    from_node_id: NodeId,
+    to_node_id: NodeId,
+    strat_name: String,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LocationChange {
+    location: String,
+    old_preset: Option<String>, // Easiest skill preset where the location is bireachable
+    new_preset: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct LogicDiff {
+    tech_added: Vec<String>,
+    tech_removed: Vec<String>,
+    notables_added: Vec<String>,
+    notables_removed: Vec<String>,
+    strats_added: Vec<String>,
+    strats_removed: Vec<String>,
+    strats_changed: Vec<StratChange>,
+    links_changed: Vec<LinkChange>,
+    locations_easier: Vec<LocationChange>,
+    locations_harder: Vec<LocationChange>,
+}
+
+fn room_name(game_data: &GameData, room_id: RoomId) -> String {
+    game_data.room_json_map[&room_id]["name"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn sorted_difference(a: &HashSet<String>, b: &HashSet<String>) -> Vec<String> {
+    let mut out: Vec<String> = a.difference(b).cloned().collect();
+    out.sort();
+    out
+}
+
+fn get_tech_names(game_data: &GameData) -> HashSet<String> {
+    game_data
+        .tech_isv
+        .keys
+        .iter()
+        .map(|id| game_data.tech_names.get(id).cloned().unwrap_or_default())
+        .collect()
+}
+
+fn get_notable_names(game_data: &GameData) -> HashSet<String> {
+    game_data
+        .notable_info
+        .iter()
+        .map(|x| format!("{}: {}", room_name(game_data, x.room_id), x.name))
+        .collect()
+}
+
+// Strats by room ID and strat ID (or name, for strats without an ID).
+fn get_strats(game_data: &GameData) -> HashMap<(RoomId, String), &JsonValue> {
+    let mut out = HashMap::new();
+    for (&room_id, room_json) in &game_data.room_json_map {
+        for strat_json in room_json["strats"].members() {
+            let key = match strat_json["id"].as_usize() {
+                Some(id) => id.to_string(),
+                None => strat_json["name"].as_str().unwrap_or_default().to_string(),
+            };
+            out.insert((room_id, key), strat_json);
+        }
+    }
+    out
+}
+
+fn strat_label(game_data: &GameData, room_id: RoomId, strat_json: &JsonValue) -> String {
+    format!(
+        "{}: {} {}",
+        room_name(game_data, room_id),
+        strat_json["link"].dump(),
+        strat_json["name"].as_str().unwrap_or_default()
+    )
+}
+
+// Rendered requirements of the processed links, grouped by room, nodes, and strat. Tech, notables,
+// items, and flags are given by name, so that requirements can be compared between two `GameData`
+// instances whose indexes may differ.
+fn get_link_requirements(
+    game_data: &GameData,
+) -> HashMap<(RoomId, NodeId, NodeId, String), Vec<String>> {
+    let mut out: HashMap<(RoomId, NodeId, NodeId, String), Vec<String>> = HashMap::new();
+    for link in &game_data.links {
+        let from_key = &game_data.vertex_isv.keys[link.from_vertex_id];
+        let to_key = &game_data.vertex_isv.keys[link.to_vertex_id];
+        let key = (
+            from_key.room_id,
+            from_key.node_id,
+            to_key.node_id,
+            link.strat_name.clone(),
+        );
+        out.entry(key)
+            .or_default()
+            .push(link.requirement.render(game_data, RenderFormat::Text));
+    }
+    for reqs in out.values_mut() {
+        reqs.sort();
+        reqs.dedup();
+    }
+    out
+}
+
+// For each item location, the index of the easiest skill preset under which it is bireachable.
+fn get_location_tiers(
+    game_data: &GameData,
+    preset_data: &PresetData,
+    map: &Map,
+) -> Result<HashMap<(RoomId, NodeId), usize>> {
+    let mut out: HashMap<(RoomId, NodeId), usize> = HashMap::new();
+    for (i, skill) in preset_data.skill_presets.iter().enumerate() {
+        let mut settings = preset_data.default_preset.clone();
+        settings.skill_assumption_settings = skill.clone();
+        let difficulty = DifficultyConfig::new(
+            skill,
+            game_data,
+            &preset_data.tech_by_difficulty["Implicit"],
+            &preset_data.notables_by_difficulty["Implicit"],
+        );
+        let mut rng = rand::rngs::StdRng::from_seed([0u8; 32]);
+        let objectives = get_objectives(&settings, Some(map), game_data, &mut rng);
+        let tracker = Tracker::new(
+            TrackerData {
+                settings,
+                difficulty,
+                map: map.clone(),
+                objectives,
+                locked_doors: vec![],
+                start_location: StartLocation::ship(),
+            },
+            game_data,
+        )?;
+        for loc in tracker.get_full_status(game_data)?.item_locations {
+            if loc.bireachable {
+                out.entry((loc.room_id, loc.node_id)).or_insert(i);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn load_preset_data(game_data: &GameData) -> Result<PresetData> {
+    let tech_path = Path::new("data/tech_data.json");
+    let notable_path = Path::new("data/notable_data.json");
+    let presets_path = Path::new("data/presets");
+    PresetData::load(tech_path, notable_path, presets_path, game_data)
+}
+
+fn compare(old: &GameData, new: &GameData, args: &Args) -> Result<LogicDiff> {
+    let mut diff = LogicDiff::default();
+
+    let (old_tech, new_tech) = (get_tech_names(old), get_tech_names(new));
+    diff.tech_added = sorted_difference(&new_tech, &old_tech);
+    diff.tech_removed = sorted_difference(&old_tech, &new_tech);
+    let (old_notables, new_notables) = (get_notable_names(old), get_notable_names(new));
+    diff.notables_added = sorted_difference(&new_notables, &old_notables);
+    diff.notables_removed = sorted_difference(&old_notables, &new_notables);
+
+    let (old_strats, new_strats) = (get_strats(old), get_strats(new));
+    for (key, &new_json) in &new_strats {
+        let room_id = key.0;
+        let Some(&old_json) = old_strats.get(key) else {
+            diff.strats_added.push(strat_label(new, room_id, new_json));
+            continue;
+        };
+        let mut fields: Vec<String> = vec![];
+        for (field, value) in new_json.entries() {
+            if old_json[field] != *value {
+                fields.push(field.to_string());
+            }
+        }
+        for (field, _) in old_json.entries() {
+            if !new_json.has_key(field) {
+                fields.push(field.to_string());
+            }
+        }
+        if !fields.is_empty() {
+            fields.sort();
+            diff.strats_changed.push(StratChange {
+                room: room_name(new, room_id),
+                strat: new_json["name"].as_str().unwrap_or_default().to_string(),
+                fields,
+            });
+        }
+    }
+    for (key, &old_json) in &old_strats {
+        if !new_strats.contains_key(key) {
+            diff.strats_removed.push(strat_label(old, key.0, old_json));
+        }
+    }
+    diff.strats_added.sort();
+    diff.strats_removed.sort();
+    diff.strats_changed
+        .sort_by(|a, b| (&a.room, &a.strat).cmp(&(&b.room, &b.strat)));
+
+    let (old_links, new_links) = (get_link_requirements(old), get_link_requirements(new));
+    let keys: HashSet<&(RoomId, NodeId, NodeId, String)> =
+        old_links.keys().chain(new_links.keys()).collect();
+    let empty = vec![];
+    for key in keys {
+        let old_reqs = old_links.get(key).unwrap_or(&empty);
+        let new_reqs = new_links.get(key).unwrap_or(&empty);
+        if old_reqs != new_reqs {
+            diff.links_changed.push(LinkChange {
+                room: room_name(if new_reqs.is_empty() { old } else { new }, key.0),
+                from_node_id: key.1,
+                to_node_id: key.2,
+                strat_name: key.3.clone(),
+                old: old_reqs.clone(),
+                new: new_reqs.clone(),
+            });
+        }
+    }
+    diff.links_changed.sort_by(|a, b| {
+        (&a.room, a.from_node_id, a.to_node_id, &a.strat_name).cmp(&(
+            &b.room,
+            b.from_node_id,
+            b.to_node_id,
+            &b.strat_name,
+        ))
+    });
+
+    if !args.skip_locations {
+        let Some(map) = MapRepository::new("Vanilla", &args.vanilla_map_repo)?
+            .get_map_batch(0, new)?
+            .pop()
+        else {
+            bail!(
+                "No vanilla map found in {}",
+                args.vanilla_map_repo.display()
+            );
+        };
+        let old_preset_data = load_preset_data(old).context("Unable to load presets (old)")?;
+        let new_preset_data = load_preset_data(new).context("Unable to load presets (new)")?;
+        let old_tiers = get_location_tiers(old, &old_preset_data, &map)?;
+        let new_tiers = get_location_tiers(new, &new_preset_data, &map)?;
+        let preset_name = |tier: Option<&usize>| {
+            tier.map(|&i| {
+                new_preset_data.skill_presets[i]
+                    .preset
+                    .clone()
+                    .unwrap_or_default()
+            })
+        };
+        for &(room_id, node_id) in &new.item_locations {
+            let old_tier = old_tiers.get(&(room_id, node_id));
+            let new_tier = new_tiers.get(&(room_id, node_id));
+            if old_tier == new_tier {
+                continue;
+            }
+            let change = LocationChange {
+                location: format!(
+                    "{}: {}",
+                    room_name(new, room_id),
+                    new.node_json_map[&(room_id, node_id)]["name"]
+                        .as_str()
+                        .unwrap_or_default()
+                ),
+                old_preset: preset_name(old_tier),
+                new_preset: preset_name(new_tier),
+            };
+            // A location that is not bireachable under any preset counts as the hardest.
+            let old_rank = old_tier.copied().unwrap_or(usize::MAX);
+            let new_rank = new_tier.copied().unwrap_or(usize::MAX);
+            if new_rank < old_rank {
+                diff.locations_easier.push(change);
+            } else {
+                diff.locations_harder.push(change);
+            }
+        }
+    }
+    Ok(diff)
+}
+
+// Print a multi-line rendered requirement, marking its first line.
+fn print_requirement(marker: &str, req: &str) {
+    for (i, line) in req.lines().enumerate() {
+        let marker = if i == 0 { marker } else { " " };
+        println!("    {marker} {line}");
+    }
+}
+
+fn print_diff(diff: &LogicDiff) {
+    let print_list = |title: &str, items: &[String]| {
+        if !items.is_empty() {
+            println!("{title}:");
+            for x in items {
+                println!("  {x}");
+            }
+        }
+    };
+    print_list("Tech added", &diff.tech_added);
+    print_list("Tech removed", &diff.tech_removed);
+    print_list("Notables added", &diff.notables_added);
+    print_list("Notables removed", &diff.notables_removed);
+    print_list("Strats added", &diff.strats_added);
+    print_list("Strats removed", &diff.strats_removed);
+    if !diff.strats_changed.is_empty() {
+        println!("Strats changed:");
+        for x in &diff.strats_changed {
+            println!("  {}: {} ({})", x.room, x.strat, x.fields.join(", "));
+        }
+    }
+    if !diff.links_changed.is_empty() {
+        println!("Link requirements changed:");
+        for x in &diff.links_changed {
+            println!(
+                "  {}: {} -> {} {}",
+                x.room, x.from_node_id, x.to_node_id, x.strat_name
+            );
+            for r in &x.old {
+                if !x.new.contains(r) {
+                    print_requirement("-", r);
+                }
+            }
+            for r in &x.new {
+                if !x.old.contains(r) {
+                    print_requirement("+", r);
+                }
+            }
+        }
+    }
+    for (title, changes) in [
+        ("Locations easier", &diff.locations_easier),
+        ("Locations harder", &diff.locations_harder),
+    ] {
+        if !changes.is_empty() {
+            println!("{title}:");
+            for x in changes {
+                println!(
+                    "  {}: {} -> {}",
+                    x.location,
+                    x.old_preset.as_deref().unwrap_or("none"),
+                    x.new_preset.as_deref().unwrap_or("none")
+                );
+            }
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp_millis()
+        .init();
+
+    let args = Args::parse();
+    let old = GameData::load_with_sm_json_data(&args.old)
+        .with_context(|| format!("Unable to load logic from {}", args.old.display()))?;
+    let new = GameData::load_with_sm_json_data(&args.new)
+        .with_context(|| format!("Unable to load logic from {}", args.new.display()))?;
+
+    let diff = compare(&old, &new, &args)?;
+    print_diff(&diff);
+    if let Some(output_path) = &args.output {
+        std::fs::write(output_path, serde_json::to_string_pretty(&diff)?)
+            .with_context(|| format!("Unable to write {}", output_path.display()))?;
+    }
+    Ok(())
+}
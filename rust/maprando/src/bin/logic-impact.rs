@@ -0,0 +1,354 @@
+// Measure the marginal effect of each tech and notable relative to a base skill preset, to guide
+// the difficulty assignments in data/tech_data.json and data/notable_data.json. For each tech and
+// notable, its setting is flipped (enabled if the preset has it disabled, and vice versa) and we
+// compare against the preset:
+// - the number of links usable after `filter_links`,
+// - which item locations become bireachable or not, on the vanilla map with all items collected,
+// - optionally, the seed generation success rate over a sample of maps.
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use hashbrown::HashSet;
+use log::info;
+use maprando::{
+    map_repository::MapRepository,
+    preset::PresetData,
+    randomize::{
+        DifficultyConfig, Randomizer, filter_links, get_difficulty_tiers, get_objectives,
+        randomize_doors,
+    },
+    settings::RandomizerSettings,
+    tracker::{Tracker, TrackerData},
+};
+use maprando_game::{GameData, Map, NodeId, RoomId, StartLocation};
+use rand::{RngCore, SeedableRng};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, default_value = "Hard")]
+    skill_preset: String,
+
+    #[arg(long, default_value = "../maps/vanilla")]
+    vanilla_map_repo: PathBuf,
+
+    // Map repository to sample from when measuring the seed generation success rate.
+    #[arg(long, default_value = "../maps/v119-standard-avro")]
+    map_repo: PathBuf,
+
+    // Number of seeds to attempt per setting (0 to skip measuring the success rate). The same
+    // maps and seeds are used for each setting.
+    #[arg(long, default_value_t = 0)]
+    seed_samples: usize,
+
+    #[arg(long, default_value_t = 0)]
+    random_seed: u64,
+
+    // Only analyze tech and notables whose name contains this string.
+    #[arg(long)]
+    filter: Option<String>,
+
+    #[arg(long)]
+    skip_notables: bool,
+
+    // File to write the results to, as JSON.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+enum SettingKind {
+    Tech,
+    Notable,
+}
+
+#[derive(Serialize)]
+struct SettingImpact {
+    kind: SettingKind,
+    name: String,
+    enabled_in_preset: bool,
+    links: usize,
+    links_delta: i64,
+    locations_gained: Vec<String>,
+    locations_lost: Vec<String>,
+    seed_successes: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ImpactReport {
+    skill_preset: String,
+    links: usize,
+    bireachable_locations: usize,
+    seed_samples: usize,
+    seed_successes: Option<usize>,
+    settings: Vec<SettingImpact>,
+}
+
+struct SeedSample {
+    map: Map,
+    door_seed: usize,
+    item_seed: usize,
+    objective_seed: u64,
+}
+
+// Item locations which are bireachable on the given map with all items, starting from the ship.
+fn get_bireachable_locations(
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    map: &Map,
+    game_data: &GameData,
+) -> Result<HashSet<(RoomId, NodeId)>> {
+    let mut rng = rand::rngs::StdRng::from_seed([0u8; 32]);
+    let objectives = get_objectives(settings, Some(map), game_data, &mut rng);
+    let tracker_data = TrackerData {
+        settings: settings.clone(),
+        difficulty: difficulty.clone(),
+        map: map.clone(),
+        objectives,
+        locked_doors: vec![],
+        start_location: StartLocation::ship(),
+    };
+    let tracker = Tracker::new(tracker_data, game_data)?;
+    Ok(tracker
+        .get_full_status(game_data)?
+        .item_locations
+        .iter()
+        .filter(|x| x.bireachable)
+        .map(|x| (x.room_id, x.node_id))
+        .collect())
+}
+
+fn get_seed_samples(args: &Args, game_data: &GameData) -> Result<Vec<SeedSample>> {
+    if args.seed_samples == 0 {
+        return Ok(vec![]);
+    }
+    let map_repo = MapRepository::new("Sample", &args.map_repo)?;
+    let mut rng_seed = [0u8; 32];
+    rng_seed[..8].copy_from_slice(&args.random_seed.to_le_bytes());
+    let mut rng = rand::rngs::StdRng::from_seed(rng_seed);
+    let mut samples = vec![];
+    let mut map_batch: Vec<Map> = vec![];
+    for _ in 0..args.seed_samples {
+        if map_batch.is_empty() {
+            let map_seed = (rng.next_u64() & 0xFFFFFFFF) as usize;
+            map_batch = map_repo.get_map_batch(map_seed, game_data)?;
+        }
+        samples.push(SeedSample {
+            map: map_batch.pop().unwrap(),
+            door_seed: (rng.next_u64() & 0xFFFFFFFF) as usize,
+            item_seed: (rng.next_u64() & 0xFFFFFFFF) as usize,
+            objective_seed: rng.next_u64(),
+        });
+    }
+    Ok(samples)
+}
+
+fn count_seed_successes(
+    settings: &RandomizerSettings,
+    preset_data: &PresetData,
+    samples: &[SeedSample],
+    game_data: &GameData,
+) -> usize {
+    let difficulty_tiers = get_difficulty_tiers(
+        settings,
+        &preset_data.difficulty_tiers,
+        game_data,
+        &preset_data.tech_by_difficulty["Implicit"],
+        &preset_data.notables_by_difficulty["Implicit"],
+    );
+    let mut successes = 0;
+    for (i, sample) in samples.iter().enumerate() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(sample.objective_seed);
+        let objectives = get_objectives(settings, Some(&sample.map), game_data, &mut rng);
+        let locked_door_data = randomize_doors(
+            game_data,
+            &sample.map,
+            settings,
+            &objectives,
+            sample.door_seed,
+        );
+        let randomizer = Randomizer::new(
+            &sample.map,
+            &locked_door_data,
+            objectives,
+            settings,
+            &difficulty_tiers,
+            game_data,
+            &game_data.base_links_data,
+            &mut rng,
+        );
+        if randomizer.randomize(i, sample.item_seed, 1).is_ok() {
+            successes += 1;
+        }
+    }
+    successes
+}
+
+fn location_names(locations: &[(RoomId, NodeId)], game_data: &GameData) -> Vec<String> {
+    let mut names: Vec<String> = locations
+        .iter()
+        .map(|(room_id, node_id)| {
+            format!(
+                "{}: {}",
+                game_data.room_json_map[room_id]["name"]
+                    .as_str()
+                    .unwrap_or_default(),
+                game_data.node_json_map[&(*room_id, *node_id)]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+            )
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp_millis()
+        .init();
+
+    let args = Args::parse();
+    let game_data = GameData::load()?;
+    let tech_path = Path::new("data/tech_data.json");
+    let notable_path = Path::new("data/notable_data.json");
+    let presets_path = Path::new("data/presets");
+    let preset_data = PresetData::load(tech_path, notable_path, presets_path, &game_data)?;
+
+    let mut base_settings = preset_data.default_preset.clone();
+    base_settings.skill_assumption_settings = preset_data
+        .skill_presets
+        .iter()
+        .find(|p| p.preset.as_deref() == Some(args.skill_preset.as_str()))
+        .with_context(|| format!("Unknown skill preset: {}", args.skill_preset))?
+        .clone();
+    let get_difficulty = |settings: &RandomizerSettings| {
+        DifficultyConfig::new(
+            &settings.skill_assumption_settings,
+            &game_data,
+            &preset_data.tech_by_difficulty["Implicit"],
+            &preset_data.notables_by_difficulty["Implicit"],
+        )
+    };
+    let Some(vanilla_map) = MapRepository::new("Vanilla", &args.vanilla_map_repo)?
+        .get_map_batch(0, &game_data)?
+        .pop()
+    else {
+        bail!(
+            "No vanilla map found in {}",
+            args.vanilla_map_repo.display()
+        );
+    };
+    let samples = get_seed_samples(&args, &game_data)?;
+
+    let base_difficulty = get_difficulty(&base_settings);
+    let base_links = filter_links(&game_data.links, &game_data, &base_difficulty).len();
+    let base_locations =
+        get_bireachable_locations(&base_settings, &base_difficulty, &vanilla_map, &game_data)?;
+    let base_successes = if samples.is_empty() {
+        None
+    } else {
+        Some(count_seed_successes(
+            &base_settings,
+            &preset_data,
+            &samples,
+            &game_data,
+        ))
+    };
+    info!(
+        "{}: {} links, {} bireachable locations, {:?} successful seeds",
+        args.skill_preset,
+        base_links,
+        base_locations.len(),
+        base_successes
+    );
+
+    // Settings to analyze, as the skill settings with that one setting flipped:
+    let skill = &base_settings.skill_assumption_settings;
+    let mut variants: Vec<(SettingKind, String, bool, RandomizerSettings)> = vec![];
+    for (i, tech) in skill.tech_settings.iter().enumerate() {
+        let mut settings = base_settings.clone();
+        settings.skill_assumption_settings.tech_settings[i].enabled = !tech.enabled;
+        variants.push((SettingKind::Tech, tech.name.clone(), tech.enabled, settings));
+    }
+    if !args.skip_notables {
+        for (i, notable) in skill.notable_settings.iter().enumerate() {
+            let mut settings = base_settings.clone();
+            settings.skill_assumption_settings.notable_settings[i].enabled = !notable.enabled;
+            let name = format!("{}: {}", notable.room_name, notable.notable_name);
+            variants.push((SettingKind::Notable, name, notable.enabled, settings));
+        }
+    }
+    if let Some(filter) = &args.filter {
+        variants.retain(|(_, name, _, _)| name.contains(filter.as_str()));
+    }
+
+    let mut impacts = vec![];
+    for (kind, name, enabled_in_preset, settings) in variants {
+        let difficulty = get_difficulty(&settings);
+        let links = filter_links(&game_data.links, &game_data, &difficulty).len();
+        let locations =
+            get_bireachable_locations(&settings, &difficulty, &vanilla_map, &game_data)?;
+        let gained: Vec<(RoomId, NodeId)> =
+            locations.difference(&base_locations).copied().collect();
+        let lost: Vec<(RoomId, NodeId)> = base_locations.difference(&locations).copied().collect();
+        let seed_successes = if samples.is_empty() {
+            None
+        } else {
+            Some(count_seed_successes(
+                &settings,
+                &preset_data,
+                &samples,
+                &game_data,
+            ))
+        };
+        let impact = SettingImpact {
+            kind,
+            name,
+            enabled_in_preset,
+            links,
+            links_delta: links as i64 - base_links as i64,
+            locations_gained: location_names(&gained, &game_data),
+            locations_lost: location_names(&lost, &game_data),
+            seed_successes,
+        };
+        println!(
+            "{} ({}): links {:+}, locations +{} -{}{}",
+            impact.name,
+            if enabled_in_preset {
+                "disabled"
+            } else {
+                "enabled"
+            },
+            impact.links_delta,
+            impact.locations_gained.len(),
+            impact.locations_lost.len(),
+            match (impact.seed_successes, base_successes) {
+                (Some(n), Some(base)) => format!(", seeds {n}/{} (base {base})", samples.len()),
+                _ => String::new(),
+            }
+        );
+        for loc in &impact.locations_gained {
+            println!("  + {loc}");
+        }
+        for loc in &impact.locations_lost {
+            println!("  - {loc}");
+        }
+        impacts.push(impact);
+    }
+
+    if let Some(output_path) = &args.output {
+        let report = ImpactReport {
+            skill_preset: args.skill_preset.clone(),
+            links: base_links,
+            bireachable_locations: base_locations.len(),
+            seed_samples: samples.len(),
+            seed_successes: base_successes,
+            settings: impacts,
+        };
+        std::fs::write(output_path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Unable to write {}", output_path.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+// Generate map layouts with the native map generator, writing each as a JSON file in the
+// format of `Map`.
+//
+// Example: cargo run --release --bin map-gen -- --output-dir ../maps/generated --num-maps 10
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use log::info;
+use maprando::{
+    map_generator::{MapGenerator, MapGeneratorConfig},
+    map_repository::check_map,
+};
+use maprando_game::GameData;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long)]
+    output_dir: PathBuf,
+
+    #[arg(long, default_value_t = 1)]
+    num_maps: usize,
+
+    #[arg(long, default_value_t = 0)]
+    start_seed: usize,
+
+    // Layouts with fewer rooms are rejected.
+    #[arg(long)]
+    min_rooms: Option<usize>,
+
+    #[arg(long)]
+    max_attempts: Option<usize>,
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp_millis()
+        .init();
+    let args = Args::parse();
+    let game_data = GameData::load()?;
+
+    let mut config = MapGeneratorConfig::default();
+    if let Some(min_rooms) = args.min_rooms {
+        config.min_rooms = min_rooms;
+    }
+    if let Some(max_attempts) = args.max_attempts {
+        config.max_attempts = max_attempts;
+    }
+    let generator = MapGenerator::new(&game_data, config)?;
+
+    std::fs::create_dir_all(&args.output_dir)?;
+    for seed in args.start_seed..args.start_seed + args.num_maps {
+        let map = generator
+            .generate(seed)
+            .with_context(|| format!("Failed to generate map for seed {seed}"))?;
+        check_map(&map, &game_data)
+            .with_context(|| format!("Generated an invalid map for seed {seed}"))?;
+        let path = args.output_dir.join(format!("map-{seed}.json"));
+        std::fs::write(&path, serde_json::to_string(&map)?)?;
+        info!("Wrote {}", path.display());
+    }
+    Ok(())
+}
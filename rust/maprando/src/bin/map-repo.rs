@@ -0,0 +1,191 @@
+// Tools for managing map repositories (directories of Avro map batches with a manifest.json):
+//   from-json: convert a directory of map JSON files (as used by maprando-cli) into a repository
+//   to-json: dump the maps of a repository as JSON files
+//   merge: combine several repositories into one
+//   split: divide a repository into several smaller ones
+//
+// Example: cargo run --bin map-repo -- from-json --input ../maps/custom-json --output ../maps/custom --maps-per-file 100
+
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand};
+use log::info;
+use maprando::map_repository::{
+    MapManifest, MapsPerFile, StoredMap, load_map_json, load_map_schema, read_manifest,
+    read_map_file, write_manifest, write_map_file,
+};
+use maprando_game::GameData;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Args)]
+struct BatchArgs {
+    #[arg(long, default_value_t = 1000)]
+    maps_per_file: usize,
+
+    // List the number of maps in each file in the manifest, even if all files are full. This is
+    // done anyway if the maps don't divide evenly, with the leftover maps in a smaller final file.
+    #[arg(long)]
+    variable: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    FromJson {
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        output: PathBuf,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    ToJson {
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        output: PathBuf,
+    },
+    Merge {
+        #[arg(long, required = true)]
+        input: Vec<PathBuf>,
+        #[arg(long)]
+        output: PathBuf,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+    Split {
+        #[arg(long)]
+        input: PathBuf,
+        // Repositories are written to subdirectories "part-0", "part-1", etc.
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long)]
+        parts: usize,
+        #[command(flatten)]
+        batch: BatchArgs,
+    },
+}
+
+fn read_repository(path: &Path) -> Result<Vec<StoredMap>> {
+    let manifest = read_manifest(path)?;
+    let mut maps = vec![];
+    for filename in &manifest.files {
+        maps.extend(read_map_file(&path.join(filename))?);
+    }
+    info!("Read {} maps from {}", maps.len(), path.display());
+    Ok(maps)
+}
+
+fn write_repository(path: &Path, maps: &[StoredMap], batch: &BatchArgs) -> Result<()> {
+    if batch.maps_per_file == 0 {
+        bail!("--maps-per-file must be positive");
+    }
+    let schema = load_map_schema(Path::new("data/schema/map.avsc"))?;
+    std::fs::create_dir_all(path)?;
+    let mut files = vec![];
+    let mut counts = vec![];
+    for (i, chunk) in maps.chunks(batch.maps_per_file).enumerate() {
+        let filename = format!("maps-{i}.avro");
+        write_map_file(&path.join(&filename), &schema, chunk)?;
+        files.push(filename);
+        counts.push(chunk.len());
+    }
+    let maps_per_file = if batch.variable || counts.iter().any(|&n| n != batch.maps_per_file) {
+        MapsPerFile::Variable(counts)
+    } else {
+        MapsPerFile::Fixed(batch.maps_per_file)
+    };
+    let manifest = MapManifest {
+        maps_per_file,
+        files,
+    };
+    write_manifest(path, &manifest)?;
+    info!(
+        "Wrote {} maps in {} files to {}",
+        manifest.num_maps(),
+        manifest.files.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn from_json(input: &Path, output: &Path, batch: &BatchArgs) -> Result<()> {
+    let game_data = GameData::load()?;
+    let mut filenames: Vec<String> = vec![];
+    for entry in std::fs::read_dir(input)? {
+        let filename = entry?.file_name().into_string().unwrap();
+        if filename.ends_with(".json") && filename != "manifest.json" {
+            filenames.push(filename);
+        }
+    }
+    filenames.sort();
+    let mut maps = vec![];
+    for filename in &filenames {
+        let map = load_map_json(&input.join(filename), &game_data)?;
+        maps.push(StoredMap::from_map(&map, &game_data)?);
+    }
+    write_repository(output, &maps, batch)
+}
+
+fn to_json(input: &Path, output: &Path) -> Result<()> {
+    let game_data = GameData::load()?;
+    let manifest = read_manifest(input)?;
+    std::fs::create_dir_all(output)?;
+    for filename in &manifest.files {
+        let stem = filename.strip_suffix(".avro").unwrap_or(filename);
+        for (i, stored_map) in read_map_file(&input.join(filename))?.iter().enumerate() {
+            let map = stored_map.to_map(&game_data)?;
+            let path = output.join(format!("{stem}-{i}.json"));
+            std::fs::write(&path, serde_json::to_string(&map)?)?;
+        }
+    }
+    info!("Wrote {} maps to {}", manifest.num_maps(), output.display());
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp_millis()
+        .init();
+    let args = Args::parse();
+
+    match &args.command {
+        Command::FromJson {
+            input,
+            output,
+            batch,
+        } => from_json(input, output, batch),
+        Command::ToJson { input, output } => to_json(input, output),
+        Command::Merge {
+            input,
+            output,
+            batch,
+        } => {
+            let mut maps = vec![];
+            for path in input {
+                maps.extend(read_repository(path)?);
+            }
+            write_repository(output, &maps, batch)
+        }
+        Command::Split {
+            input,
+            output,
+            parts,
+            batch,
+        } => {
+            if *parts == 0 {
+                bail!("--parts must be positive");
+            }
+            let maps = read_repository(input)?;
+            let part_size = maps.len().div_ceil(*parts);
+            for (i, chunk) in maps.chunks(part_size.max(1)).enumerate() {
+                write_repository(&output.join(format!("part-{i}")), chunk, batch)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -1,15 +1,22 @@
 use anyhow::{Context, Result, bail};
 use clap::Parser;
+use hashbrown::HashSet;
 use log::info;
+use maprando::area_assignment::balance_map_areas;
 use maprando::customize::samus_sprite::{SamusSpriteCategory, SamusSpriteInfo};
 use maprando::customize::{ControllerConfig, CustomizeSettings, MusicSettings};
+use maprando::map_repository::{load_map_json, map_matches_filter};
+use maprando::mystery::{generate_mystery_settings, parse_mystery_weights};
 use maprando::patch::Rom;
 use maprando::patch::make_rom;
 use maprando::preset::PresetData;
 use maprando::randomize::{
-    Randomization, Randomizer, get_difficulty_tiers, get_objectives, randomize_doors,
+    Randomization, Randomizer, get_difficulty_tiers, get_objectives, order_map_areas,
+    randomize_doors, randomize_map_areas,
+};
+use maprando::settings::{
+    AreaAssignment, RandomizerSettings, StartLocationMode, decode_settings_code,
 };
-use maprando::settings::{RandomizerSettings, StartLocationMode};
 use maprando::spoiler_log::SpoilerLog;
 use maprando::spoiler_map;
 use maprando_game::{GameData, Map};
@@ -24,6 +31,12 @@ struct Args {
     #[arg(long)]
     preset: Option<String>,
 
+    #[arg(long)]
+    settings_code: Option<String>,
+
+    #[arg(long)]
+    mystery_weights: Option<PathBuf>,
+
     #[arg(long)]
     skill_preset: Option<String>,
 
@@ -54,19 +67,68 @@ struct Args {
     #[arg(long)]
     output_spoiler_log: Option<PathBuf>,
 
+    #[arg(long)]
+    output_settings: Option<PathBuf>,
+
     #[arg(long)]
     output_spoiler_map_explored: Option<PathBuf>,
 
     #[arg(long)]
     output_spoiler_map_outline: Option<PathBuf>,
 
+    #[arg(long)]
+    output_spoiler_map_svg: Option<PathBuf>,
+
+    #[arg(long)]
+    output_spoiler_route_animation: Option<PathBuf>,
+
+    #[arg(long)]
+    output_rom_map: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 4)]
+    rom_map_scale: u32,
+
     #[arg(long)]
     area_themed_palette: bool,
 }
 
-fn get_settings(args: &Args, preset_data: &PresetData) -> Result<RandomizerSettings> {
+fn get_settings(
+    args: &Args,
+    preset_data: &PresetData,
+    game_data: &GameData,
+) -> Result<RandomizerSettings> {
     let mut settings = preset_data.default_preset.clone();
 
+    if let Some(code) = &args.settings_code {
+        if args.preset.is_some() {
+            bail!("--preset cannot be combined with --settings-code");
+        }
+        settings = decode_settings_code(code, preset_data)
+            .context("Unable to decode settings code")?
+            .1;
+    }
+    if let Some(weights_path) = &args.mystery_weights {
+        if args.preset.is_some() || args.settings_code.is_some() {
+            bail!("--mystery-weights cannot be combined with --preset or --settings-code");
+        }
+        let weights_str = std::fs::read_to_string(weights_path).with_context(|| {
+            format!(
+                "Unable to read mystery weights at {}",
+                weights_path.display()
+            )
+        })?;
+        let weights = parse_mystery_weights(&weights_str).with_context(|| {
+            format!(
+                "Unable to parse mystery weights at {}",
+                weights_path.display()
+            )
+        })?;
+        let mut rng = match args.random_seed {
+            Some(s) => rand::rngs::StdRng::seed_from_u64(s as u64),
+            None => rand::rngs::StdRng::from_entropy(),
+        };
+        settings = generate_mystery_settings(&weights, preset_data, game_data, &mut rng)?;
+    }
     if let Some(preset) = &args.preset {
         let path = format!("data/presets/full-settings/{preset}.json");
         let s = std::fs::read_to_string(path)?;
@@ -88,6 +150,12 @@ fn get_settings(args: &Args, preset_data: &PresetData) -> Result<RandomizerSetti
         settings.quality_of_life_settings = serde_json::from_str(&s)?;
     }
     settings.other_settings.random_seed = args.random_seed;
+
+    let errors = settings.validate(preset_data, game_data);
+    if !errors.is_empty() {
+        let error_strs: Vec<String> = errors.iter().map(|x| x.to_string()).collect();
+        bail!("Invalid settings:\n{}", error_strs.join("\n"));
+    }
     Ok(settings)
 }
 
@@ -121,12 +189,11 @@ fn get_randomization(
         );
         None
     } else {
-        let map_string = std::fs::read_to_string(&args.map)
-            .with_context(|| format!("Unable to read map file at {}", args.map.display()))?;
-        Some(
-            serde_json::from_str(&map_string)
-                .with_context(|| format!("Unable to parse map file at {}", args.map.display()))?,
-        )
+        let map = load_map_json(&args.map, game_data)?;
+        if !map_matches_filter(&map, &settings.map_filter, game_data) {
+            bail!("Map {} does not match the map filter", args.map.display());
+        }
+        Some(map)
     };
     let root_seed = match args.random_seed {
         Some(s) => s,
@@ -150,21 +217,50 @@ fn get_randomization(
     };
     let max_map_attempts = max_attempts / max_attempts_per_map;
     let mut attempt_num = 0;
+    let mut rejected_maps: HashSet<usize> = HashSet::new();
     for _ in 0..max_map_attempts {
         let map_seed = (rng.next_u64() & 0xFFFFFFFF) as usize;
-        let map = match single_map {
+        let mut map = match single_map {
             Some(ref m) => m.clone(),
             None => {
                 let idx = map_seed % filenames.len();
+                if rejected_maps.contains(&idx) {
+                    continue;
+                }
                 let path = args.map.join(&filenames[idx]);
-                let map_string = std::fs::read_to_string(&path)
-                    .with_context(|| format!("Unable to read map file at {}", path.display()))?;
                 info!("[attempt {attempt_num}] Map: {}", path.display());
-                serde_json::from_str(&map_string).with_context(|| {
-                    format!("Unable to parse map file at {}", args.map.display())
-                })?
+                let map = load_map_json(&path, game_data)?;
+                if !map_matches_filter(&map, &settings.map_filter, game_data) {
+                    info!("Map {} does not match the map filter", path.display());
+                    rejected_maps.insert(idx);
+                    if rejected_maps.len() == filenames.len() {
+                        bail!(
+                            "Map pool exhausted: no maps in {} match the map filter",
+                            args.map.display()
+                        );
+                    }
+                    continue;
+                }
+                map
             }
         };
+        // Same as maprando-web:
+        match settings.other_settings.area_assignment {
+            AreaAssignment::Ordered => {
+                order_map_areas(&mut map, map_seed, game_data);
+            }
+            AreaAssignment::Random => {
+                randomize_map_areas(&mut map, map_seed);
+            }
+            AreaAssignment::Standard => {}
+            AreaAssignment::Balanced => {
+                let pins = &settings.other_settings.area_pins;
+                if let Err(e) = balance_map_areas(&mut map, pins, map_seed, game_data) {
+                    info!("Map seed={map_seed}: Area assignment failed: {e}");
+                    continue;
+                }
+            }
+        }
         let door_seed = match args.item_placement_seed {
             Some(s) => s,
             None => (rng.next_u64() & 0xFFFFFFFF) as usize,
@@ -221,7 +317,7 @@ fn main() -> Result<()> {
     let notable_path = Path::new("data/notable_data.json");
     let presets_path = Path::new("data/presets");
     let preset_data = PresetData::load(tech_path, notable_path, presets_path, &game_data)?;
-    let settings = get_settings(&args, &preset_data)?;
+    let settings = get_settings(&args, &preset_data, &game_data)?;
 
     // Perform randomization (map selection & item placement):
     let (randomization, spoiler_log) =
@@ -285,6 +381,12 @@ fn main() -> Result<()> {
         std::fs::write(output_spoiler_log_path, spoiler_str)?;
     }
 
+    if let Some(output_settings_path) = &args.output_settings {
+        println!("Writing settings to {}", output_settings_path.display());
+        let settings_str = serde_json::to_string_pretty(&settings)?;
+        std::fs::write(output_settings_path, settings_str)?;
+    }
+
     let spoiler_maps = spoiler_map::get_spoiler_map(&randomization, &game_data, &settings, true)?;
 
     if let Some(output_spoiler_map_explored_path) = &args.output_spoiler_map_explored {
@@ -305,5 +407,43 @@ fn main() -> Result<()> {
         std::fs::write(output_spoiler_map_outline_path, spoiler_map_outline)?;
     }
 
+    if let Some(output_spoiler_map_svg_path) = &args.output_spoiler_map_svg {
+        println!(
+            "Writing spoiler map (SVG) to {}",
+            output_spoiler_map_svg_path.display()
+        );
+        let spoiler_svg =
+            spoiler_map::get_spoiler_svg(&randomization, &spoiler_log, &game_data, &settings)?;
+        std::fs::write(output_spoiler_map_svg_path, spoiler_svg)?;
+    }
+
+    if let Some(output_route_animation_path) = &args.output_spoiler_route_animation {
+        println!(
+            "Writing spoiler route animation to {}",
+            output_route_animation_path.display()
+        );
+        let route_animation = spoiler_map::get_spoiler_route_animation(
+            &randomization,
+            &spoiler_log,
+            &game_data,
+            &settings,
+        )?;
+        std::fs::write(output_route_animation_path, route_animation)?;
+    }
+
+    if let Some(output_rom_map_path) = &args.output_rom_map {
+        println!(
+            "Writing map from ROM tiles to {}",
+            output_rom_map_path.display()
+        );
+        let rom_map = spoiler_map::get_rom_map_image(
+            &output_rom,
+            &randomization,
+            &game_data,
+            args.rom_map_scale,
+        )?;
+        rom_map.save(output_rom_map_path)?;
+    }
+
     Ok(())
 }
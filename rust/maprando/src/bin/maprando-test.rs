@@ -1,6 +1,7 @@
 use anyhow::{Context, Result, bail};
 use clap::Parser;
 use log::{error, info};
+use maprando::area_assignment::balance_map_areas;
 use maprando::customize::samus_sprite::SamusSpriteCategory;
 use maprando::customize::{ControllerConfig, CustomizeSettings, MusicSettings};
 use maprando::map_repository::MapRepository;
@@ -147,7 +148,8 @@ fn get_randomization(
         let door_seed = (rng.next_u64() & 0xFFFFFFFF) as usize;
 
         if map_batch.is_empty() {
-            map_batch = map_repo.get_map_batch(map_seed, game_data).unwrap();
+            map_batch =
+                map_repo.get_filtered_map_batch(map_seed, &settings.map_filter, game_data)?;
         }
 
         let mut map = map_batch.pop().unwrap();
@@ -159,6 +161,13 @@ fn get_randomization(
                 randomize_map_areas(&mut map, map_seed);
             }
             AreaAssignment::Standard => {}
+            AreaAssignment::Balanced => {
+                let pins = &settings.other_settings.area_pins;
+                if let Err(e) = balance_map_areas(&mut map, pins, map_seed, game_data) {
+                    info!("Map seed={map_seed}: Area assignment failed: {e}");
+                    continue;
+                }
+            }
         }
         let objectives = get_objectives(&settings, Some(&map), game_data, &mut rng);
         let locked_door_data = randomize_doors(game_data, &map, &settings, &objectives, door_seed);
@@ -302,6 +311,14 @@ fn perform_test_cycle(app: &TestAppData, cycle_count: usize) -> Result<()> {
     let spoiler_map_outline = spoiler_maps.outline.clone();
     std::fs::write(output_spoiler_map_outline_path, spoiler_map_outline)?;
 
+    // Map as rendered from the in-game tiles in the patched ROM, for comparing against the
+    // spoiler map:
+    let output_rom_map_path =
+        Path::join(&app.output_dir, format!("{output_file_prefix}-rom-map.png"));
+    info!("Writing ROM map to {}", output_rom_map_path.display());
+    let rom_map = spoiler_map::get_rom_map_image(&game_rom, &randomization, &app.game_data, 1)?;
+    rom_map.save(output_rom_map_path)?;
+
     Ok(())
 }
 
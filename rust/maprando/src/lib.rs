@@ -3,14 +3,24 @@
 // TODO: consider removing this later. It's not a bad lint but I don't want to deal with it now.
 #![allow(clippy::too_many_arguments)]
 
+pub mod area_assignment;
 pub mod customize;
 pub mod helpers;
+#[cfg(feature = "native")]
+pub mod map_generator;
+#[cfg(feature = "native")]
 pub mod map_repository;
+pub mod mystery;
 pub mod patch;
 pub mod preset;
 pub mod randomize;
+#[cfg(feature = "native")]
 pub mod seed_repository;
 pub mod settings;
 pub mod spoiler_log;
+#[cfg(feature = "native")]
 pub mod spoiler_map;
+#[cfg(test)]
+mod test_data;
+pub mod tracker;
 pub mod traverse;
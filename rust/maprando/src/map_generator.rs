@@ -0,0 +1,1142 @@
+// Native map layout generator. Rooms are attached one at a time to open doors of the layout,
+// growing outward from Landing Site. Sand/elevator doors (and both sides of the Toilet) must
+// always be connected, and every placed room part must stay strongly connected to the ship;
+// both are enforced by a bounded backtracking search after each placement. Doors that end up
+// without a partner become walls, and rooms that can't be fit are left out of the map (as on
+// Small maps). Areas, subareas and subsubareas are then assigned by balanced region growth.
+//
+// This is much slower and less polished than the map pools produced by the external pipeline,
+// but needs nothing besides the room geometry.
+
+use anyhow::{Result, bail};
+use hashbrown::HashMap;
+use log::{info, warn};
+use maprando_game::{
+    DoorPtrPair, GameData, Map, RoomGeometryRoomIdx, RoomId,
+    map_validation::{LANDING_SITE_ROOM_ID, MAP_GRID_SIZE, MOTHER_BRAIN_ROOM_ID},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+use crate::{area_assignment::get_connected_rooms, map_repository::check_map, patch::NUM_AREAS};
+
+const GRID_SIZE: i32 = MAP_GRID_SIZE as i32;
+// Maximum extent of the tiles of an area, so that it fits on the 64 x 32 pause map:
+const MAX_AREA_WIDTH: i32 = 60;
+const MAX_AREA_HEIGHT: i32 = 30;
+const MAX_AREA_ATTEMPTS: usize = 100;
+
+// Rooms which the Toilet may pass through, with the allowed x positions relative to the room
+// (same list as python/logic/tube.py).
+const TOILET_ROOMS: &[(&str, &[usize])] = &[
+    ("Amphitheatre", &[0, 1, 2, 3]),
+    ("Aqueduct", &[2, 4, 5]),
+    ("Below Spazer", &[1]),
+    ("Beta Power Bomb Room", &[0]),
+    ("Bowling Alley", &[2, 3, 4]),
+    ("Bubble Mountain", &[1]),
+    ("Bat Cave", &[0]),
+    ("Cathedral Entrance", &[1, 2]),
+    ("Cathedral", &[0, 1, 2]),
+    ("Construction Zone", &[0]),
+    ("Crab Hole", &[0]),
+    ("Crocomire Escape", &[1]),
+    ("Crumble Shaft", &[0]),
+    ("Double Chamber", &[0, 1, 2, 3]),
+    ("The Moat", &[0, 1]),
+    ("The Precious Room", &[0]),
+    ("Early Supers Room", &[0, 1, 2]),
+    ("East Ocean", &[0, 1, 2, 3, 4, 5, 6]),
+    ("Electric Death Room", &[0]),
+    ("Etecoon Energy Tank Room", &[0]),
+    ("Fast Pillars Setup Room", &[0]),
+    ("Fish Tank", &[1]),
+    ("Grapple Beam Room", &[0]),
+    ("Grapple Tutorial Room 2", &[0]),
+    ("Ice Beam Snake Room", &[0]),
+    ("Kassiuz Room", &[0]),
+    ("Kraid Eye Door Room", &[0]),
+    ("Kronic Boost Room", &[1]),
+    ("Landing Site", &[3, 5]),
+    ("Lava Dive Room", &[1]),
+    ("Lower Norfair Spring Ball Maze Room", &[1]),
+    ("Mama Turtle Room", &[1, 2]),
+    ("Metroid Room 2", &[0]),
+    ("Mickey Mouse Room", &[3]),
+    ("Mt. Everest", &[3, 5]),
+    ("Northwest Maridia Bug Room", &[0]),
+    ("Pants Room", &[0, 1]),
+    ("Parlor And Alcatraz", &[3]),
+    ("Pink Brinstar Hopper Room", &[0, 1]),
+    ("Pink Brinstar Power Bomb Room", &[0]),
+    ("Plasma Room", &[0, 1]),
+    ("Plasma Spark Room", &[1, 2]),
+    ("Post Crocomire Farming Room", &[1]),
+    ("Post Crocomire Jump Room", &[0, 1, 2, 3]),
+    ("Pseudo Plasma Spark Room", &[1, 2]),
+    ("Red Kihunter Shaft", &[0]),
+    ("Rinka Shaft", &[0]),
+    ("Screw Attack Room", &[0]),
+    ("Seaweed Room", &[0]),
+    ("Single Chamber", &[0]),
+    ("The Worst Room In The Game", &[0]),
+    ("Three Musketeers' Room", &[1]),
+    ("Tourian Escape Room 3", &[0, 1, 2, 3, 4, 5]),
+    ("Tourian Escape Room 4", &[0, 1, 2]),
+    ("Volcano Room", &[2]),
+    ("Wasteland", &[1]),
+    ("Warehouse Entrance", &[1]),
+    ("Watering Hole", &[0]),
+    ("West Cactus Alley Room", &[0]),
+    ("West Ocean", &[0, 1, 2, 5]),
+    ("Wrecked Ship Energy Tank Room", &[1, 2]),
+];
+
+#[derive(Clone, Debug)]
+pub struct MapGeneratorConfig {
+    pub max_attempts: usize,
+    // Layouts with fewer rooms than this are discarded:
+    pub min_rooms: usize,
+    // Number of valid placements tried at each step, of which the best is kept:
+    pub candidates_per_step: usize,
+    // Maximum number of placements explored by the backtracking search after each step:
+    pub search_budget: usize,
+    // Rooms which must be included in the layout:
+    pub required_room_ids: Vec<RoomId>,
+}
+
+impl Default for MapGeneratorConfig {
+    fn default() -> Self {
+        MapGeneratorConfig {
+            max_attempts: 50,
+            min_rooms: 200,
+            candidates_per_step: 8,
+            search_budget: 150,
+            required_room_ids: vec![LANDING_SITE_ROOM_ID, MOTHER_BRAIN_ROOM_ID],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+struct DoorInfo {
+    direction: Direction,
+    x: i32,
+    y: i32,
+    subtype: String,
+    ptr_pair: DoorPtrPair,
+    part: usize,
+    // Doors which can't be turned into walls (sand, elevators, and the Toilet):
+    must_connect: bool,
+}
+
+struct RoomInfo {
+    width: i32,
+    height: i32,
+    tiles: Vec<(i32, i32)>,
+    doors: Vec<DoorInfo>,
+    // Index of the room's first part in the global part numbering:
+    part_base: usize,
+}
+
+// (room index, door index)
+type DoorRef = (RoomGeometryRoomIdx, usize);
+
+// Position of the boundary between two tiles where a door connection is made: the left edge
+// of tile (x, y) if horizontal, or its top edge if vertical.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Slot {
+    x: i32,
+    y: i32,
+    vertical: bool,
+}
+
+// A group of rooms placed together at fixed relative positions: a single room, a cluster of
+// sand rooms whose transitions only work with their vanilla partners, or a room together with
+// the Toilet passing through it.
+#[derive(Clone)]
+struct Unit {
+    rooms: Vec<(RoomGeometryRoomIdx, i32, i32)>,
+    tiles: Vec<(i32, i32, RoomGeometryRoomIdx)>,
+    // Doors not connected within the unit, with the offset of their room:
+    doors: Vec<(DoorRef, i32, i32)>,
+    internal_conns: Vec<(DoorRef, DoorRef)>,
+}
+
+struct Placement {
+    unit_idx: usize,
+    x: i32,
+    y: i32,
+    // (new door, existing open door, slot):
+    matches: Vec<(DoorRef, DoorRef, Slot)>,
+    // (new door, slot, tile that it faces):
+    new_open: Vec<(DoorRef, Slot, (i32, i32))>,
+}
+
+#[derive(Clone)]
+struct LayoutState {
+    occupied: Vec<Option<RoomGeometryRoomIdx>>,
+    positions: Vec<Option<(i32, i32)>>,
+    // Doors which are not connected yet (including dead ones), by slot:
+    open_slots: HashMap<Slot, DoorRef>,
+    // Slots of unconnected doors facing each tile:
+    facing: HashMap<(i32, i32), Vec<Slot>>,
+    // Unconnected doors which may still get a connection:
+    open_doors: Vec<DoorRef>,
+    conns: Vec<(DoorRef, DoorRef)>,
+    remaining_units: Vec<usize>,
+}
+
+pub struct MapGenerator<'a> {
+    game_data: &'a GameData,
+    config: MapGeneratorConfig,
+    rooms: Vec<RoomInfo>,
+    num_parts: usize,
+    base_units: Vec<Unit>,
+    // Possible Toilet positions (host room, x and y relative to the host room):
+    toilet_positions: Vec<(RoomGeometryRoomIdx, i32, i32)>,
+    landing_site_idx: RoomGeometryRoomIdx,
+    required_room_idxs: Vec<RoomGeometryRoomIdx>,
+}
+
+fn slot(door: &DoorInfo, room_x: i32, room_y: i32) -> Slot {
+    let x = room_x + door.x;
+    let y = room_y + door.y;
+    match door.direction {
+        Direction::Left => Slot {
+            x,
+            y,
+            vertical: false,
+        },
+        Direction::Right => Slot {
+            x: x + 1,
+            y,
+            vertical: false,
+        },
+        Direction::Up => Slot {
+            x,
+            y,
+            vertical: true,
+        },
+        Direction::Down => Slot {
+            x,
+            y: y + 1,
+            vertical: true,
+        },
+    }
+}
+
+fn facing_tile(door: &DoorInfo, room_x: i32, room_y: i32) -> (i32, i32) {
+    let x = room_x + door.x;
+    let y = room_y + door.y;
+    match door.direction {
+        Direction::Left => (x - 1, y),
+        Direction::Right => (x + 1, y),
+        Direction::Up => (x, y - 1),
+        Direction::Down => (x, y + 1),
+    }
+}
+
+fn in_bounds((x, y): (i32, i32)) -> bool {
+    (0..GRID_SIZE).contains(&x) && (0..GRID_SIZE).contains(&y)
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+        Direction::Up => Direction::Down,
+        Direction::Down => Direction::Up,
+    }
+}
+
+fn is_compatible(a: &DoorInfo, b: &DoorInfo) -> bool {
+    if opposite(a.direction) != b.direction || a.subtype != b.subtype {
+        return false;
+    }
+    let (a_exit, a_entrance) = a.ptr_pair;
+    let (b_exit, b_entrance) = b.ptr_pair;
+    if a_exit.is_none() || a_entrance.is_none() || b_exit.is_none() || b_entrance.is_none() {
+        // These transitions only work with their vanilla partner:
+        return a_exit == b_entrance && a_entrance == b_exit;
+    }
+    true
+}
+
+impl LayoutState {
+    fn new(num_rooms: usize, num_units: usize) -> Self {
+        LayoutState {
+            occupied: vec![None; (GRID_SIZE * GRID_SIZE) as usize],
+            positions: vec![None; num_rooms],
+            open_slots: HashMap::new(),
+            facing: HashMap::new(),
+            open_doors: vec![],
+            conns: vec![],
+            remaining_units: (0..num_units).collect(),
+        }
+    }
+
+    fn get_occupant(&self, (x, y): (i32, i32)) -> Option<RoomGeometryRoomIdx> {
+        if in_bounds((x, y)) {
+            self.occupied[(y * GRID_SIZE + x) as usize]
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> MapGenerator<'a> {
+    pub fn new(game_data: &'a GameData, config: MapGeneratorConfig) -> Result<Self> {
+        let toilet_idx = game_data.toilet_room_idx;
+        let mut rooms: Vec<RoomInfo> = vec![];
+        let mut num_parts = 0;
+        for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+            let mut door_part: Vec<usize> = vec![0; room.doors.len()];
+            for (part_idx, part) in room.parts.iter().enumerate() {
+                for &door_idx in part {
+                    door_part[door_idx] = part_idx;
+                }
+            }
+            let mut doors = vec![];
+            for (door_idx, door) in room.doors.iter().enumerate() {
+                let direction = match door.direction.as_str() {
+                    "left" => Direction::Left,
+                    "right" => Direction::Right,
+                    "up" => Direction::Up,
+                    "down" => Direction::Down,
+                    d => bail!("Unrecognized door direction in {}: {}", room.name, d),
+                };
+                doors.push(DoorInfo {
+                    direction,
+                    x: door.x as i32,
+                    y: door.y as i32,
+                    subtype: door.subtype.clone(),
+                    ptr_pair: (door.exit_ptr, door.entrance_ptr),
+                    part: door_part[door_idx],
+                    must_connect: door.subtype != "normal" || room_idx == toilet_idx,
+                });
+            }
+            let mut tiles = vec![];
+            for (y, row) in room.map.iter().enumerate() {
+                for (x, &c) in row.iter().enumerate() {
+                    if c == 1 {
+                        tiles.push((x as i32, y as i32));
+                    }
+                }
+            }
+            rooms.push(RoomInfo {
+                width: room.map[0].len() as i32,
+                height: room.map.len() as i32,
+                tiles,
+                doors,
+                part_base: num_parts,
+            });
+            num_parts += room.parts.len();
+        }
+        let landing_site_idx = game_data.room_idx_by_id[&LANDING_SITE_ROOM_ID];
+        let mut required_room_idxs = vec![];
+        for room_id in &config.required_room_ids {
+            let Some(&room_idx) = game_data.room_idx_by_id.get(room_id) else {
+                bail!("Unknown required room ID {room_id}");
+            };
+            required_room_idxs.push(room_idx);
+        }
+
+        let mut generator = MapGenerator {
+            game_data,
+            config,
+            rooms,
+            num_parts,
+            base_units: vec![],
+            toilet_positions: vec![],
+            landing_site_idx,
+            required_room_idxs,
+        };
+        generator.base_units = generator.build_base_units()?;
+        generator.toilet_positions = generator.get_toilet_positions();
+        Ok(generator)
+    }
+
+    fn build_unit(&self, rooms: Vec<(RoomGeometryRoomIdx, i32, i32)>) -> Option<Unit> {
+        let mut tiles = vec![];
+        let mut slot_doors: HashMap<Slot, Vec<(DoorRef, i32, i32)>> = HashMap::new();
+        for &(room_idx, dx, dy) in &rooms {
+            let room = &self.rooms[room_idx];
+            for &(x, y) in &room.tiles {
+                tiles.push((dx + x, dy + y, room_idx));
+            }
+            for (door_idx, door) in room.doors.iter().enumerate() {
+                slot_doors.entry(slot(door, dx, dy)).or_default().push((
+                    (room_idx, door_idx),
+                    dx,
+                    dy,
+                ));
+            }
+        }
+        let mut doors = vec![];
+        let mut internal_conns = vec![];
+        for v in slot_doors.into_values() {
+            match v.as_slice() {
+                [d] => doors.push(*d),
+                [(a, _, _), (b, _, _)] => {
+                    if !is_compatible(self.door(*a), self.door(*b)) {
+                        return None;
+                    }
+                    internal_conns.push((*a, *b));
+                }
+                _ => return None,
+            }
+        }
+        // Keep the door order deterministic, for reproducibility from a seed:
+        doors.sort_by_key(|&(d, _, _)| d);
+        internal_conns.sort();
+        Some(Unit {
+            rooms,
+            tiles,
+            doors,
+            internal_conns,
+        })
+    }
+
+    // Each room is its own unit, except that sand rooms which can only connect to their vanilla
+    // partner are grouped together. The Toilet is left out, as it is only placed along with the
+    // room it passes through.
+    fn build_base_units(&self) -> Result<Vec<Unit>> {
+        let num_rooms = self.rooms.len();
+        let mut offsets: Vec<Option<(i32, i32)>> = vec![None; num_rooms];
+        let mut units = vec![];
+        for start_idx in 0..num_rooms {
+            if offsets[start_idx].is_some() || start_idx == self.game_data.toilet_room_idx {
+                continue;
+            }
+            offsets[start_idx] = Some((0, 0));
+            let mut unit_rooms = vec![(start_idx, 0, 0)];
+            let mut stack = vec![start_idx];
+            while let Some(a) = stack.pop() {
+                let (ax, ay) = offsets[a].unwrap();
+                for door_a in &self.rooms[a].doors {
+                    if door_a.ptr_pair.0.is_some() && door_a.ptr_pair.1.is_some() {
+                        continue;
+                    }
+                    for (b, room_b) in self.rooms.iter().enumerate() {
+                        for door_b in &room_b.doors {
+                            if b == a || !is_compatible(door_a, door_b) {
+                                continue;
+                            }
+                            let sa = slot(door_a, ax, ay);
+                            let sb = slot(door_b, 0, 0);
+                            let offset = (sa.x - sb.x, sa.y - sb.y);
+                            match offsets[b] {
+                                Some(o) if o != offset => {
+                                    bail!(
+                                        "Inconsistent relative position of {} and {}",
+                                        self.game_data.room_geometry[a].name,
+                                        self.game_data.room_geometry[b].name
+                                    );
+                                }
+                                Some(_) => {}
+                                None => {
+                                    offsets[b] = Some(offset);
+                                    unit_rooms.push((b, offset.0, offset.1));
+                                    stack.push(b);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            let Some(unit) = self.build_unit(unit_rooms) else {
+                bail!(
+                    "Inconsistent doors in room group containing {}",
+                    self.game_data.room_geometry[start_idx].name
+                );
+            };
+            units.push(unit);
+        }
+        Ok(units)
+    }
+
+    // Positions where the Toilet would pass through exactly one room, at an allowed column,
+    // without the top and bottom of the Toilet intersecting it.
+    fn get_toilet_positions(&self) -> Vec<(RoomGeometryRoomIdx, i32, i32)> {
+        let mut out = vec![];
+        for &(name, allowed_x) in TOILET_ROOMS {
+            let Some(room_idx) = self
+                .game_data
+                .room_geometry
+                .iter()
+                .position(|r| r.name == name)
+            else {
+                continue;
+            };
+            let room_map = &self.game_data.room_geometry[room_idx].map;
+            let height = room_map.len() as i32;
+            for &x in allowed_x {
+                for y in -7..(height - 2) {
+                    let mut good = false;
+                    let mut bad = false;
+                    for i in 0..10 {
+                        let y1 = y + i;
+                        if y1 < 0 || y1 >= height || room_map[y1 as usize][x] != 1 {
+                            continue;
+                        }
+                        if (2..8).contains(&i) {
+                            good = true;
+                        } else {
+                            bad = true;
+                        }
+                    }
+                    if good && !bad {
+                        out.push((room_idx, x as i32, y));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn door(&self, (room_idx, door_idx): DoorRef) -> &DoorInfo {
+        &self.rooms[room_idx].doors[door_idx]
+    }
+
+    fn get_room_name(&self, room_idx: RoomGeometryRoomIdx) -> &str {
+        &self.game_data.room_geometry[room_idx].name
+    }
+
+    // Returns the units for one attempt, with the Toilet merged into the unit of a randomly
+    // chosen room that it passes through. Rows of the Toilet not covered by that room are
+    // reserved, so that no other room can intersect the Toilet.
+    fn get_units<R: Rng>(&self, rng: &mut R) -> Vec<Unit> {
+        let mut units = self.base_units.clone();
+        let toilet_idx = self.game_data.toilet_room_idx;
+        let Some(&(host_idx, tx, ty)) = self.toilet_positions.choose(rng) else {
+            return units;
+        };
+        let unit_idx = units
+            .iter()
+            .position(|u| u.rooms.iter().any(|&(r, _, _)| r == host_idx))
+            .unwrap();
+        let &(_, hx, hy) = units[unit_idx]
+            .rooms
+            .iter()
+            .find(|&&(r, _, _)| r == host_idx)
+            .unwrap();
+        let (x, y) = (hx + tx, hy + ty);
+        let mut rooms = units[unit_idx].rooms.clone();
+        rooms.push((toilet_idx, x, y));
+        let Some(mut unit) = self.build_unit(rooms) else {
+            return units;
+        };
+        for i in 0..10 {
+            let t = (x, y + i);
+            match unit
+                .tiles
+                .iter()
+                .filter(|&&(x1, y1, _)| (x1, y1) == t)
+                .count()
+            {
+                0 => unit.tiles.push((t.0, t.1, toilet_idx)),
+                1 => {}
+                _ => return units,
+            }
+        }
+        units[unit_idx] = unit;
+        units
+    }
+
+    fn try_place(
+        &self,
+        units: &[Unit],
+        state: &LayoutState,
+        unit_idx: usize,
+        x: i32,
+        y: i32,
+    ) -> Option<Placement> {
+        let unit = &units[unit_idx];
+        for &(room_idx, dx, dy) in &unit.rooms {
+            let room = &self.rooms[room_idx];
+            let (rx, ry) = (x + dx, y + dy);
+            if rx < 0 || ry < 0 || rx + room.width > GRID_SIZE || ry + room.height > GRID_SIZE {
+                return None;
+            }
+        }
+        for &(tx, ty, _) in &unit.tiles {
+            if state.get_occupant((x + tx, y + ty)).is_some() {
+                return None;
+            }
+        }
+        let covers = |t: (i32, i32)| unit.tiles.iter().any(|&(tx, ty, _)| (x + tx, y + ty) == t);
+        let mut matches = vec![];
+        let mut new_open = vec![];
+        for &(door_ref, dx, dy) in &unit.doors {
+            let door = self.door(door_ref);
+            let (rx, ry) = (x + dx, y + dy);
+            let s = slot(door, rx, ry);
+            if let Some(&other_ref) = state.open_slots.get(&s) {
+                if !is_compatible(door, self.door(other_ref)) {
+                    return None;
+                }
+                matches.push((door_ref, other_ref, s));
+            } else {
+                let f = facing_tile(door, rx, ry);
+                if in_bounds(f) {
+                    if state.get_occupant(f).is_some() || covers(f) {
+                        return None;
+                    }
+                } else if door.must_connect {
+                    return None;
+                }
+                new_open.push((door_ref, s, f));
+            }
+        }
+        // Unconnected doors facing the new tiles have to be connected to the unit:
+        for &(tx, ty, _) in &unit.tiles {
+            if let Some(slots) = state.facing.get(&(x + tx, y + ty)) {
+                for s in slots {
+                    if !matches.iter().any(|m| m.2 == *s) {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(Placement {
+            unit_idx,
+            x,
+            y,
+            matches,
+            new_open,
+        })
+    }
+
+    fn place(&self, units: &[Unit], state: &mut LayoutState, p: &Placement) {
+        let unit = &units[p.unit_idx];
+        for &(room_idx, dx, dy) in &unit.rooms {
+            state.positions[room_idx] = Some((p.x + dx, p.y + dy));
+        }
+        for &(tx, ty, room_idx) in &unit.tiles {
+            state.occupied[((p.y + ty) * GRID_SIZE + p.x + tx) as usize] = Some(room_idx);
+        }
+        for &(door_ref, other_ref, s) in &p.matches {
+            state.conns.push((door_ref, other_ref));
+            state.open_slots.remove(&s);
+            let (ox, oy) = state.positions[other_ref.0].unwrap();
+            let f = facing_tile(self.door(other_ref), ox, oy);
+            if let Some(slots) = state.facing.get_mut(&f) {
+                slots.retain(|&s1| s1 != s);
+            }
+            state.open_doors.retain(|&d| d != other_ref);
+        }
+        state.conns.extend(unit.internal_conns.iter().copied());
+        for &(door_ref, s, f) in &p.new_open {
+            state.open_slots.insert(s, door_ref);
+            state.facing.entry(f).or_default().push(s);
+            state.open_doors.push(door_ref);
+        }
+        state.remaining_units.retain(|&u| u != p.unit_idx);
+    }
+
+    // Valid placements of remaining units connecting to the given open door.
+    fn get_candidates<R: Rng>(
+        &self,
+        units: &[Unit],
+        state: &LayoutState,
+        open_door: DoorRef,
+        limit: usize,
+        rng: &mut R,
+    ) -> Vec<Placement> {
+        let door = self.door(open_door);
+        let (rx, ry) = state.positions[open_door.0].unwrap();
+        let s = slot(door, rx, ry);
+        let mut order: Vec<usize> = state.remaining_units.clone();
+        if state.open_doors.len() < 6 {
+            // With few open doors left, prefer units with many doors to keep the layout growing:
+            let mut keyed: Vec<(f32, usize)> = order
+                .iter()
+                .map(|&u| (rng.r#gen::<f32>() * units[u].doors.len() as f32, u))
+                .collect();
+            keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+            order = keyed.into_iter().map(|(_, u)| u).collect();
+        } else {
+            order.shuffle(rng);
+        }
+        // Try required rooms first, as otherwise they are easily left out:
+        order.sort_by_key(|&u| {
+            !units[u]
+                .rooms
+                .iter()
+                .any(|(r, _, _)| self.required_room_idxs.contains(r))
+        });
+        let mut out = vec![];
+        for unit_idx in order {
+            for &(door_ref, dx, dy) in &units[unit_idx].doors {
+                let d = self.door(door_ref);
+                if !is_compatible(d, door) {
+                    continue;
+                }
+                let s0 = slot(d, dx, dy);
+                if let Some(p) = self.try_place(units, state, unit_idx, s.x - s0.x, s.y - s0.y) {
+                    out.push(p);
+                    if out.len() >= limit {
+                        return out;
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    // Part graph of the layout: nodes are room parts, with edges for connections within rooms
+    // and through doors (sand only going downward).
+    fn get_part_graph(&self, state: &LayoutState) -> Vec<Vec<usize>> {
+        let mut adj: Vec<Vec<usize>> = vec![vec![]; self.num_parts];
+        for (room_idx, room) in self.game_data.room_geometry.iter().enumerate() {
+            if state.positions[room_idx].is_none() {
+                continue;
+            }
+            let base = self.rooms[room_idx].part_base;
+            for &(a, b) in &room.durable_part_connections {
+                adj[base + a].push(base + b);
+                adj[base + b].push(base + a);
+            }
+            for &(a, b) in &room.transient_part_connections {
+                adj[base + a].push(base + b);
+            }
+        }
+        for &(a, b) in &state.conns {
+            let door_a = self.door(a);
+            let pa = self.rooms[a.0].part_base + door_a.part;
+            let pb = self.rooms[b.0].part_base + self.door(b).part;
+            if door_a.subtype == "sand" {
+                if door_a.direction == Direction::Down {
+                    adj[pa].push(pb);
+                } else {
+                    adj[pb].push(pa);
+                }
+            } else {
+                adj[pa].push(pb);
+                adj[pb].push(pa);
+            }
+        }
+        adj
+    }
+
+    // Which parts can both be reached from and return to the ship.
+    fn get_ship_component(&self, state: &LayoutState) -> Vec<bool> {
+        let adj = self.get_part_graph(state);
+        let mut radj: Vec<Vec<usize>> = vec![vec![]; self.num_parts];
+        for (a, v) in adj.iter().enumerate() {
+            for &b in v {
+                radj[b].push(a);
+            }
+        }
+        let start = self.rooms[self.landing_site_idx].part_base;
+        let reach = |adj: &Vec<Vec<usize>>| -> Vec<bool> {
+            let mut seen = vec![false; self.num_parts];
+            seen[start] = true;
+            let mut stack = vec![start];
+            while let Some(u) = stack.pop() {
+                for &v in &adj[u] {
+                    if !seen[v] {
+                        seen[v] = true;
+                        stack.push(v);
+                    }
+                }
+            }
+            seen
+        };
+        let forward = reach(&adj);
+        let backward = reach(&radj);
+        forward.iter().zip(backward).map(|(&a, b)| a && b).collect()
+    }
+
+    // Extend the layout until all sand/elevator/Toilet doors are connected and every placed part
+    // is strongly connected to the ship, using depth-first search with a budget on the number of
+    // placements tried.
+    fn satisfy<R: Rng>(
+        &self,
+        units: &[Unit],
+        state: LayoutState,
+        counter: &mut usize,
+        rng: &mut R,
+    ) -> Option<LayoutState> {
+        let target = if let Some(&d) = state
+            .open_doors
+            .iter()
+            .find(|&&d| self.door(d).must_connect)
+        {
+            d
+        } else {
+            let component = self.get_ship_component(&state);
+            let in_component = |room_idx: RoomGeometryRoomIdx, part: usize| {
+                component[self.rooms[room_idx].part_base + part]
+            };
+            let detached = (0..self.rooms.len()).any(|r| {
+                state.positions[r].is_some()
+                    && (0..self.game_data.room_geometry[r].parts.len()).any(|p| !in_component(r, p))
+            });
+            if !detached {
+                return Some(state);
+            }
+            state
+                .open_doors
+                .iter()
+                .copied()
+                .find(|&(r, d)| !in_component(r, self.rooms[r].doors[d].part))?
+        };
+        let mut candidates: Vec<(f32, Placement)> = self
+            .get_candidates(units, &state, target, usize::MAX, rng)
+            .into_iter()
+            .map(|p| (p.matches.len() as f32 + rng.r#gen::<f32>(), p))
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, p) in candidates {
+            *counter += 1;
+            if *counter > self.config.search_budget {
+                return None;
+            }
+            let mut new_state = state.clone();
+            self.place(units, &mut new_state, &p);
+            if let Some(s) = self.satisfy(units, new_state, counter, rng) {
+                return Some(s);
+            }
+        }
+        None
+    }
+
+    fn generate_layout<R: Rng>(&self, units: &[Unit], rng: &mut R) -> Option<LayoutState> {
+        let mut state = LayoutState::new(self.rooms.len(), units.len());
+        let start_unit = units
+            .iter()
+            .position(|u| u.rooms.iter().any(|&(r, _, _)| r == self.landing_site_idx))
+            .unwrap();
+        let p = self.try_place(
+            units,
+            &state,
+            start_unit,
+            rng.gen_range(20..46),
+            rng.gen_range(10..31),
+        )?;
+        self.place(units, &mut state, &p);
+        state = self.satisfy(units, state, &mut 0, rng)?;
+
+        while !state.remaining_units.is_empty() && !state.open_doors.is_empty() {
+            let open_door = *state.open_doors.choose(rng).unwrap();
+            let candidates = self.get_candidates(
+                units,
+                &state,
+                open_door,
+                self.config.candidates_per_step,
+                rng,
+            );
+            // While few doors are open, prefer units which leave more doors open:
+            let few_open = state.open_doors.len() < 10;
+            let mut best: Option<(f32, LayoutState)> = None;
+            for p in candidates {
+                let num_matches = p.matches.len() as f32;
+                let num_doors = units[p.unit_idx].doors.len() as f32;
+                let mut score = num_matches + rng.r#gen::<f32>();
+                if few_open {
+                    score += 0.5 * (num_doors - 2.0 * num_matches);
+                }
+                if best.as_ref().is_some_and(|(s, _)| *s >= score) {
+                    continue;
+                }
+                let mut new_state = state.clone();
+                self.place(units, &mut new_state, &p);
+                if let Some(s) = self.satisfy(units, new_state, &mut 0, rng) {
+                    best = Some((score, s));
+                }
+            }
+            match best {
+                Some((_, s)) => state = s,
+                // No way to connect this door, so it will become a wall:
+                None => state.open_doors.retain(|&d| d != open_door),
+            }
+        }
+        Some(state)
+    }
+
+    // Split the rooms into areas by growing regions from random seed rooms, always extending
+    // the smallest region (by number of tiles). Landing Site is the seed of area 0.
+    fn assign_areas<R: Rng>(
+        &self,
+        adj: &[Vec<RoomGeometryRoomIdx>],
+        placed: &[RoomGeometryRoomIdx],
+        rng: &mut R,
+    ) -> Option<Vec<usize>> {
+        let num_rooms = self.rooms.len();
+        let mut area: Vec<Option<usize>> = vec![None; num_rooms];
+        let mut size = [0usize; NUM_AREAS];
+        let mut seeds = vec![self.landing_site_idx];
+        let mut others: Vec<RoomGeometryRoomIdx> = placed
+            .iter()
+            .copied()
+            .filter(|&r| r != self.landing_site_idx)
+            .collect();
+        others.shuffle(rng);
+        seeds.extend(others.into_iter().take(NUM_AREAS - 1));
+        if seeds.len() < NUM_AREAS {
+            return None;
+        }
+        for (a, &r) in seeds.iter().enumerate() {
+            area[r] = Some(a);
+            size[a] += self.rooms[r].tiles.len();
+        }
+        loop {
+            let mut order: Vec<usize> = (0..NUM_AREAS).collect();
+            order.sort_by_key(|&a| size[a]);
+            let mut extended = false;
+            for a in order {
+                let frontier: Vec<RoomGeometryRoomIdx> = placed
+                    .iter()
+                    .copied()
+                    .filter(|&r| area[r].is_none() && adj[r].iter().any(|&s| area[s] == Some(a)))
+                    .collect();
+                if let Some(&r) = frontier.choose(rng) {
+                    area[r] = Some(a);
+                    size[a] += self.rooms[r].tiles.len();
+                    extended = true;
+                    break;
+                }
+            }
+            if !extended {
+                break;
+            }
+        }
+        placed
+            .iter()
+            .map(|&r| area[r])
+            .collect::<Option<Vec<_>>>()?;
+        Some(area.into_iter().map(|a| a.unwrap_or(0)).collect())
+    }
+
+    // Split a set of rooms in two halves by number of tiles, taking rooms in breadth-first
+    // order from `start`, so that the first half is connected.
+    fn split_region(
+        &self,
+        adj: &[Vec<RoomGeometryRoomIdx>],
+        region: &[RoomGeometryRoomIdx],
+        start: RoomGeometryRoomIdx,
+        out: &mut [usize],
+    ) {
+        let total: usize = region.iter().map(|&r| self.rooms[r].tiles.len()).sum();
+        let mut order = get_connected_rooms(adj, start, |r| region.contains(&r));
+        while order.len() < region.len() {
+            // Disconnected remainder:
+            let r = *region.iter().find(|r| !order.contains(r)).unwrap();
+            let piece = get_connected_rooms(adj, r, |s| region.contains(&s) && !order.contains(&s));
+            order.extend(piece);
+        }
+        let mut count = 0;
+        for r in order {
+            out[r] = if count * 2 < total { 0 } else { 1 };
+            count += self.rooms[r].tiles.len();
+        }
+    }
+
+    fn check_area_bounds(
+        &self,
+        state: &LayoutState,
+        placed: &[RoomGeometryRoomIdx],
+        area: &[usize],
+    ) -> bool {
+        let mut min_x = [i32::MAX; NUM_AREAS];
+        let mut max_x = [i32::MIN; NUM_AREAS];
+        let mut min_y = [i32::MAX; NUM_AREAS];
+        let mut max_y = [i32::MIN; NUM_AREAS];
+        for &r in placed {
+            let (x, y) = state.positions[r].unwrap();
+            let a = area[r];
+            for &(tx, ty) in &self.rooms[r].tiles {
+                min_x[a] = min_x[a].min(x + tx);
+                max_x[a] = max_x[a].max(x + tx);
+                min_y[a] = min_y[a].min(y + ty);
+                max_y[a] = max_y[a].max(y + ty);
+            }
+        }
+        (0..NUM_AREAS).all(|a| {
+            max_x[a] - min_x[a] <= MAX_AREA_WIDTH && max_y[a] - min_y[a] <= MAX_AREA_HEIGHT
+        })
+    }
+
+    fn build_map<R: Rng>(&self, state: &LayoutState, rng: &mut R) -> Option<Map> {
+        let num_rooms = self.rooms.len();
+        let toilet_idx = self.game_data.toilet_room_idx;
+        let toilet_host: Option<RoomGeometryRoomIdx> = state.positions[toilet_idx].map(|(x, y)| {
+            (y + 2..y + 8)
+                .find_map(|ty| state.get_occupant((x, ty)).filter(|&r| r != toilet_idx))
+                .unwrap()
+        });
+        // Region growing works on rooms other than the Toilet, which goes with its host room:
+        let resolve = |r: RoomGeometryRoomIdx| {
+            if r == toilet_idx {
+                toilet_host.unwrap()
+            } else {
+                r
+            }
+        };
+        let placed: Vec<RoomGeometryRoomIdx> = (0..num_rooms)
+            .filter(|&r| state.positions[r].is_some() && r != toilet_idx)
+            .collect();
+        let mut adj: Vec<Vec<RoomGeometryRoomIdx>> = vec![vec![]; num_rooms];
+        for &(a, b) in &state.conns {
+            let (ra, rb) = (resolve(a.0), resolve(b.0));
+            if ra != rb && !adj[ra].contains(&rb) {
+                adj[ra].push(rb);
+                adj[rb].push(ra);
+            }
+        }
+
+        let mut area = None;
+        for _ in 0..MAX_AREA_ATTEMPTS {
+            if let Some(a) = self.assign_areas(&adj, &placed, rng)
+                && self.check_area_bounds(state, &placed, &a)
+            {
+                area = Some(a);
+                break;
+            }
+        }
+        let mut area = area?;
+
+        let mut subarea = vec![0; num_rooms];
+        let mut subsubarea = vec![0; num_rooms];
+        for a in 0..NUM_AREAS {
+            let region: Vec<RoomGeometryRoomIdx> =
+                placed.iter().copied().filter(|&r| area[r] == a).collect();
+            let start = if a == 0 {
+                self.landing_site_idx
+            } else {
+                region[0]
+            };
+            self.split_region(&adj, &region, start, &mut subarea);
+            for s in 0..2 {
+                let subregion: Vec<RoomGeometryRoomIdx> = region
+                    .iter()
+                    .copied()
+                    .filter(|&r| subarea[r] == s)
+                    .collect();
+                if let Some(&start) = subregion.first() {
+                    let start = if subregion.contains(&self.landing_site_idx) {
+                        self.landing_site_idx
+                    } else {
+                        start
+                    };
+                    self.split_region(&adj, &subregion, start, &mut subsubarea);
+                }
+            }
+        }
+        if let Some(host) = toilet_host {
+            area[toilet_idx] = area[host];
+            subarea[toilet_idx] = subarea[host];
+            subsubarea[toilet_idx] = subsubarea[host];
+        }
+
+        let mut doors = vec![];
+        for &(a, b) in &state.conns {
+            let door_a = self.door(a);
+            // Connections go from the right/down door to the left/up door:
+            let (src, dst) = match door_a.direction {
+                Direction::Right | Direction::Down => (a, b),
+                Direction::Left | Direction::Up => (b, a),
+            };
+            doors.push((
+                self.door(src).ptr_pair,
+                self.door(dst).ptr_pair,
+                door_a.subtype != "sand",
+            ));
+        }
+
+        Some(Map {
+            room_mask: state.positions.iter().map(|p| p.is_some()).collect(),
+            rooms: state
+                .positions
+                .iter()
+                .map(|p| p.map(|(x, y)| (x as usize, y as usize)).unwrap_or((0, 0)))
+                .collect(),
+            doors,
+            area,
+            subarea,
+            subsubarea,
+        })
+    }
+
+    pub fn generate(&self, seed: usize) -> Result<Map> {
+        let mut rng_seed = [0u8; 32];
+        rng_seed[..8].copy_from_slice(&seed.to_le_bytes());
+        let mut rng = StdRng::from_seed(rng_seed);
+
+        for attempt_num in 0..self.config.max_attempts {
+            let units = self.get_units(&mut rng);
+            let Some(state) = self.generate_layout(&units, &mut rng) else {
+                info!("[attempt {attempt_num}] Failed to connect Landing Site");
+                continue;
+            };
+            let num_rooms = state.positions.iter().filter(|p| p.is_some()).count();
+            if let Some(&r) = self
+                .required_room_idxs
+                .iter()
+                .find(|&&r| state.positions[r].is_none())
+            {
+                info!(
+                    "[attempt {attempt_num}] Layout with {num_rooms} rooms is missing {}",
+                    self.get_room_name(r)
+                );
+                continue;
+            }
+            if num_rooms < self.config.min_rooms {
+                info!("[attempt {attempt_num}] Layout has only {num_rooms} rooms");
+                continue;
+            }
+            let Some(map) = self.build_map(&state, &mut rng) else {
+                info!("[attempt {attempt_num}] Failed to assign areas");
+                continue;
+            };
+            if let Err(e) = check_map(&map, self.game_data) {
+                warn!("[attempt {attempt_num}] Generated an invalid map: {e:#}");
+                continue;
+            }
+            info!(
+                "[attempt {attempt_num}] Generated map with {num_rooms} rooms and {} connections",
+                map.doors.len()
+            );
+            return Ok(map);
+        }
+        bail!(
+            "Failed to generate map after {} attempts",
+            self.config.max_attempts
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn generated_maps_are_valid() {
+        let game_data = test_data::game_data();
+        let generator = MapGenerator::new(game_data, MapGeneratorConfig::default()).unwrap();
+        for seed in 0..3 {
+            let map = generator.generate(seed).unwrap();
+            let errors: Vec<String> = map
+                .validate(game_data)
+                .iter()
+                .map(|x| x.to_string())
+                .collect();
+            assert!(errors.is_empty(), "seed {seed}: {}", errors.join("\n"));
+
+            // The same seed gives the same map:
+            let map2 = generator.generate(seed).unwrap();
+            assert_eq!(
+                serde_json::to_string(&map).unwrap(),
+                serde_json::to_string(&map2).unwrap()
+            );
+        }
+    }
+}
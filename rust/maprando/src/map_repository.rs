@@ -1,14 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use apache_avro::{
+    Codec, DeflateSettings, Schema, Writer,
+    types::{Record, Value},
+};
 use log::info;
 use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
-    io::BufReader,
+    io::{BufReader, BufWriter},
     path::{Path, PathBuf},
 };
 
-use crate::randomize::Randomizer;
+use crate::{patch::NUM_AREAS, randomize::Randomizer, settings::MapFilterSettings};
 use maprando_game::{GameData, Map, RoomId};
 
 pub struct MapRepository {
@@ -16,8 +20,10 @@ pub struct MapRepository {
     pub filenames: Vec<String>,
 }
 
-#[derive(Deserialize)]
-struct StoredMap {
+// A map in the form stored in the Avro files (see data/schema/map.avsc). Rooms and doors are
+// referenced by room ID and door index, and only rooms included in the map are listed.
+#[derive(Deserialize, Clone)]
+pub struct StoredMap {
     pub room_id: Vec<RoomId>,
     pub room_x: Vec<usize>,
     pub room_y: Vec<usize>,
@@ -31,28 +37,359 @@ struct StoredMap {
     pub conn_bidirectional: Vec<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(untagged)]
-enum MapsPerFile {
+pub enum MapsPerFile {
     Fixed(usize),
     Variable(Vec<usize>),
 }
 
-#[derive(Deserialize)]
-struct MapManifest {
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MapManifest {
     pub maps_per_file: MapsPerFile,
     pub files: Vec<String>,
 }
 
-impl MapRepository {
-    pub fn new(name: &str, base_path: &Path) -> Result<Self> {
-        let manifest_bytes = std::fs::read(base_path.join("manifest.json"))?;
-        let manifest: MapManifest = serde_json::from_slice(&manifest_bytes)?;
-
-        let num_maps = match manifest.maps_per_file {
-            MapsPerFile::Fixed(n) => manifest.files.len() * n,
+impl MapManifest {
+    pub fn num_maps(&self) -> usize {
+        match &self.maps_per_file {
+            MapsPerFile::Fixed(n) => self.files.len() * n,
             MapsPerFile::Variable(v) => v.iter().sum(),
+        }
+    }
+}
+
+impl StoredMap {
+    pub fn from_map(map: &Map, game_data: &GameData) -> Result<Self> {
+        let mut stored_map = StoredMap {
+            room_id: vec![],
+            room_x: vec![],
+            room_y: vec![],
+            room_area: vec![],
+            room_subarea: vec![],
+            room_subsubarea: vec![],
+            conn_from_room_id: vec![],
+            conn_from_door_id: vec![],
+            conn_to_room_id: vec![],
+            conn_to_door_id: vec![],
+            conn_bidirectional: vec![],
         };
+        for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+            if !map.room_mask[room_idx] {
+                continue;
+            }
+            stored_map.room_id.push(room.room_id);
+            stored_map.room_x.push(map.rooms[room_idx].0);
+            stored_map.room_y.push(map.rooms[room_idx].1);
+            stored_map.room_area.push(map.area[room_idx]);
+            stored_map.room_subarea.push(map.subarea[room_idx]);
+            stored_map.room_subsubarea.push(map.subsubarea[room_idx]);
+        }
+        for &(src, dst, bidirectional) in &map.doors {
+            let Some(&(src_room_idx, src_door_idx)) =
+                game_data.room_and_door_idxs_by_door_ptr_pair.get(&src)
+            else {
+                bail!("Unknown door {:?}", src);
+            };
+            let Some(&(dst_room_idx, dst_door_idx)) =
+                game_data.room_and_door_idxs_by_door_ptr_pair.get(&dst)
+            else {
+                bail!("Unknown door {:?}", dst);
+            };
+            stored_map
+                .conn_from_room_id
+                .push(game_data.room_geometry[src_room_idx].room_id);
+            stored_map.conn_from_door_id.push(src_door_idx);
+            stored_map
+                .conn_to_room_id
+                .push(game_data.room_geometry[dst_room_idx].room_id);
+            stored_map.conn_to_door_id.push(dst_door_idx);
+            stored_map.conn_bidirectional.push(bidirectional);
+        }
+        Ok(stored_map)
+    }
+
+    pub fn to_map(&self, game_data: &GameData) -> Result<Map> {
+        let room_geometry = &game_data.room_geometry;
+        let num_rooms = self.room_id.len();
+        let num_conns = self.conn_from_door_id.len();
+
+        let mut room_mask: Vec<bool> = vec![false; room_geometry.len()];
+        let mut rooms: Vec<(usize, usize)> = vec![(0, 0); room_geometry.len()];
+        let mut areas: Vec<usize> = vec![0; room_geometry.len()];
+        let mut subareas: Vec<usize> = vec![0; room_geometry.len()];
+        let mut subsubareas: Vec<usize> = vec![0; room_geometry.len()];
+        for i in 0..num_rooms {
+            let room_id = self.room_id[i];
+            let room_ptr = game_data.room_ptr_by_id[&room_id];
+            let room_idx = game_data.room_idx_by_ptr[&room_ptr];
+            room_mask[room_idx] = true;
+            rooms[room_idx] = (self.room_x[i], self.room_y[i]);
+            areas[room_idx] = self.room_area[i];
+            subareas[room_idx] = self.room_subarea[i];
+            subsubareas[room_idx] = self.room_subsubarea[i];
+        }
+
+        let mut doors = vec![];
+        for i in 0..num_conns {
+            let from_room_id = self.conn_from_room_id[i];
+            let from_room_ptr = game_data.room_ptr_by_id[&from_room_id];
+            let from_room_idx = game_data.room_idx_by_ptr[&from_room_ptr];
+            let from_door_id = self.conn_from_door_id[i];
+            let from_exit_ptr = game_data.room_geometry[from_room_idx].doors[from_door_id].exit_ptr;
+            let from_entrance_ptr =
+                game_data.room_geometry[from_room_idx].doors[from_door_id].entrance_ptr;
+            let to_room_id = self.conn_to_room_id[i];
+            let to_room_ptr = game_data.room_ptr_by_id[&to_room_id];
+            let to_room_idx = game_data.room_idx_by_ptr[&to_room_ptr];
+            let to_door_id = self.conn_to_door_id[i];
+            let to_exit_ptr = game_data.room_geometry[to_room_idx].doors[to_door_id].exit_ptr;
+            let to_entrance_ptr =
+                game_data.room_geometry[to_room_idx].doors[to_door_id].entrance_ptr;
+            let bidirectional = self.conn_bidirectional[i];
+            doors.push((
+                (from_exit_ptr, from_entrance_ptr),
+                (to_exit_ptr, to_entrance_ptr),
+                bidirectional,
+            ));
+        }
+
+        let mut map = Map {
+            room_mask,
+            rooms,
+            doors,
+            area: areas,
+            subarea: subareas,
+            subsubarea: subsubareas,
+        };
+
+        // Make Toilet area/subarea/subsubarea align with its intersecting room(s):
+        // TODO: Push this upstream into the map generation
+        let toilet_intersections = Randomizer::get_toilet_intersections(&map, game_data);
+        if !toilet_intersections.is_empty() {
+            let area = map.area[toilet_intersections[0]];
+            let subarea = map.subarea[toilet_intersections[0]];
+            let subsubarea = map.subsubarea[toilet_intersections[0]];
+            for &t in &toilet_intersections {
+                if map.area[t] != area {
+                    bail!("Mismatched areas for Toilet intersection");
+                }
+                if map.subarea[t] != subarea {
+                    bail!("Mismatched subareas for Toilet intersection");
+                }
+                if map.subsubarea[t] != subsubarea {
+                    bail!("Mismatched subsubareas for Toilet intersection");
+                }
+            }
+            map.area[game_data.toilet_room_idx] = area;
+            map.subarea[game_data.toilet_room_idx] = subarea;
+            map.subsubarea[game_data.toilet_room_idx] = subsubarea;
+        }
+
+        let toilet_top = (Some(0x1A60C), Some(0x1A5AC));
+        let toilet_bottom = (Some(0x1A600), Some(0x1A678));
+        let mut found_top: bool = false;
+        let mut found_bottom: bool = false;
+        for d in &map.doors {
+            if d.0 == toilet_top || d.1 == toilet_top {
+                found_top = true;
+            }
+            if d.0 == toilet_bottom || d.1 == toilet_bottom {
+                found_bottom = true;
+            }
+        }
+        if !found_top || !found_bottom {
+            // If Toilet does not connect on both sides, then remove it,
+            // since we can't put a wall inside it.
+            // TODO: push this upstream to the small map extraction
+            map.room_mask[game_data.toilet_room_idx] = false;
+            map.doors.retain(|x| {
+                x.0 != toilet_top
+                    && x.0 != toilet_bottom
+                    && x.1 != toilet_top
+                    && x.1 != toilet_bottom
+            });
+        }
+
+        Ok(map)
+    }
+}
+
+// Load the Avro schema of the map files (data/schema/map.avsc).
+pub fn load_map_schema(path: &Path) -> Result<Schema> {
+    let schema_str = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read map schema at {}", path.display()))?;
+    Ok(Schema::parse_str(&schema_str)?)
+}
+
+// Read the maps in an Avro batch file, in the order they are stored.
+pub fn read_map_file(path: &Path) -> Result<Vec<StoredMap>> {
+    let file = File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    let avro_reader = apache_avro::Reader::new(BufReader::new(file))?;
+    let mut out = vec![];
+    for value in avro_reader {
+        out.push(apache_avro::from_value(&value?)?);
+    }
+    Ok(out)
+}
+
+pub fn write_map_file(path: &Path, schema: &Schema, maps: &[StoredMap]) -> Result<()> {
+    let int_array = |v: &[usize]| Value::Array(v.iter().map(|&x| Value::Int(x as i32)).collect());
+    let file =
+        File::create(path).with_context(|| format!("Unable to create {}", path.display()))?;
+    let mut writer = Writer::with_codec(
+        schema,
+        BufWriter::new(file),
+        Codec::Deflate(DeflateSettings::default()),
+    );
+    for m in maps {
+        let mut record = Record::new(schema).context("Map schema is not a record")?;
+        record.put("room_id", int_array(&m.room_id));
+        record.put("room_x", int_array(&m.room_x));
+        record.put("room_y", int_array(&m.room_y));
+        record.put("room_area", int_array(&m.room_area));
+        record.put("room_subarea", int_array(&m.room_subarea));
+        record.put("room_subsubarea", int_array(&m.room_subsubarea));
+        record.put("conn_from_room_id", int_array(&m.conn_from_room_id));
+        record.put("conn_from_door_id", int_array(&m.conn_from_door_id));
+        record.put("conn_to_room_id", int_array(&m.conn_to_room_id));
+        record.put("conn_to_door_id", int_array(&m.conn_to_door_id));
+        let bidirectional = m.conn_bidirectional.iter().map(|&b| Value::Boolean(b));
+        record.put("conn_bidirectional", Value::Array(bidirectional.collect()));
+        writer.append(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_manifest(base_path: &Path) -> Result<MapManifest> {
+    let path = base_path.join("manifest.json");
+    let manifest_bytes =
+        std::fs::read(&path).with_context(|| format!("Unable to read {}", path.display()))?;
+    Ok(serde_json::from_slice(&manifest_bytes)?)
+}
+
+pub fn write_manifest(base_path: &Path, manifest: &MapManifest) -> Result<()> {
+    let path = base_path.join("manifest.json");
+    std::fs::write(&path, serde_json::to_string(manifest)?)
+        .with_context(|| format!("Unable to write {}", path.display()))?;
+    Ok(())
+}
+
+// Parse and validate a map JSON in the format of `Map` (as written by maprando-cli, map-gen, or
+// map-repo to-json).
+pub fn parse_map_json(map_string: &str, game_data: &GameData) -> Result<Map> {
+    let mut map: Map = serde_json::from_str(map_string).context("Unable to parse map JSON")?;
+    if map.room_mask.is_empty() {
+        // Older maps without a room mask include all rooms:
+        map.room_mask = vec![true; map.rooms.len()];
+    }
+    check_map(&map, game_data)?;
+    Ok(map)
+}
+
+// Fail with all the validation errors of the map, if there are any.
+pub fn check_map(map: &Map, game_data: &GameData) -> Result<()> {
+    let errors = map.validate(game_data);
+    if !errors.is_empty() {
+        let error_strs: Vec<String> = errors.iter().map(|x| x.to_string()).collect();
+        bail!("Invalid map:\n{}", error_strs.join("\n"));
+    }
+    Ok(())
+}
+
+pub fn load_map_json(path: &Path, game_data: &GameData) -> Result<Map> {
+    let map_string = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read map file at {}", path.display()))?;
+    parse_map_json(&map_string, game_data)
+        .with_context(|| format!("Error in map file at {}", path.display()))
+}
+
+// Count the door connections which are the same as in the vanilla game, excluding the one-way sand
+// connections (without a door pointer) which are always kept.
+fn count_vanilla_connections(map: &Map) -> usize {
+    map.doors
+        .iter()
+        .filter(|(src, dst, _)| {
+            src.0.is_some() && src.1.is_some() && src.0 == dst.1 && src.1 == dst.0
+        })
+        .count()
+}
+
+pub fn map_matches_filter(map: &Map, filter: &MapFilterSettings, game_data: &GameData) -> bool {
+    for room_id in &filter.required_rooms {
+        if !map.room_mask[game_data.room_idx_by_id[room_id]] {
+            return false;
+        }
+    }
+    for room_id in &filter.forbidden_rooms {
+        if map.room_mask[game_data.room_idx_by_id[room_id]] {
+            return false;
+        }
+    }
+
+    if filter.min_rooms_per_area.is_some() || filter.max_rooms_per_area.is_some() {
+        let mut area_room_count = [0; NUM_AREAS];
+        for (room_idx, &area) in map.area.iter().enumerate() {
+            if map.room_mask[room_idx] {
+                area_room_count[area] += 1;
+            }
+        }
+        for count in area_room_count {
+            if filter.min_rooms_per_area.is_some_and(|x| count < x)
+                || filter.max_rooms_per_area.is_some_and(|x| count > x)
+            {
+                return false;
+            }
+        }
+    }
+
+    if let Some(toilet_intersection) = filter.toilet_intersection {
+        let intersects = !Randomizer::get_toilet_intersections(map, game_data).is_empty();
+        if intersects != toilet_intersection {
+            return false;
+        }
+    }
+
+    if filter.min_vanilla_connections.is_some() || filter.max_vanilla_connections.is_some() {
+        let count = count_vanilla_connections(map);
+        if filter.min_vanilla_connections.is_some_and(|x| count < x)
+            || filter.max_vanilla_connections.is_some_and(|x| count > x)
+        {
+            return false;
+        }
+    }
+
+    if filter.max_width.is_some() || filter.max_height.is_some() {
+        let mut x_min = usize::MAX;
+        let mut x_max = 0;
+        let mut y_min = usize::MAX;
+        let mut y_max = 0;
+        for (room_idx, &(x, y)) in map.rooms.iter().enumerate() {
+            if !map.room_mask[room_idx] {
+                continue;
+            }
+            let room_map = &game_data.room_geometry[room_idx].map;
+            x_min = x_min.min(x);
+            x_max = x_max.max(x + room_map[0].len());
+            y_min = y_min.min(y);
+            y_max = y_max.max(y + room_map.len());
+        }
+        let width = x_max.saturating_sub(x_min);
+        let height = y_max.saturating_sub(y_min);
+        if filter.max_width.is_some_and(|w| width > w)
+            || filter.max_height.is_some_and(|h| height > h)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+impl MapRepository {
+    pub fn new(name: &str, base_path: &Path) -> Result<Self> {
+        let manifest = read_manifest(base_path)?;
+        let num_maps = manifest.num_maps();
         info!(
             "{}: {} maps available ({})",
             name,
@@ -70,116 +407,9 @@ impl MapRepository {
         let path = self.base_path.join(&self.filenames[idx]);
         info!("Map batch file: {}", path.display());
 
-        let file = File::open(path)?;
-        let buf_reader = BufReader::new(file);
-        let avro_reader = apache_avro::Reader::new(buf_reader)?;
         let mut map_vec: Vec<Map> = vec![];
-        let room_geometry = &game_data.room_geometry;
-
-        for value in avro_reader {
-            let stored_map: StoredMap = apache_avro::from_value(&value?)?;
-            let num_rooms = stored_map.room_id.len();
-            let num_conns = stored_map.conn_from_door_id.len();
-
-            let mut room_mask: Vec<bool> = vec![false; room_geometry.len()];
-            let mut rooms: Vec<(usize, usize)> = vec![(0, 0); room_geometry.len()];
-            let mut areas: Vec<usize> = vec![0; room_geometry.len()];
-            let mut subareas: Vec<usize> = vec![0; room_geometry.len()];
-            let mut subsubareas: Vec<usize> = vec![0; room_geometry.len()];
-            for i in 0..num_rooms {
-                let room_id = stored_map.room_id[i];
-                let room_ptr = game_data.room_ptr_by_id[&room_id];
-                let room_idx = game_data.room_idx_by_ptr[&room_ptr];
-                room_mask[room_idx] = true;
-                rooms[room_idx] = (stored_map.room_x[i], stored_map.room_y[i]);
-                areas[room_idx] = stored_map.room_area[i];
-                subareas[room_idx] = stored_map.room_subarea[i];
-                subsubareas[room_idx] = stored_map.room_subsubarea[i];
-            }
-
-            let mut doors = vec![];
-            for i in 0..num_conns {
-                let from_room_id = stored_map.conn_from_room_id[i];
-                let from_room_ptr = game_data.room_ptr_by_id[&from_room_id];
-                let from_room_idx = game_data.room_idx_by_ptr[&from_room_ptr];
-                let from_door_id = stored_map.conn_from_door_id[i];
-                let from_exit_ptr =
-                    game_data.room_geometry[from_room_idx].doors[from_door_id].exit_ptr;
-                let from_entrance_ptr =
-                    game_data.room_geometry[from_room_idx].doors[from_door_id].entrance_ptr;
-                let to_room_id = stored_map.conn_to_room_id[i];
-                let to_room_ptr = game_data.room_ptr_by_id[&to_room_id];
-                let to_room_idx = game_data.room_idx_by_ptr[&to_room_ptr];
-                let to_door_id = stored_map.conn_to_door_id[i];
-                let to_exit_ptr = game_data.room_geometry[to_room_idx].doors[to_door_id].exit_ptr;
-                let to_entrance_ptr =
-                    game_data.room_geometry[to_room_idx].doors[to_door_id].entrance_ptr;
-                let bidirectional = stored_map.conn_bidirectional[i];
-                doors.push((
-                    (from_exit_ptr, from_entrance_ptr),
-                    (to_exit_ptr, to_entrance_ptr),
-                    bidirectional,
-                ));
-            }
-
-            let mut map = Map {
-                room_mask,
-                rooms,
-                doors,
-                area: areas,
-                subarea: subareas,
-                subsubarea: subsubareas,
-            };
-
-            // Make Toilet area/subarea/subsubarea align with its intersecting room(s):
-            // TODO: Push this upstream into the map generation
-            let toilet_intersections = Randomizer::get_toilet_intersections(&map, game_data);
-            if !toilet_intersections.is_empty() {
-                let area = map.area[toilet_intersections[0]];
-                let subarea = map.subarea[toilet_intersections[0]];
-                let subsubarea = map.subsubarea[toilet_intersections[0]];
-                for &t in &toilet_intersections {
-                    if map.area[t] != area {
-                        panic!("Mismatched areas for Toilet intersection");
-                    }
-                    if map.subarea[t] != subarea {
-                        panic!("Mismatched subareas for Toilet intersection");
-                    }
-                    if map.subsubarea[t] != subsubarea {
-                        panic!("Mismatched subsubareas for Toilet intersection");
-                    }
-                }
-                map.area[game_data.toilet_room_idx] = area;
-                map.subarea[game_data.toilet_room_idx] = subarea;
-                map.subsubarea[game_data.toilet_room_idx] = subsubarea;
-            }
-
-            let toilet_top = (Some(0x1A60C), Some(0x1A5AC));
-            let toilet_bottom = (Some(0x1A600), Some(0x1A678));
-            let mut found_top: bool = false;
-            let mut found_bottom: bool = false;
-            for d in &map.doors {
-                if d.0 == toilet_top || d.1 == toilet_top {
-                    found_top = true;
-                }
-                if d.0 == toilet_bottom || d.1 == toilet_bottom {
-                    found_bottom = true;
-                }
-            }
-            if !found_top || !found_bottom {
-                // If Toilet does not connect on both sides, then remove it,
-                // since we can't put a wall inside it.
-                // TODO: push this upstream to the small map extraction
-                map.room_mask[game_data.toilet_room_idx] = false;
-                map.doors.retain(|x| {
-                    x.0 != toilet_top
-                        && x.0 != toilet_bottom
-                        && x.1 != toilet_top
-                        && x.1 != toilet_bottom
-                });
-            }
-
-            map_vec.push(map);
+        for stored_map in read_map_file(&path)? {
+            map_vec.push(stored_map.to_map(game_data)?);
         }
 
         let mut rng_seed = [0u8; 32];
@@ -189,4 +419,69 @@ impl MapRepository {
 
         Ok(map_vec)
     }
+
+    // Get a batch of maps matching the filter, starting from the file selected by the seed and
+    // moving on to the following files if none of its maps match. Fails if no map in the
+    // repository matches.
+    pub fn get_filtered_map_batch(
+        &self,
+        seed: usize,
+        filter: &MapFilterSettings,
+        game_data: &GameData,
+    ) -> Result<Vec<Map>> {
+        if *filter == MapFilterSettings::default() {
+            return self.get_map_batch(seed, game_data);
+        }
+        for i in 0..self.filenames.len() {
+            let map_vec: Vec<Map> = self
+                .get_map_batch(seed.wrapping_add(i), game_data)?
+                .into_iter()
+                .filter(|map| map_matches_filter(map, filter, game_data))
+                .collect();
+            if !map_vec.is_empty() {
+                info!("{} maps in batch match the map filter", map_vec.len());
+                return Ok(map_vec);
+            }
+        }
+        bail!(
+            "Map pool exhausted: no maps in {} match the map filter",
+            self.base_path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn stored_map_round_trip() {
+        let game_data = test_data::game_data();
+        let schema = load_map_schema(&test_data::workspace_path("data/schema/map.avsc")).unwrap();
+        let stored_maps =
+            read_map_file(&test_data::workspace_path("../maps/vanilla/maps-0.avro")).unwrap();
+        let maps: Vec<Map> = stored_maps
+            .iter()
+            .map(|m| m.to_map(game_data).unwrap())
+            .collect();
+
+        let path = std::env::temp_dir().join(format!("maprando-test-{}.avro", std::process::id()));
+        let new_stored_maps: Vec<StoredMap> = maps
+            .iter()
+            .map(|m| StoredMap::from_map(m, game_data).unwrap())
+            .collect();
+        write_map_file(&path, &schema, &new_stored_maps).unwrap();
+        let read_maps = read_map_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read_maps.len(), maps.len());
+        for (map, stored_map) in maps.iter().zip(&read_maps) {
+            let new_map = stored_map.to_map(game_data).unwrap();
+            assert_eq!(
+                serde_json::to_value(map).unwrap(),
+                serde_json::to_value(&new_map).unwrap()
+            );
+        }
+    }
 }
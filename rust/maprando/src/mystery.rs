@@ -0,0 +1,212 @@
+// Weighted random settings ("mystery seeds").
+//
+// A weights file assigns probabilities to the possible values of fields in the settings, e.g.:
+//
+//     {
+//         "name": "Mystery",
+//         "fields": [
+//             {"path": "skill_assumption_settings.preset", "options": [
+//                 {"value": "Hard", "weight": 2}, {"value": "Very Hard", "weight": 1}]},
+//             {"path": "doors_mode", "options": [
+//                 {"value": "Ammo", "weight": 3}, {"value": "Beam", "weight": 1}]},
+//             {"path": "item_progression_settings.item_pool.ETank.count", "options": [
+//                 {"value": 10, "weight": 1}, {"value": 14, "weight": 1}]}
+//         ]
+//     }
+//
+// Fields are sampled in order, starting from the base settings. A path is a sequence of
+// dot-separated JSON keys, where a segment indexing into an array selects the element whose
+// "item", "objective", or "name" field matches it. Sampling a section's "preset" field (or the
+// top-level "name" field) replaces the whole section (or all settings) with the named preset,
+// so presets should be listed before any fields which override parts of them.
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use rand::{Rng, distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    preset::PresetData,
+    settings::{RandomizerSettings, find_section_preset, try_upgrade_settings},
+};
+use maprando_game::GameData;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MysteryOption {
+    pub value: serde_json::Value,
+    pub weight: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MysteryField {
+    pub path: String,
+    pub options: Vec<MysteryOption>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MysteryWeights {
+    // Settings name shown for generated seeds (e.g. "Weekly Mystery"):
+    pub name: Option<String>,
+    // Full-settings preset to start from, if not the default preset:
+    pub base_preset: Option<String>,
+    pub fields: Vec<MysteryField>,
+}
+
+const MAX_SAMPLE_ATTEMPTS: usize = 100;
+
+pub fn parse_mystery_weights(weights_json: &str) -> Result<MysteryWeights> {
+    let mut des = serde_json::Deserializer::from_str(weights_json);
+    let weights: MysteryWeights = serde_path_to_error::deserialize(&mut des)?;
+    for field in &weights.fields {
+        ensure!(
+            !field.options.is_empty(),
+            "no options for mystery field {}",
+            field.path
+        );
+        WeightedIndex::new(field.options.iter().map(|x| x.weight))
+            .with_context(|| format!("invalid weights for mystery field {}", field.path))?;
+    }
+    Ok(weights)
+}
+
+fn get_path_mut<'a>(
+    value: &'a mut serde_json::Value,
+    path: &str,
+) -> Result<&'a mut serde_json::Value> {
+    let mut v = value;
+    for segment in path.split('.') {
+        v = match v {
+            serde_json::Value::Object(obj) => obj
+                .get_mut(segment)
+                .with_context(|| format!("unrecognized field {segment} in {path}"))?,
+            serde_json::Value::Array(arr) => arr
+                .iter_mut()
+                .find(|x| {
+                    ["item", "objective", "name"]
+                        .iter()
+                        .any(|&k| x[k].as_str() == Some(segment))
+                })
+                .with_context(|| format!("unrecognized element {segment} in {path}"))?,
+            _ => bail!("unable to index into {segment} in {path}"),
+        };
+    }
+    Ok(v)
+}
+
+fn apply_preset(
+    settings: &mut serde_json::Value,
+    path: &str,
+    preset_name: &str,
+    preset_data: &PresetData,
+) -> Result<()> {
+    if path == "name" {
+        let preset = preset_data
+            .full_presets
+            .iter()
+            .find(|p| p.name.as_deref() == Some(preset_name))
+            .with_context(|| format!("unrecognized preset {preset_name} for {path}"))?;
+        *settings = serde_json::to_value(preset)?;
+        return Ok(());
+    }
+    let section = path.strip_suffix(".preset").unwrap();
+    let preset = find_section_preset(section, preset_name, preset_data)?
+        .with_context(|| format!("unrecognized preset {preset_name} for {path}"))?;
+    *get_path_mut(settings, section)? = preset;
+    Ok(())
+}
+
+fn is_preset_path(path: &str) -> bool {
+    path == "name" || (path.ends_with(".preset") && path.matches('.').count() == 1)
+}
+
+fn sample_settings_json(
+    weights: &MysteryWeights,
+    preset_data: &PresetData,
+    rng: &mut impl Rng,
+) -> Result<serde_json::Value> {
+    let mut settings = serde_json::to_value(&preset_data.default_preset)?;
+    if let Some(name) = &weights.base_preset {
+        apply_preset(&mut settings, "name", name, preset_data)?;
+    }
+    for field in &weights.fields {
+        let dist = WeightedIndex::new(field.options.iter().map(|x| x.weight))
+            .with_context(|| format!("invalid weights for mystery field {}", field.path))?;
+        let value = &field.options[dist.sample(rng)].value;
+        if is_preset_path(&field.path) {
+            let preset_name = value
+                .as_str()
+                .with_context(|| format!("expected preset name for {}", field.path))?;
+            apply_preset(&mut settings, &field.path, preset_name, preset_data)?;
+            continue;
+        }
+        *get_path_mut(&mut settings, &field.path)? = value.clone();
+
+        // The section no longer matches its preset (if any), so show it as custom:
+        let section = field.path.split('.').next().unwrap();
+        if let Some(preset) = settings[section].get_mut("preset") {
+            *preset = serde_json::Value::Null;
+        }
+    }
+    settings["name"] = weights.name.clone().unwrap_or("Mystery".to_string()).into();
+    Ok(settings)
+}
+
+// Sample concrete settings from the weights. Samples that do not form valid settings
+// (e.g. an inconsistent combination of objective options) are rejected and resampled.
+pub fn generate_mystery_settings(
+    weights: &MysteryWeights,
+    preset_data: &PresetData,
+    game_data: &GameData,
+    rng: &mut impl Rng,
+) -> Result<RandomizerSettings> {
+    let mut last_err = None;
+    for _ in 0..MAX_SAMPLE_ATTEMPTS {
+        let settings_json = sample_settings_json(weights, preset_data, rng)?;
+        let settings = try_upgrade_settings(settings_json.to_string(), preset_data, false)
+            .context("invalid mystery settings")?
+            .1;
+        let errors = settings.validate(preset_data, game_data);
+        if errors.is_empty() {
+            return Ok(settings);
+        }
+        last_err = Some(anyhow!("{}", errors[0]));
+    }
+    Err(last_err.unwrap().context(format!(
+        "failed to sample valid settings in {MAX_SAMPLE_ATTEMPTS} attempts"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn path_into_arrays() {
+        let mut settings = json!({
+            "item_progression_settings": {
+                "item_pool": [{"item": "ETank", "count": 14}, {"item": "Missile", "count": 46}]
+            }
+        });
+        *get_path_mut(
+            &mut settings,
+            "item_progression_settings.item_pool.Missile.count",
+        )
+        .unwrap() = 30.into();
+        assert_eq!(
+            settings["item_progression_settings"]["item_pool"][1]["count"],
+            30
+        );
+        assert!(get_path_mut(&mut settings, "item_progression_settings.item_pool.Foo").is_err());
+        assert!(get_path_mut(&mut settings, "doors_mode").is_err());
+    }
+
+    #[test]
+    fn reject_bad_weights() {
+        let weights = r#"{"name": null, "base_preset": null, "fields": [
+            {"path": "doors_mode", "options": [{"value": "Ammo", "weight": 0}]}]}"#;
+        assert!(parse_mystery_weights(weights).is_err());
+        let weights = r#"{"name": null, "base_preset": null, "fields": [
+            {"path": "doors_mode", "options": [{"value": "Ammo", "weight": 1}]}]}"#;
+        assert!(parse_mystery_weights(weights).is_ok());
+    }
+}
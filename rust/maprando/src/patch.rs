@@ -18,8 +18,8 @@ use crate::{
     patch::map_tiles::diagonal_flip_tile,
     randomize::{LockedDoor, Randomization},
     settings::{
-        AreaAssignment, ETankRefill, Fanfares, ItemCount, MotherBrainFight, Objective,
-        ObjectiveScreen, RandomizerSettings, SaveAnimals, StartLocationMode, WallJump,
+        AreaAssignment, ETankRefill, Fanfares, ItemCount, MAX_OBJECTIVES, MotherBrainFight,
+        Objective, ObjectiveScreen, RandomizerSettings, SaveAnimals, StartLocationMode, WallJump,
     },
 };
 use anyhow::{Context, Result, bail, ensure};
@@ -36,7 +36,7 @@ use strum::VariantNames;
 
 use self::map_tiles::write_tile_4bpp;
 
-pub const NUM_AREAS: usize = 6;
+pub use maprando_game::NUM_AREAS;
 
 type PcAddr = usize; // PC pointer to ROM data
 type AsmPtr = usize; // 16-bit SNES pointer to ASM code in bank 0x8F
@@ -262,7 +262,7 @@ fn item_to_plm_type(item: Item, orig_plm_type: isize) -> isize {
     // Item container: 0 = none, 1 = chozo orb, 2 = shot block (scenery)
     let item_container = (orig_plm_type - 0xEED7) / 84;
 
-    let plm_table: [[isize; 23]; 3] = [
+    let plm_table: [[isize; 25]; 3] = [
         [
             0xEED7, // Energy tank
             0xEEDB, // Missile tank
@@ -287,6 +287,8 @@ fn item_to_plm_type(item: Item, orig_plm_type: isize) -> isize {
             0xEF27, // Reserve tank
             0xF000, // Wall-jump boots
             0xEEDB, // Missile tank (nothing)
+            0xF630, // Progressive beam
+            0xF63C, // Progressive suit
         ],
         [
             0xEF2B, // Energy tank, chozo orb
@@ -312,6 +314,8 @@ fn item_to_plm_type(item: Item, orig_plm_type: isize) -> isize {
             0xEF7B, // Reserve tank, chozo orb
             0xF004, // Wall-jump boots, chozo orb
             0xEF2F, // Missile tank (nothing)
+            0xF634, // Progressive beam, chozo orb
+            0xF640, // Progressive suit, chozo orb
         ],
         [
             0xEF7F, // Energy tank, shot block
@@ -337,6 +341,8 @@ fn item_to_plm_type(item: Item, orig_plm_type: isize) -> isize {
             0xEFCF, // Reserve tank, shot block
             0xF008, // Wall-jump boots, shot block
             0xEF83, // Missile tank (nothing)
+            0xF638, // Progressive beam, shot block
+            0xF644, // Progressive suit, shot block
         ],
     ];
 
@@ -409,6 +415,7 @@ fn apply_orig_ips_patches(rom: &mut Rom, settings: &RandomizerSettings) -> Resul
     let mut patches: Vec<&'static str> = vec![
         "mb_barrier",
         "mb_barrier_clear",
+        "objective_tracking",
         "mb_left_entrance",
         "gray_doors",
     ];
@@ -540,6 +547,12 @@ impl Patcher<'_> {
             }
         }
 
+        if self.settings.item_progression_settings.progressive_beams
+            || self.settings.item_progression_settings.progressive_suits
+        {
+            patches.push("progressive_items");
+        }
+
         match self.settings.quality_of_life_settings.etank_refill {
             ETankRefill::Disabled => {
                 patches.push("etank_refill_disabled");
@@ -2692,8 +2705,8 @@ impl Patcher<'_> {
                 if plm_type == 0x0000 {
                     break;
                 }
-                if (0xEED7..=0xF100).contains(&plm_type) {
-                    // item PLM
+                if (0xEED7..=0xF100).contains(&plm_type) || (0xF630..=0xF644).contains(&plm_type) {
+                    // item PLM (including progressive items)
                     let mut plm_x = self.rom.read_u8(intersection_plm_ptr + 2)?;
                     let mut plm_y = self.rom.read_u8(intersection_plm_ptr + 3)?;
                     let plm_var = self.rom.read_u16(intersection_plm_ptr + 4)?;
@@ -2947,6 +2960,19 @@ impl Patcher<'_> {
             .into_iter()
             .filter(|x| obj_set.contains(x))
             .collect();
+        let others: Vec<Objective> =
+            vec![ItemPercentage, AllEnergyTanks, AllMapStations, BossCount]
+                .into_iter()
+                .filter(|x| obj_set.contains(x))
+                .collect();
+
+        // Limited by the size of the objective tables in mb_barrier_clear.asm and pause_menu_objectives.asm:
+        if self.randomization.objectives.len() > MAX_OBJECTIVES as usize {
+            bail!(
+                "Too many objectives: {} (maximum is {MAX_OBJECTIVES})",
+                self.randomization.objectives.len()
+            );
+        }
 
         // Show Golden Torizo under Miniboss if there are other Miniboss objectives; otherwise put it under Chozos:
         if obj_set.contains(&Objective::GoldenTorizo) {
@@ -3140,6 +3166,34 @@ impl Patcher<'_> {
             advance_pos(&mut row, &mut row_max, &mut col);
         }
 
+        if !others.is_empty() {
+            row = row_max;
+            col = 1;
+            if row + others.len() + 2 > tile_data.len() {
+                bail!("Too many objectives to fit on the objective screen");
+            }
+            draw_row("OTHER:", &mut tile_data, &mut row, &mut row_max, col);
+            for obj in others {
+                obj_coords.insert((row, col), obj);
+                // Avoid using '-' in the text, since it would be taken as the location of a checkmark.
+                let text = match obj {
+                    ItemPercentage => format!(
+                        "- COLLECT {} PCT OF ITEMS",
+                        self.settings.objective_settings.item_percentage
+                    ),
+                    AllEnergyTanks => "- COLLECT ALL ENERGY TANKS".to_string(),
+                    AllMapStations => "- USE ALL MAP STATIONS".to_string(),
+                    BossCount => format!(
+                        "- DEFEAT {} OF 4 BOSSES",
+                        self.settings.objective_settings.boss_count
+                    ),
+                    _ => panic!("unexpected objective: {obj:?}"),
+                };
+                draw_row(&text, &mut tile_data, &mut row, &mut row_max, col);
+            }
+            advance_pos(&mut row, &mut row_max, &mut col);
+        }
+
         if self.settings.save_animals == SaveAnimals::Yes {
             row = row_max;
             col = 1;
@@ -3213,11 +3267,66 @@ impl Patcher<'_> {
                 BabyKraidRoom => (0xD823, 4),
                 PlasmaRoom => (0xD823, 8),
                 MetalPiratesRoom => (0xD823, 0x10),
+                // Derived objective flags, maintained by objective_tracking.asm:
+                ItemPercentage => (0xD824, 1),
+                AllEnergyTanks => (0xD824, 2),
+                AllMapStations => (0xD824, 4),
+                BossCount => (0xD824, 8),
             };
             self.rom.write_u16(snes2pc(0x8FEBC0) + obj_i * 2, addr)?;
             self.rom.write_u16(snes2pc(0x8FEBE8) + obj_i * 2, mask)?;
         }
 
+        self.write_objective_targets()?;
+        Ok(())
+    }
+
+    fn write_objective_targets(&mut self) -> Result<()> {
+        // Addresses used in objective_tracking.asm:
+        let item_count_target = snes2pc(0x82F810);
+        let max_energy_target = snes2pc(0x82F812);
+        let map_station_area_mask = snes2pc(0x82F814);
+        let boss_count_target = snes2pc(0x82F816);
+        let initial_max_energy = snes2pc(0xB5FE54); // (set in `set_starting_items`)
+
+        // The collected item bits for "Nothing" items are set at the start of the game,
+        // so they are included in the target count of collected item bits.
+        let num_nothing_items = self
+            .randomization
+            .item_placement
+            .iter()
+            .filter(|&&x| x == Item::Nothing)
+            .count();
+        let num_items = self.randomization.item_placement.len() - num_nothing_items;
+        let item_percentage = self.settings.objective_settings.item_percentage as usize;
+        let num_items_required = (num_items * item_percentage).div_ceil(100);
+        self.rom.write_u16(
+            item_count_target,
+            (num_nothing_items + num_items_required) as isize,
+        )?;
+
+        let num_etanks = self
+            .randomization
+            .item_placement
+            .iter()
+            .filter(|&&x| x == Item::ETank)
+            .count();
+        let max_energy = self.rom.read_u16(initial_max_energy)? + 100 * num_etanks as isize;
+        self.rom.write_u16(max_energy_target, max_energy)?;
+
+        let mut area_mask = 0;
+        for (room_id, _) in &self.game_data.map_station_locations {
+            let room_idx = self.game_data.room_idx_by_id[room_id];
+            if self.map.room_mask[room_idx] {
+                area_mask |= 1 << self.map.area[room_idx];
+            }
+        }
+        self.rom.write_u16(map_station_area_mask, area_mask)?;
+
+        self.rom.write_u16(
+            boss_count_target,
+            self.settings.objective_settings.boss_count as isize,
+        )?;
         Ok(())
     }
 }
@@ -3288,7 +3397,10 @@ pub fn make_rom(
     patcher.remove_non_blue_doors()?;
     override_music(patcher.rom)?;
     if randomizer_settings.map_layout != "Vanilla"
-        || randomizer_settings.other_settings.area_assignment == AreaAssignment::Random
+        || matches!(
+            randomizer_settings.other_settings.area_assignment,
+            AreaAssignment::Random | AreaAssignment::Balanced
+        )
     {
         patcher.use_area_based_music()?;
     }
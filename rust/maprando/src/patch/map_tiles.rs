@@ -10,8 +10,8 @@ use crate::{
 };
 use maprando_game::{
     AreaIdx, BeamType, Direction, DoorLockType, DoorType, GameData, Item, ItemIdx, Map,
-    MapLiquidType, MapTile, MapTileEdge, MapTileInterior, MapTileSpecialType, RoomGeometryDoor,
-    RoomGeometryItem, RoomId, RoomPtr,
+    MapLiquidType, MapTile, MapTileEdge, MapTileInterior, MapTileSpecialType, NUM_AREAS,
+    RoomGeometryDoor, RoomGeometryItem, RoomId, RoomPtr,
 };
 
 use super::{Rom, snes2pc, xy_to_explored_bit_ptr, xy_to_map_offset};
@@ -28,8 +28,6 @@ enum TileSide {
     Bottom,
 }
 
-const NUM_AREAS: usize = 6;
-
 pub struct MapPatcher<'a> {
     pub rom: &'a mut Rom,
     pub game_data: &'a GameData,
@@ -1469,6 +1467,9 @@ pub fn get_objective_tiles(objectives: &[Objective]) -> Vec<(RoomId, usize, usiz
                 out.push((139, 1, 0));
                 out.push((139, 2, 0));
             }
+            ItemPercentage | AllEnergyTanks | AllMapStations | BossCount => {
+                // These objectives are not tied to a specific room.
+            }
         }
     }
 
@@ -2955,3 +2956,411 @@ impl<'a> MapPatcher<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    // Golden-image tests for the map tile graphics: tiles are rendered under each relevant setting
+    // and compared against the reference images in `testdata/map_tiles`. After an intended change
+    // to the tile graphics, run the tests with `UPDATE_MAP_TILE_GOLDEN=1` to rewrite the reference
+    // images, and review the differences before committing them.
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use maprando_game::parse_map_tile_data;
+    use std::path::{Path, PathBuf};
+
+    // A distinct color for each tile color value, so that differences are easy to spot:
+    const PALETTE: [[u8; 3]; 16] = [
+        [0, 0, 0],       // 0: transparent (black)
+        [80, 80, 160],   // 1: cool room color
+        [160, 160, 255], // 2: hot room color
+        [255, 255, 255], // 3: walls/passages
+        [48, 48, 48],    // 4: opaque black
+        [255, 0, 255],   // 5: unused
+        [232, 120, 0],   // 6: orange door
+        [216, 16, 216],  // 7: pink door
+        [32, 104, 255],  // 8: blue door
+        [255, 255, 0],   // 9: yellow
+        [255, 0, 0],     // 10: red
+        [128, 0, 255],   // 11: purple
+        [96, 0, 0],      // 12: door lock shadow
+        [255, 255, 192], // 13: item dots
+        [56, 255, 56],   // 14: green door
+        [144, 96, 112],  // 15: gray door
+    ];
+
+    // Items placed in the item tiles (cycling through the list), covering each kind of marker:
+    const ITEMS: [Item; 8] = [
+        Item::Missile,
+        Item::Super,
+        Item::PowerBomb,
+        Item::ETank,
+        Item::ReserveTank,
+        Item::Nothing,
+        Item::Varia,
+        Item::Morph,
+    ];
+
+    const DIRECTIONS: [&str; 4] = ["left", "right", "up", "down"];
+
+    type TileGrid = Vec<Vec<Option<MapTile>>>;
+
+    fn load_settings() -> RandomizerSettings {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../data/presets/full-settings/Default.json");
+        let settings_str = std::fs::read_to_string(path).unwrap();
+        let mut settings: RandomizerSettings = serde_json::from_str(&settings_str).unwrap();
+        settings.other_settings.ultra_low_qol = false;
+        settings
+    }
+
+    fn golden_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/map_tiles")
+            .join(format!("{name}.png"))
+    }
+
+    fn render_grid(
+        grid: &TileGrid,
+        settings: &RandomizerSettings,
+        customize_settings: &CustomizeSettings,
+    ) -> RgbaImage {
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut img = RgbaImage::new(width as u32 * 8, grid.len() as u32 * 8);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let Some(tile) = tile else {
+                    continue;
+                };
+                let data = render_tile(tile.clone(), settings, customize_settings).unwrap();
+                for py in 0..8 {
+                    for px in 0..8 {
+                        let [r, g, b] = PALETTE[data[py][px] as usize];
+                        img.put_pixel(
+                            (x * 8 + px) as u32,
+                            (y * 8 + py) as u32,
+                            Rgba([r, g, b, 255]),
+                        );
+                    }
+                }
+            }
+        }
+        img
+    }
+
+    fn check_golden(name: &str, img: &RgbaImage) {
+        let path = golden_path(name);
+        if std::env::var("UPDATE_MAP_TILE_GOLDEN").is_ok() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            img.save(&path).unwrap();
+            return;
+        }
+        let expected = image::open(&path)
+            .unwrap_or_else(|e| panic!("Unable to load {}: {e}", path.display()))
+            .to_rgba8();
+        if &expected != img {
+            let actual_path = std::env::temp_dir().join(format!("map_tiles_{name}.png"));
+            img.save(&actual_path).unwrap();
+            panic!(
+                "Rendered map tiles differ from {}; see {}",
+                path.display(),
+                actual_path.display()
+            );
+        }
+    }
+
+    // Lay out the tiles of every room, left to right in rows, with a blank tile between rooms.
+    fn get_room_grid(settings: &RandomizerSettings) -> TileGrid {
+        const GRID_WIDTH: usize = 64;
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/map_tiles.json");
+        let map_tile_data = parse_map_tile_data(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let mut grid: TileGrid = vec![];
+        let mut row_x = 0;
+        let mut row_y = 0;
+        let mut row_height = 0;
+        let mut item_idx = 0;
+        for room in &map_tile_data {
+            let width = room.map_tiles.iter().map(|t| t.coords.0 + 1).max().unwrap();
+            let height = room.map_tiles.iter().map(|t| t.coords.1 + 1).max().unwrap();
+            if row_x + width > GRID_WIDTH {
+                row_x = 0;
+                row_y += row_height + 1;
+                row_height = 0;
+            }
+            if grid.len() < row_y + height {
+                grid.resize(row_y + height, vec![None; GRID_WIDTH]);
+            }
+            for tile in &room.map_tiles {
+                let mut tile = tile.clone();
+                if tile.interior.is_item() {
+                    let item = ITEMS[item_idx % ITEMS.len()];
+                    tile.interior = apply_item_interior(tile.clone(), item, settings);
+                    item_idx += 1;
+                }
+                let (x, y) = tile.coords;
+                grid[row_y + y][row_x + x] = Some(tile);
+            }
+            row_x += width + 1;
+            row_height = row_height.max(height);
+        }
+        grid
+    }
+
+    fn walled_tile(heated: bool) -> MapTile {
+        MapTile {
+            left: MapTileEdge::Wall,
+            right: MapTileEdge::Wall,
+            top: MapTileEdge::Wall,
+            bottom: MapTileEdge::Wall,
+            heated,
+            ..MapTile::default()
+        }
+    }
+
+    fn open_tile(heated: bool) -> MapTile {
+        MapTile {
+            heated,
+            ..MapTile::default()
+        }
+    }
+
+    #[test]
+    fn room_tiles() {
+        let mut settings = load_settings();
+        let customize_settings = CustomizeSettings::default();
+        for (name, item_markers) in [
+            ("simple", ItemMarkers::Simple),
+            ("majors", ItemMarkers::Majors),
+            ("uniques", ItemMarkers::Uniques),
+            ("3-tiered", ItemMarkers::ThreeTiered),
+            ("4-tiered", ItemMarkers::FourTiered),
+        ] {
+            settings.quality_of_life_settings.item_markers = item_markers;
+            let grid = get_room_grid(&settings);
+            let img = render_grid(&grid, &settings, &customize_settings);
+            check_golden(&format!("rooms_{name}"), &img);
+        }
+    }
+
+    #[test]
+    fn door_lock_tiles() {
+        let mut settings = load_settings();
+        let customize_settings = CustomizeSettings::default();
+        let door_types = [
+            DoorType::Red,
+            DoorType::Green,
+            DoorType::Yellow,
+            DoorType::Beam(BeamType::Charge),
+            DoorType::Beam(BeamType::Ice),
+            DoorType::Beam(BeamType::Wave),
+            DoorType::Beam(BeamType::Spazer),
+            DoorType::Beam(BeamType::Plasma),
+            DoorType::Wall,
+        ];
+        for (name, door_locks_size) in [
+            ("small", DoorLocksSize::Small),
+            ("large", DoorLocksSize::Large),
+        ] {
+            settings.other_settings.door_locks_size = door_locks_size;
+            // One row per door type (with gray doors last), and one column per direction, for
+            // walled and open tiles, each unheated and heated:
+            let mut grid: TileGrid = vec![];
+            for door_type in door_types.iter().copied().map(Some).chain([None]) {
+                let mut row = vec![];
+                for heated in [false, true] {
+                    for base_tile in [walled_tile(heated), open_tile(heated)] {
+                        for direction in DIRECTIONS {
+                            let door = RoomGeometryDoor {
+                                direction: direction.to_string(),
+                                x: 0,
+                                y: 0,
+                                exit_ptr: None,
+                                entrance_ptr: None,
+                                subtype: "normal".to_string(),
+                                offset: None,
+                            };
+                            let tile = match door_type {
+                                Some(door_type) => {
+                                    let locked_door = LockedDoor {
+                                        src_ptr_pair: (None, None),
+                                        dst_ptr_pair: (None, None),
+                                        door_type,
+                                        bidirectional: false,
+                                    };
+                                    apply_door_lock(&base_tile, &locked_door, &door)
+                                }
+                                None => {
+                                    let mut tile = base_tile.clone();
+                                    let edge = MapTileEdge::LockedDoor(DoorLockType::Gray);
+                                    match direction {
+                                        "left" => tile.left = edge,
+                                        "right" => tile.right = edge,
+                                        "up" => tile.top = edge,
+                                        _ => tile.bottom = edge,
+                                    }
+                                    tile
+                                }
+                            };
+                            row.push(Some(tile));
+                        }
+                        row.push(None);
+                    }
+                }
+                grid.push(row);
+                grid.push(vec![]);
+            }
+            let img = render_grid(&grid, &settings, &customize_settings);
+            check_golden(&format!("door_locks_{name}"), &img);
+        }
+    }
+
+    #[test]
+    fn special_tiles() {
+        let settings = load_settings();
+        let mut customize_settings = CustomizeSettings::default();
+        let mut grid: TileGrid = vec![];
+
+        // Special tile types, unheated and heated:
+        let special_types = [
+            MapTileSpecialType::SlopeUpFloorLow,
+            MapTileSpecialType::SlopeUpFloorHigh,
+            MapTileSpecialType::SlopeUpCeilingLow,
+            MapTileSpecialType::SlopeUpCeilingHigh,
+            MapTileSpecialType::SlopeDownFloorLow,
+            MapTileSpecialType::SlopeDownFloorHigh,
+            MapTileSpecialType::SlopeDownCeilingLow,
+            MapTileSpecialType::SlopeDownCeilingHigh,
+            MapTileSpecialType::Tube,
+            MapTileSpecialType::Elevator,
+            MapTileSpecialType::Black,
+        ];
+        for heated in [false, true] {
+            let mut row = vec![];
+            for special_type in special_types {
+                row.push(Some(MapTile {
+                    special_type: Some(special_type),
+                    ..open_tile(heated)
+                }));
+                row.push(None);
+            }
+            grid.push(row);
+            grid.push(vec![]);
+        }
+
+        // Interiors, in walled tiles, unheated and heated (double and hidden items are always
+        // replaced by an item marker before rendering):
+        let interiors = [
+            MapTileInterior::Empty,
+            MapTileInterior::Item,
+            MapTileInterior::ElevatorPlatformHigh,
+            MapTileInterior::ElevatorPlatformLow,
+            MapTileInterior::SaveStation,
+            MapTileInterior::MapStation,
+            MapTileInterior::EnergyRefill,
+            MapTileInterior::AmmoRefill,
+            MapTileInterior::DoubleRefill,
+            MapTileInterior::Ship,
+            MapTileInterior::Event,
+            MapTileInterior::Objective,
+            MapTileInterior::AmmoItem,
+            MapTileInterior::MediumItem,
+            MapTileInterior::MajorItem,
+        ];
+        for heated in [false, true] {
+            let mut row = vec![];
+            for interior in interiors {
+                row.push(Some(MapTile {
+                    interior,
+                    ..walled_tile(heated)
+                }));
+                row.push(None);
+            }
+            grid.push(row);
+            grid.push(vec![]);
+        }
+
+        // Edges, on each side of an otherwise open tile:
+        let edges = [
+            MapTileEdge::Empty,
+            MapTileEdge::QolEmpty,
+            MapTileEdge::Passage,
+            MapTileEdge::QolPassage,
+            MapTileEdge::Door,
+            MapTileEdge::QolDoor,
+            MapTileEdge::Wall,
+            MapTileEdge::QolWall,
+            MapTileEdge::ElevatorEntrance,
+            MapTileEdge::Sand,
+            MapTileEdge::QolSand,
+        ];
+        for direction in DIRECTIONS {
+            let mut row = vec![];
+            for edge in edges {
+                let mut tile = open_tile(false);
+                match direction {
+                    "left" => tile.left = edge,
+                    "right" => tile.right = edge,
+                    "up" => tile.top = edge,
+                    _ => tile.bottom = edge,
+                }
+                row.push(Some(tile));
+                row.push(None);
+            }
+            grid.push(row);
+            grid.push(vec![]);
+        }
+
+        // Liquids at various levels:
+        let liquids = [
+            (MapLiquidType::Water, false),
+            (MapLiquidType::Lava, true),
+            (MapLiquidType::Acid, false),
+            (MapLiquidType::Acid, true),
+        ];
+        for (liquid_type, heated) in liquids {
+            let mut row = vec![];
+            for liquid_level in [0.0, 0.25, 0.5, 0.75] {
+                row.push(Some(MapTile {
+                    liquid_type,
+                    liquid_level: Some(liquid_level),
+                    ..walled_tile(heated)
+                }));
+                row.push(None);
+            }
+            grid.push(row);
+            grid.push(vec![]);
+        }
+        let img = render_grid(&grid, &settings, &customize_settings);
+        check_golden("special_tiles", &img);
+
+        // Area transition markers, as letters and as arrows:
+        let mut grid: TileGrid = vec![];
+        for transition_letters in [true, false] {
+            customize_settings.transition_letters = transition_letters;
+            for area in 0..NUM_AREAS {
+                let mut row = vec![];
+                for direction in [
+                    Direction::Left,
+                    Direction::Right,
+                    Direction::Up,
+                    Direction::Down,
+                ] {
+                    row.push(Some(MapTile {
+                        special_type: Some(MapTileSpecialType::AreaTransition(area, direction)),
+                        ..open_tile(false)
+                    }));
+                    row.push(None);
+                }
+                grid.push(row);
+                grid.push(vec![]);
+            }
+            let img = render_grid(&grid, &settings, &customize_settings);
+            let name = if transition_letters {
+                "area_transitions_letters"
+            } else {
+                "area_transitions_arrows"
+            };
+            check_golden(name, &img);
+            grid.clear();
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod escape_timer;
+pub mod normalize;
 mod run_speed;
 
 use crate::helpers::get_item_priorities;
@@ -6,7 +7,7 @@ use crate::patch::NUM_AREAS;
 use crate::patch::map_tiles::get_objective_tiles;
 use crate::settings::{
     DoorsMode, FillerItemPriority, ItemPlacementStyle, ItemPriorityStrength, KeyItemPriority,
-    MotherBrainFight, Objective, ObjectiveSetting, ProgressionRate, RandomizerSettings,
+    LogicMode, MotherBrainFight, Objective, ObjectiveSetting, ProgressionRate, RandomizerSettings,
     SaveAnimals, SkillAssumptionSettings, StartLocationMode, WallJump,
 };
 use crate::spoiler_log::{
@@ -19,7 +20,7 @@ use crate::traverse::{
 };
 use anyhow::{Context, Result, bail};
 use hashbrown::{HashMap, HashSet};
-use log::info;
+use log::{info, warn};
 use maprando_game::{
     self, AreaIdx, BeamType, BlueOption, BounceMovementType, Capacity, DoorOrientation,
     DoorPtrPair, DoorType, EntranceCondition, ExitCondition, Float, GModeMobility, GModeMode,
@@ -38,6 +39,7 @@ use maprando_game::{
     TechId, TemporaryBlueDirection, TraversalId, VertexId, VertexKey,
 };
 use maprando_logic::{GlobalState, Inventory, LocalState};
+use normalize::normalize_links;
 use rand::SeedableRng;
 use rand::{Rng, seq::SliceRandom};
 use run_speed::{
@@ -81,6 +83,10 @@ pub struct DifficultyConfig {
     pub draygon_proficiency: f32,
     pub ridley_proficiency: f32,
     pub botwoon_proficiency: f32,
+    pub kraid_proficiency: f32,
+    pub crocomire_proficiency: f32,
+    pub spore_spawn_proficiency: f32,
+    pub golden_torizo_proficiency: f32,
     pub mother_brain_proficiency: f32,
 }
 
@@ -137,6 +143,10 @@ impl DifficultyConfig {
             draygon_proficiency: skill.draygon_proficiency,
             ridley_proficiency: skill.ridley_proficiency,
             botwoon_proficiency: skill.botwoon_proficiency,
+            kraid_proficiency: skill.kraid_proficiency,
+            crocomire_proficiency: skill.crocomire_proficiency,
+            spore_spawn_proficiency: skill.spore_spawn_proficiency,
+            golden_torizo_proficiency: skill.golden_torizo_proficiency,
             mother_brain_proficiency: skill.mother_brain_proficiency,
         }
     }
@@ -198,6 +208,19 @@ impl DifficultyConfig {
             draygon_proficiency: f32::min(self.draygon_proficiency, other.draygon_proficiency),
             ridley_proficiency: f32::min(self.ridley_proficiency, other.ridley_proficiency),
             botwoon_proficiency: f32::min(self.botwoon_proficiency, other.botwoon_proficiency),
+            kraid_proficiency: f32::min(self.kraid_proficiency, other.kraid_proficiency),
+            crocomire_proficiency: f32::min(
+                self.crocomire_proficiency,
+                other.crocomire_proficiency,
+            ),
+            spore_spawn_proficiency: f32::min(
+                self.spore_spawn_proficiency,
+                other.spore_spawn_proficiency,
+            ),
+            golden_torizo_proficiency: f32::min(
+                self.golden_torizo_proficiency,
+                other.golden_torizo_proficiency,
+            ),
             mother_brain_proficiency: f32::min(
                 self.mother_brain_proficiency,
                 other.mother_brain_proficiency,
@@ -219,7 +242,7 @@ pub struct Randomizer<'a> {
     pub filler_priority_map: HashMap<Item, FillerItemPriority>,
     pub item_priority_groups: Vec<ItemPriorityGroup>,
     pub difficulty_tiers: &'a [DifficultyConfig],
-    pub base_links_data: &'a LinksDataGroup,
+    pub base_links_data: LinksDataGroup,
     pub seed_links_data: LinksDataGroup,
     pub initial_items_remaining: Vec<usize>, // Corresponds to GameData.items_isv (one count per distinct item name)
     pub next_traversal_number: RefCell<usize>,
@@ -433,9 +456,9 @@ fn remove_some_duplicates<T: Clone + PartialEq + Eq + Hash>(
     out
 }
 
-struct Preprocessor<'a> {
+pub(crate) struct Preprocessor<'a> {
     game_data: &'a GameData,
-    door_map: HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+    pub(crate) door_map: HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
     difficulty: &'a DifficultyConfig,
 }
 
@@ -2903,6 +2926,11 @@ pub fn randomize_doors(
         });
     }
 
+    get_locked_door_data(locked_doors, game_data)
+}
+
+// Index the locked doors by the door nodes and vertices that they affect.
+pub fn get_locked_door_data(locked_doors: Vec<LockedDoor>, game_data: &GameData) -> LockedDoorData {
     let mut locked_door_node_map: HashMap<(RoomId, NodeId), usize> = HashMap::new();
     for (i, door) in locked_doors.iter().enumerate() {
         let (src_room_id, src_node_id) = game_data.door_ptr_pair_map[&door.src_ptr_pair];
@@ -2970,6 +2998,67 @@ pub fn filter_links(
     out
 }
 
+// Item placement without regard to progression, as a vector of the item placed at each item
+// location. The first copy of each item (and every copy of a progressive item) is a key item,
+// which must be placed at an eligible location. In the minimal logic mode, no other real item is
+// placed outside of the eligible locations either: those which don't fit are left out, and every
+// other location gets Nothing. In the no logic mode, the other items fill the remaining locations
+// in the map.
+fn assign_items_without_logic<R: Rng>(
+    initial_items_remaining: &[usize],
+    eligible: &[ItemLocationId],
+    in_map: &[bool],
+    logic_mode: LogicMode,
+    rng: &mut R,
+) -> Result<Vec<Item>> {
+    let mut key_items: Vec<Item> = vec![];
+    let mut other_items: Vec<Item> = vec![];
+    for (item_id, &cnt) in initial_items_remaining.iter().enumerate() {
+        let item = Item::try_from(item_id).unwrap();
+        for i in 0..cnt {
+            if item != Item::Nothing && (i == 0 || item.is_progressive()) {
+                key_items.push(item);
+            } else if item != Item::Nothing || logic_mode != LogicMode::Minimal {
+                other_items.push(item);
+            }
+        }
+    }
+    if key_items.len() > eligible.len() {
+        bail!(
+            "{} eligible item locations for {} key items",
+            eligible.len(),
+            key_items.len()
+        );
+    }
+    key_items.shuffle(rng);
+    other_items.shuffle(rng);
+
+    let mut eligible = eligible.to_vec();
+    eligible.shuffle(rng);
+    let (key_locs, other_eligible_locs) = eligible.split_at(key_items.len());
+    let mut placed_items: Vec<Option<Item>> = vec![None; in_map.len()];
+    for (&loc, &item) in key_locs.iter().zip(key_items.iter()) {
+        placed_items[loc] = Some(item);
+    }
+    let other_locs: Vec<ItemLocationId> = if logic_mode == LogicMode::Minimal {
+        other_eligible_locs.to_vec()
+    } else {
+        let mut locs: Vec<ItemLocationId> = (0..in_map.len())
+            .filter(|&i| in_map[i] && placed_items[i].is_none())
+            .collect();
+        locs.shuffle(rng);
+        locs
+    };
+    let mut other_items = other_items.into_iter();
+    for loc in other_locs {
+        placed_items[loc] = Some(other_items.next().unwrap_or(Item::Nothing));
+    }
+    Ok(placed_items
+        .into_iter()
+        .map(|x| x.unwrap_or(Item::Nothing))
+        .collect())
+}
+
 fn get_minimal_tank_count(difficulty: &DifficultyConfig) -> usize {
     if difficulty.ridley_proficiency < 0.3 {
         12
@@ -3033,6 +3122,23 @@ pub fn get_difficulty_tiers(
     difficulty_tiers
 }
 
+// Flags which are set from the start of the game (internal flags used by the logic).
+pub fn get_initial_flag_vec(settings: &RandomizerSettings, game_data: &GameData) -> Vec<bool> {
+    let mut flag_vec = vec![false; game_data.flag_isv.keys.len()];
+    let tourian_open_idx = game_data.flag_isv.index_by_key["f_TourianOpen"];
+    flag_vec[tourian_open_idx] = true;
+    if settings.quality_of_life_settings.all_items_spawn {
+        let all_items_spawn_idx = game_data.flag_isv.index_by_key["f_AllItemsSpawn"];
+        flag_vec[all_items_spawn_idx] = true;
+    }
+    if settings.quality_of_life_settings.acid_chozo {
+        let acid_chozo_without_space_jump_idx =
+            game_data.flag_isv.index_by_key["f_AcidChozoWithoutSpaceJump"];
+        flag_vec[acid_chozo_without_space_jump_idx] = true;
+    }
+    flag_vec
+}
+
 pub fn get_objectives<R: Rng>(
     settings: &RandomizerSettings,
     map: Option<&Map>,
@@ -3085,7 +3191,7 @@ impl<'r> Randomizer<'r> {
         settings: &'r RandomizerSettings,
         difficulty_tiers: &'r [DifficultyConfig],
         game_data: &'r GameData,
-        base_links_data: &'r LinksDataGroup,
+        base_links_data: &LinksDataGroup,
         _rng: &mut R,
     ) -> Randomizer<'r> {
         let mut available_items: usize = 0;
@@ -3096,6 +3202,13 @@ impl<'r> Randomizer<'r> {
             }
         }
 
+        // The base links are normalized for the hardest difficulty tier, which is also valid for the
+        // other tiers (see `normalize`). Links which aren't possible for it are dropped.
+        let base_links_data = LinksDataGroup::new(
+            normalize_links(&base_links_data.links, &difficulty_tiers[0]),
+            game_data.vertex_isv.keys.len(),
+            0,
+        );
         let preprocessor = Preprocessor::new(game_data, map, &difficulty_tiers[0]);
         let preprocessed_seed_links: Vec<Link> = preprocessor.get_all_door_links();
         info!(
@@ -3125,6 +3238,28 @@ impl<'r> Randomizer<'r> {
             }
         }
 
+        // With progressive items, the items in each sequence are replaced in the pool by copies of the
+        // corresponding progressive pseudo-item:
+        for (progressive_item, enabled) in [
+            (
+                Item::ProgressiveBeam,
+                settings.item_progression_settings.progressive_beams,
+            ),
+            (
+                Item::ProgressiveSuit,
+                settings.item_progression_settings.progressive_suits,
+            ),
+        ] {
+            initial_items_remaining[progressive_item as usize] = 0;
+            if enabled {
+                for &item in progressive_item.progressive_sequence().unwrap() {
+                    initial_items_remaining[progressive_item as usize] +=
+                        initial_items_remaining[item as usize];
+                    initial_items_remaining[item as usize] = 0;
+                }
+            }
+        }
+
         while initial_items_remaining[Item::ETank as usize]
             + initial_items_remaining[Item::ReserveTank as usize]
             < minimal_tank_count
@@ -3182,12 +3317,15 @@ impl<'r> Randomizer<'r> {
 
         let toilet_intersections = Self::get_toilet_intersections(map, game_data);
 
-        let filler_priority_map: HashMap<Item, FillerItemPriority> = settings
+        let mut filler_priority_map: HashMap<Item, FillerItemPriority> = settings
             .item_progression_settings
             .filler_items
             .iter()
             .map(|x| (x.item, x.priority))
             .collect();
+        // Progressive items are always placed as key items:
+        filler_priority_map.insert(Item::ProgressiveBeam, FillerItemPriority::No);
+        filler_priority_map.insert(Item::ProgressiveSuit, FillerItemPriority::No);
 
         let mut item_areas: Vec<AreaIdx> = Vec::new();
         for &(room_id, _) in &game_data.item_locations {
@@ -3211,12 +3349,12 @@ impl<'r> Randomizer<'r> {
             item_priority_groups: get_item_priorities(
                 &settings.item_progression_settings.key_item_priority,
             ),
-            base_links_data,
             seed_links_data: LinksDataGroup::new(
                 preprocessed_seed_links,
                 game_data.vertex_isv.keys.len(),
                 base_links_data.links.len(),
             ),
+            base_links_data,
             difficulty_tiers,
             next_traversal_number: RefCell::new(0),
         }
@@ -3265,20 +3403,74 @@ impl<'r> Randomizer<'r> {
         }
     }
 
-    fn get_initial_flag_vec(&self) -> Vec<bool> {
-        let mut flag_vec = vec![false; self.game_data.flag_isv.keys.len()];
-        let tourian_open_idx = self.game_data.flag_isv.index_by_key["f_TourianOpen"];
-        flag_vec[tourian_open_idx] = true;
-        if self.settings.quality_of_life_settings.all_items_spawn {
-            let all_items_spawn_idx = self.game_data.flag_isv.index_by_key["f_AllItemsSpawn"];
-            flag_vec[all_items_spawn_idx] = true;
-        }
-        if self.settings.quality_of_life_settings.acid_chozo {
-            let acid_chozo_without_space_jump_idx =
-                self.game_data.flag_isv.index_by_key["f_AcidChozoWithoutSpaceJump"];
-            flag_vec[acid_chozo_without_space_jump_idx] = true;
+    // Set the flags for objectives which are derived from the overall state (rather than from
+    // a single event in the game), returning true if any flag was newly set.
+    fn update_derived_objective_flags(&self, state: &mut RandomizationState) -> bool {
+        let mut any_update = false;
+        for obj in &self.objectives {
+            if !obj.is_derived() {
+                continue;
+            }
+            let flag_id = self.game_data.flag_isv.index_by_key[obj.get_flag_name()];
+            if state.global_state.flags[flag_id] {
+                continue;
+            }
+            let complete = match obj {
+                Objective::ItemPercentage => {
+                    let num_items: usize = self
+                        .initial_items_remaining
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| i != Item::Nothing as usize)
+                        .map(|(_, &cnt)| cnt)
+                        .sum();
+                    let num_collected = state
+                        .item_location_state
+                        .iter()
+                        .filter(|x| x.collected && x.placed_item != Some(Item::Nothing))
+                        .count();
+                    let pct = self.settings.objective_settings.item_percentage as usize;
+                    num_collected * 100 >= num_items * pct
+                }
+                Objective::AllEnergyTanks => {
+                    state.items_remaining[Item::ETank as usize] == 0
+                        && state
+                            .item_location_state
+                            .iter()
+                            .all(|x| x.collected || x.placed_item != Some(Item::ETank))
+                }
+                Objective::AllMapStations => self
+                    .game_data
+                    .map_station_locations
+                    .iter()
+                    .zip(self.game_data.map_station_flag_ids.iter())
+                    .all(|((room_id, _), &map_flag_id)| {
+                        let room_idx = self.game_data.room_idx_by_id[room_id];
+                        !self.map.room_mask[room_idx] || state.global_state.flags[map_flag_id]
+                    }),
+                Objective::BossCount => {
+                    let num_defeated = [
+                        Objective::Kraid,
+                        Objective::Phantoon,
+                        Objective::Draygon,
+                        Objective::Ridley,
+                    ]
+                    .iter()
+                    .filter(|x| {
+                        state.global_state.flags
+                            [self.game_data.flag_isv.index_by_key[x.get_flag_name()]]
+                    })
+                    .count();
+                    num_defeated as i32 >= self.settings.objective_settings.boss_count
+                }
+                _ => panic!("unexpected derived objective: {obj:?}"),
+            };
+            if complete {
+                state.global_state.flags[flag_id] = true;
+                any_update = true;
+            }
         }
-        flag_vec
+        any_update
     }
 
     pub fn update_reachability(
@@ -3287,7 +3479,7 @@ impl<'r> Randomizer<'r> {
         traverser_pair: &mut TraverserPair,
     ) {
         traverser_pair.forward.traverse(
-            self.base_links_data,
+            &self.base_links_data,
             &self.seed_links_data,
             &state.global_state,
             self.settings,
@@ -3299,7 +3491,7 @@ impl<'r> Randomizer<'r> {
             state.step_num,
         );
         traverser_pair.reverse.traverse(
-            self.base_links_data,
+            &self.base_links_data,
             &self.seed_links_data,
             &state.global_state,
             self.settings,
@@ -3386,15 +3578,22 @@ impl<'r> Randomizer<'r> {
         num_oneway_reachable: usize,
     ) -> (usize, usize) {
         let num_items_to_place = num_bireachable + num_oneway_reachable;
-        let filtered_item_precedence: Vec<Item> = state
+        let num_key_items_remaining: usize = state
             .item_precedence
             .iter()
-            .copied()
-            .filter(|&item| {
-                state.items_remaining[item as usize] == self.initial_items_remaining[item as usize]
+            .map(|&item| {
+                if item.is_progressive() {
+                    // Each copy of a progressive item grants a different item, so each counts as a key item:
+                    state.items_remaining[item as usize]
+                } else if state.items_remaining[item as usize]
+                    == self.initial_items_remaining[item as usize]
+                {
+                    1
+                } else {
+                    0
+                }
             })
-            .collect();
-        let num_key_items_remaining = filtered_item_precedence.len();
+            .sum();
         let num_items_remaining: usize = state.items_remaining.iter().sum();
         let mut num_key_items_to_place =
             match self.settings.item_progression_settings.progression_rate {
@@ -3549,7 +3748,11 @@ impl<'r> Randomizer<'r> {
                         .stop_item_placement_early
                         && item == Item::Nothing)
                 {
-                    if self.settings.item_progression_settings.progression_rate
+                    if item.is_progressive() {
+                        // Each copy of a progressive item grants a different item, so the next copy
+                        // keeps its same position in the key item priority even after others were placed.
+                        unplaced_items.push(item);
+                    } else if self.settings.item_progression_settings.progression_rate
                         == ProgressionRate::Slow
                     {
                         // With Slow progression, items that have been placed before (e.g. an ETank) are treated like any other
@@ -3617,7 +3820,7 @@ impl<'r> Randomizer<'r> {
             let difficulty = &self.difficulty_tiers[tier];
 
             traverser.traverse(
-                self.base_links_data,
+                &self.base_links_data,
                 &self.seed_links_data,
                 &state.global_state,
                 self.settings,
@@ -3670,7 +3873,7 @@ impl<'r> Randomizer<'r> {
         other_locations: &[ItemLocationId],
         key_items_to_place: &[Item],
         other_items_to_place: &[Item],
-        traverser_pair: &TraverserPair,
+        traverser_pair: &mut TraverserPair,
     ) {
         info!(
             "[attempt {attempt_num_rando}] Placing {key_items_to_place:?}, {other_items_to_place:?}"
@@ -3687,14 +3890,16 @@ impl<'r> Randomizer<'r> {
         let mut new_bireachable_locations: Vec<ItemLocationId> = bireachable_locations.to_vec();
         let mut tier_vec: Vec<usize> = vec![];
         if self.difficulty_tiers.len() > 1 && !skip_hard_placement {
-            // TODO: avoid doing this clone:
-            let mut past_traverser = traverser_pair.forward.clone();
+            // Temporarily rewind the forward traversal to before the previous step:
+            let past_traverser = &mut traverser_pair.forward;
             let step = past_traverser.past_steps.last().unwrap().step_num;
-            while past_traverser.past_steps.len() > 1
-                && past_traverser.past_steps.last().unwrap().step_num >= step - 1
+            let mut num_past_steps = past_traverser.past_steps.len();
+            while num_past_steps > 1
+                && past_traverser.past_steps[num_past_steps - 1].step_num >= step - 1
             {
-                past_traverser.pop_step();
+                num_past_steps -= 1;
             }
+            let checkpoint = past_traverser.rewind(num_past_steps);
             let mut new_key_areas: Vec<AreaIdx> = Vec::new();
             for i in 0..key_items_to_place.len() {
                 let (hard_idx, tier) = if key_items_to_place.len() > 1 {
@@ -3704,7 +3909,7 @@ impl<'r> Randomizer<'r> {
                     self.find_hard_location(
                         new_state,
                         &new_bireachable_locations[i..],
-                        &mut past_traverser,
+                        past_traverser,
                         &state.last_key_areas,
                     )
                 } else {
@@ -3713,7 +3918,7 @@ impl<'r> Randomizer<'r> {
                     self.find_hard_location(
                         state,
                         &new_bireachable_locations[i..],
-                        &mut past_traverser,
+                        past_traverser,
                         &state.last_key_areas,
                     )
                 };
@@ -3731,6 +3936,7 @@ impl<'r> Randomizer<'r> {
                 new_key_areas.push(self.item_areas[hard_loc]);
             }
             new_state.last_key_areas = new_key_areas;
+            past_traverser.restore(checkpoint);
         }
 
         let mut all_locations: Vec<ItemLocationId> = Vec::new();
@@ -4006,13 +4212,13 @@ impl<'r> Randomizer<'r> {
         }
     }
 
-    fn step<R: Rng + Clone>(
+    // Obtain the flags and unlock the doors which have become bireachable, updating the
+    // reachability until nothing more is obtained.
+    fn update_flags_and_doors(
         &self,
-        attempt_num_rando: usize,
         state: &mut RandomizationState,
         traverser_pair: &mut TraverserPair,
-        rng: &mut R,
-    ) -> Result<bool> {
+    ) {
         loop {
             let mut any_update = false;
             for (i, &flag_id) in self.game_data.flag_ids.iter().enumerate() {
@@ -4039,12 +4245,25 @@ impl<'r> Randomizer<'r> {
                     state.global_state.doors_unlocked[i] = true;
                 }
             }
+            if self.update_derived_objective_flags(state) {
+                any_update = true;
+            }
             if any_update {
                 self.update_reachability(state, traverser_pair);
             } else {
                 break;
             }
         }
+    }
+
+    fn step<R: Rng + Clone>(
+        &self,
+        attempt_num_rando: usize,
+        state: &mut RandomizationState,
+        traverser_pair: &mut TraverserPair,
+        rng: &mut R,
+    ) -> Result<bool> {
+        self.update_flags_and_doors(state, traverser_pair);
 
         if self
             .settings
@@ -4162,7 +4381,9 @@ impl<'r> Randomizer<'r> {
         // Include collectible items in the middle:
         for (step, step_summary) in spoiler_log.summary.iter().enumerate() {
             for item_info in step_summary.items.iter() {
-                let item = Item::try_from(item_info.item.as_str()).unwrap();
+                let item = Item::try_from(item_info.item.as_str())
+                    .unwrap()
+                    .resolve_progressive(|x| items_set.contains(&x));
                 if !items_set.contains(&item) {
                     item_spoiler_info.push(EssentialItemSpoilerInfo {
                         item,
@@ -4179,7 +4400,9 @@ impl<'r> Randomizer<'r> {
             if loc.item == "Nothing" {
                 continue;
             }
-            let item = Item::try_from(loc.item.as_str()).unwrap();
+            let item = Item::try_from(loc.item.as_str())
+                .unwrap()
+                .resolve_progressive(|x| items_set.contains(&x));
             if !items_set.contains(&item) {
                 item_spoiler_info.push(EssentialItemSpoilerInfo {
                     item,
@@ -4200,6 +4423,9 @@ impl<'r> Randomizer<'r> {
                 continue;
             }
             let item = Item::try_from(name).unwrap();
+            if item.is_progressive() {
+                continue;
+            }
             if !items_set.contains(&item) {
                 item_spoiler_info.push(EssentialItemSpoilerInfo {
                     item,
@@ -4338,6 +4564,23 @@ impl<'r> Randomizer<'r> {
         item_precedence[reserve_idx] = Item::ETank;
     }
 
+    fn apply_progressive_item_precedence(&self, item_precedence: &mut Vec<Item>) {
+        // Each progressive item takes the place of the highest-priority item in its sequence,
+        // and the other items in the sequence are removed, since they will not be placed directly.
+        for progressive_item in [Item::ProgressiveBeam, Item::ProgressiveSuit] {
+            if self.initial_items_remaining[progressive_item as usize] == 0 {
+                continue;
+            }
+            let seq = progressive_item.progressive_sequence().unwrap();
+            let Some(idx) = item_precedence.iter().position(|x| seq.contains(x)) else {
+                item_precedence.push(progressive_item);
+                continue;
+            };
+            item_precedence[idx] = progressive_item;
+            item_precedence.retain(|x| !seq.contains(x));
+        }
+    }
+
     fn apply_spazer_plasma_priority(&self, item_precedence: &mut [Item]) {
         let spazer_idx_opt = item_precedence.iter().position(|&x| x == Item::Spazer);
         let plasma_idx_opt = item_precedence.iter().position(|&x| x == Item::Plasma);
@@ -4360,16 +4603,6 @@ impl<'r> Randomizer<'r> {
         traverser_pair: &mut TraverserPair,
     ) -> Result<StartLocationData> {
         if self.settings.start_location_settings.mode == StartLocationMode::Ship {
-            let ship_start = StartLocation {
-                name: "Ship".to_string(),
-                room_id: 8,
-                node_id: 5,
-                door_load_node_id: Some(2),
-                x: 72.0,
-                y: 69.5,
-                ..StartLocation::default()
-            };
-
             let ship_hub = HubLocation {
                 room_id: 8,
                 node_id: 5,
@@ -4377,7 +4610,7 @@ impl<'r> Randomizer<'r> {
             };
 
             return Ok(StartLocationData {
-                start_location: ship_start,
+                start_location: StartLocation::ship(),
                 hub_location: ship_hub,
                 hub_obtain_route: vec![],
                 hub_return_route: vec![],
@@ -4446,7 +4679,7 @@ impl<'r> Randomizer<'r> {
                 .forward
                 .add_origin(local, start_vertex_id, &global);
             traverser_pair.forward.traverse(
-                self.base_links_data,
+                &self.base_links_data,
                 &self.seed_links_data,
                 &global,
                 self.settings,
@@ -4476,7 +4709,7 @@ impl<'r> Randomizer<'r> {
                 .reverse
                 .add_origin(LocalState::full(), start_vertex_id, &global);
             traverser_pair.reverse.traverse(
-                self.base_links_data,
+                &self.base_links_data,
                 &self.seed_links_data,
                 &global,
                 self.settings,
@@ -4582,7 +4815,7 @@ impl<'r> Randomizer<'r> {
                 collectible_super_packs: 0,
                 collectible_power_bomb_packs: 0,
             },
-            flags: self.get_initial_flag_vec(),
+            flags: get_initial_flag_vec(self.settings, self.game_data),
             doors_unlocked: vec![false; self.locked_door_data.locked_doors.len()],
             weapon_mask,
         };
@@ -4685,6 +4918,7 @@ impl<'r> Randomizer<'r> {
                 link_idxs: vec![],
                 local_states: vec![],
             },
+            unreachable_items: vec![],
         };
 
         let randomization = Randomization {
@@ -4704,6 +4938,148 @@ impl<'r> Randomizer<'r> {
         Ok((randomization, spoiler_log))
     }
 
+    fn add_hub_origins(&self, state: &RandomizationState, traverser_pair: &mut TraverserPair) {
+        let start_vertex_id = self.game_data.vertex_isv.index_by_key[&VertexKey {
+            room_id: state.hub_location.room_id,
+            node_id: state.hub_location.node_id,
+            obstacle_mask: 0,
+            actions: vec![],
+        }];
+        traverser_pair.forward.add_origin(
+            state.starting_local_state,
+            start_vertex_id,
+            &state.global_state,
+        );
+        traverser_pair.forward.finish_step(1);
+        traverser_pair
+            .reverse
+            .add_origin(LocalState::full(), start_vertex_id, &state.global_state);
+        traverser_pair.reverse.finish_step(1);
+    }
+
+    // Item locations which are bireachable with all items collected (and all obtainable flags and
+    // doors), as used by the minimal logic mode.
+    fn get_all_items_bireachable_locations(
+        &self,
+        state: &RandomizationState,
+    ) -> Result<Vec<ItemLocationId>> {
+        let mut full_state = state.clone();
+        for (item_id, &cnt) in self.initial_items_remaining.iter().enumerate() {
+            let item = Item::try_from(item_id).unwrap();
+            for _ in 0..cnt {
+                full_state.global_state.collect(
+                    item,
+                    self.game_data,
+                    self.settings
+                        .item_progression_settings
+                        .ammo_collect_fraction,
+                    &self.difficulty_tiers[0].tech,
+                    &mut full_state.starting_local_state,
+                );
+            }
+        }
+        let num_vertices = self.game_data.vertex_isv.keys.len();
+        let mut traverser_pair = TraverserPair {
+            forward: Traverser::new(num_vertices, false, &full_state.global_state),
+            reverse: Traverser::new(num_vertices, true, &full_state.global_state),
+        };
+        self.add_hub_origins(&full_state, &mut traverser_pair);
+        self.update_reachability(&mut full_state, &mut traverser_pair);
+        self.update_flags_and_doors(&mut full_state, &mut traverser_pair);
+        if !self.is_game_beatable(&full_state) {
+            bail!("Game not beatable with all items");
+        }
+        Ok((0..full_state.item_location_state.len())
+            .filter(|&i| {
+                full_state.item_location_state[i]
+                    .bireachable_traversal
+                    .is_some()
+            })
+            .collect())
+    }
+
+    // Item placement for the minimal logic and no logic modes: items are placed at random,
+    // ignoring the progression (see `assign_items_without_logic`). The eligible locations are
+    // those bireachable with all items in the minimal logic mode, or any location in the map in
+    // the no logic mode. The items are then collected according to the logic for as long as
+    // possible, to produce the spoiler log steps; the rest are listed as unreachable.
+    fn place_items_without_logic<R: Rng>(
+        &self,
+        attempt_num_rando: usize,
+        state: &mut RandomizationState,
+        traverser_pair: &mut TraverserPair,
+        rng: &mut R,
+    ) -> Result<()> {
+        let in_map: Vec<bool> = self
+            .game_data
+            .item_locations
+            .iter()
+            .map(|&(room_id, _)| self.map.room_mask[self.game_data.room_idx_by_id[&room_id]])
+            .collect();
+        let eligible: Vec<ItemLocationId> = match self.settings.item_progression_settings.logic_mode
+        {
+            LogicMode::Minimal => self
+                .get_all_items_bireachable_locations(state)
+                .with_context(|| format!("[attempt {attempt_num_rando}] Attempt failed"))?,
+            _ => {
+                warn!(
+                    "[attempt {attempt_num_rando}] Placing items without logic: the game may not be beatable"
+                );
+                (0..in_map.len()).filter(|&i| in_map[i]).collect()
+            }
+        };
+        let placed_items = assign_items_without_logic(
+            &self.initial_items_remaining,
+            &eligible,
+            &in_map,
+            self.settings.item_progression_settings.logic_mode,
+            rng,
+        )
+        .with_context(|| format!("[attempt {attempt_num_rando}] Attempt failed"))?;
+        for (loc_state, item) in state.item_location_state.iter_mut().zip(placed_items) {
+            loc_state.placed_item = Some(item);
+        }
+        state.items_remaining.fill(0);
+
+        loop {
+            self.update_flags_and_doors(state, traverser_pair);
+            let mut any_collected = false;
+            for i in 0..state.item_location_state.len() {
+                let loc_state = &mut state.item_location_state[i];
+                if loc_state.collected || loc_state.bireachable_traversal.is_none() {
+                    continue;
+                }
+                loc_state.collected = true;
+                any_collected = true;
+                state.global_state.collect(
+                    loc_state.placed_item.unwrap(),
+                    self.game_data,
+                    self.settings
+                        .item_progression_settings
+                        .ammo_collect_fraction,
+                    &self.difficulty_tiers[0].tech,
+                    &mut state.starting_local_state,
+                );
+            }
+            if !any_collected {
+                break;
+            }
+            state.step_num += 1;
+            self.update_reachability(state, traverser_pair);
+        }
+        let cnt_collected = state
+            .item_location_state
+            .iter()
+            .filter(|x| x.collected)
+            .count();
+        info!(
+            "[attempt {attempt_num_rando}] Placed items without logic: steps={}, collected={cnt_collected}, beatable={}",
+            state.step_num,
+            self.is_game_beatable(state)
+        );
+        Ok(())
+    }
+
     fn is_game_beatable(&self, state: &RandomizationState) -> bool {
         for (i, &flag_id) in self.game_data.flag_ids.iter().enumerate() {
             if flag_id == self.game_data.mother_brain_defeated_flag_id
@@ -4775,6 +5151,7 @@ impl<'r> Randomizer<'r> {
                 .item_priority_strength,
             &mut rng,
         );
+        self.apply_progressive_item_precedence(&mut item_precedence);
         if self.settings.item_progression_settings.spazer_before_plasma {
             self.apply_spazer_plasma_priority(&mut item_precedence);
         }
@@ -4799,23 +5176,23 @@ impl<'r> Randomizer<'r> {
             global_state: initial_global_state,
             last_key_areas: Vec::new(),
         };
-        let start_vertex_id = self.game_data.vertex_isv.index_by_key[&VertexKey {
-            room_id: state.hub_location.room_id,
-            node_id: state.hub_location.node_id,
-            obstacle_mask: 0,
-            actions: vec![],
-        }];
-        traverser_pair.forward.add_origin(
-            initial_local_state,
-            start_vertex_id,
-            &state.global_state,
-        );
-        traverser_pair.forward.finish_step(1);
-        traverser_pair
-            .reverse
-            .add_origin(LocalState::full(), start_vertex_id, &state.global_state);
-        traverser_pair.reverse.finish_step(1);
+        self.add_hub_origins(&state, &mut traverser_pair);
         self.update_reachability(&mut state, &mut traverser_pair);
+        if self.settings.item_progression_settings.logic_mode != LogicMode::Standard {
+            self.place_items_without_logic(
+                attempt_num_rando,
+                &mut state,
+                &mut traverser_pair,
+                &mut rng,
+            )?;
+            return self.get_randomization(
+                &state,
+                seed,
+                display_seed,
+                &mut rng,
+                &mut traverser_pair,
+            );
+        }
         if !state
             .item_location_state
             .iter()
@@ -4900,6 +5277,26 @@ impl<'r> Randomizer<'r> {
                             );
                         }
                     }
+                    // Each copy of a progressive item grants a different item, so all of them must be collectible.
+                    for (i, item_loc_state) in state.item_location_state.iter().enumerate() {
+                        if let Some(item) = item_loc_state.placed_item
+                            && item.is_progressive()
+                            && !item_loc_state.collected
+                        {
+                            bail!(
+                                "[attempt {attempt_num_rando}] Attempt failed: Key items not all collectible, missing {:?} at {:?}",
+                                item,
+                                self.game_data.item_locations[i]
+                            );
+                        }
+                    }
+                    for progressive_item in [Item::ProgressiveBeam, Item::ProgressiveSuit] {
+                        if state.items_remaining[progressive_item as usize] > 0 {
+                            bail!(
+                                "[attempt {attempt_num_rando}] Attempt failed: Key items not all collectible, missing {progressive_item:?}"
+                            );
+                        }
+                    }
 
                     if self.settings.map_layout != "Small" {
                         // Check that Phantoon can be defeated. This is to rule out the possibility that Phantoon may be locked
@@ -4943,3 +5340,91 @@ impl<'r> Randomizer<'r> {
         self.get_randomization(&state, seed, display_seed, &mut rng, &mut traverser_pair)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // An item pool with 28 key items and 107 real items in total.
+    fn get_item_pool() -> Vec<usize> {
+        let mut items = vec![1; Item::ProgressiveSuit as usize + 1];
+        items[Item::ETank as usize] = 14;
+        items[Item::Missile as usize] = 46;
+        items[Item::Super as usize] = 10;
+        items[Item::PowerBomb as usize] = 10;
+        items[Item::ReserveTank as usize] = 4;
+        items[Item::ProgressiveBeam as usize] = 4;
+        items[Item::ProgressiveSuit as usize] = 2;
+        items[Item::Nothing as usize] = 5;
+        items
+    }
+
+    #[test]
+    fn test_minimal_logic_placement() {
+        let in_map: Vec<bool> = (0..100).map(|i| i % 10 != 9).collect();
+        let eligible: Vec<ItemLocationId> = (0..100).filter(|&i| i % 10 < 4).collect();
+        for seed in 0..20 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let placed_items = assign_items_without_logic(
+                &get_item_pool(),
+                &eligible,
+                &in_map,
+                LogicMode::Minimal,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(placed_items.len(), in_map.len());
+            for (loc, &item) in placed_items.iter().enumerate() {
+                if !eligible.contains(&loc) {
+                    assert_eq!(item, Item::Nothing, "{item:?} placed at ineligible {loc}");
+                }
+            }
+            for item in [Item::Morph, Item::Varia, Item::ProgressiveBeam] {
+                assert!(placed_items.contains(&item));
+            }
+            let progressive_beams = placed_items
+                .iter()
+                .filter(|&&x| x == Item::ProgressiveBeam)
+                .count();
+            assert_eq!(progressive_beams, 4);
+        }
+
+        // The key items must all fit in the eligible locations:
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let few_eligible: Vec<ItemLocationId> = (0..20).collect();
+        assert!(
+            assign_items_without_logic(
+                &get_item_pool(),
+                &few_eligible,
+                &in_map,
+                LogicMode::Minimal,
+                &mut rng,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_logic_placement() {
+        let in_map: Vec<bool> = (0..120).map(|i| i % 10 != 9).collect();
+        let eligible: Vec<ItemLocationId> = (0..120).filter(|&i| in_map[i]).collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let placed_items = assign_items_without_logic(
+            &get_item_pool(),
+            &eligible,
+            &in_map,
+            LogicMode::NoLogic,
+            &mut rng,
+        )
+        .unwrap();
+        let num_real_items = placed_items.iter().filter(|&&x| x != Item::Nothing).count();
+        // The 108 locations in the map are filled from the pool, of which only 5 are Nothing:
+        assert!(num_real_items >= 103);
+        for (loc, &item) in placed_items.iter().enumerate() {
+            if !in_map[loc] {
+                assert_eq!(item, Item::Nothing);
+            }
+        }
+    }
+}
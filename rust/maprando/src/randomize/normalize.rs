@@ -0,0 +1,314 @@
+// Normalization of processed link requirements, applied after `filter_links`. This shrinks the
+// requirement trees that `apply_requirement` has to walk during traversal:
+// - nested And/Or are flattened, and Free/Never terms collapsed,
+// - tech and notables not enabled in the difficulty become Never,
+// - duplicate checks are removed, and Capacity checks of the same kind keep only the largest,
+// - adjacent Missiles/Supers/PowerBombs requirements of the same kind are summed,
+// - Or branches which are dominated by another branch (needing the same resource-consuming
+//   terms plus extra checks) are removed,
+// - checks common to every branch of an Or are hoisted out of it.
+//
+// Only "pure" checks (ones which depend on the inventory, flags, settings, and difficulty but not
+// on the local state) are ever reordered or dropped, so the result of `apply_requirement` is
+// unchanged in both forward and reverse traversal, for the given difficulty or any difficulty
+// whose tech and notables are a subset of it (as with the difficulty tiers). The one exception is
+// an Or containing Free, which becomes Free, the same as `make_or` already does when the
+// requirements are built.
+
+use maprando_game::{Link, Requirement};
+
+use super::DifficultyConfig;
+
+// Merge `b` into `a` if both are Capacity checks of the same kind, keeping the larger one.
+fn merge_capacity(a: &mut Requirement, b: &Requirement) -> bool {
+    match (a, b) {
+        (Requirement::MissilesCapacity(x), Requirement::MissilesCapacity(y))
+        | (Requirement::SupersCapacity(x), Requirement::SupersCapacity(y))
+        | (Requirement::PowerBombsCapacity(x), Requirement::PowerBombsCapacity(y))
+        | (Requirement::RegularEnergyCapacity(x), Requirement::RegularEnergyCapacity(y))
+        | (Requirement::ReserveEnergyCapacity(x), Requirement::ReserveEnergyCapacity(y)) => {
+            *x = (*x).max(*y);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Merge `b` into `a` if both consume ammo of the same kind, summing the amounts. This is only
+// valid if nothing in between them changes the local state.
+fn merge_ammo(a: &mut Requirement, b: &Requirement) -> bool {
+    match (a, b) {
+        (Requirement::Missiles(x), Requirement::Missiles(y))
+        | (Requirement::Supers(x), Requirement::Supers(y))
+        | (Requirement::PowerBombs(x), Requirement::PowerBombs(y)) => {
+            *x += *y;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn and_terms(req: &Requirement) -> Vec<Requirement> {
+    match req {
+        Requirement::And(reqs) => reqs.clone(),
+        Requirement::Free => vec![],
+        other => vec![other.clone()],
+    }
+}
+
+fn normalize_and(reqs: Vec<Requirement>) -> Requirement {
+    let mut flat: Vec<Requirement> = vec![];
+    for req in reqs {
+        match req {
+            Requirement::And(sub_reqs) => flat.extend(sub_reqs),
+            Requirement::Never => return Requirement::Never,
+            Requirement::Free => {}
+            req => flat.push(req),
+        }
+    }
+    let mut out: Vec<Requirement> = vec![];
+    for req in flat {
+        if req.is_pure() {
+            if out.contains(&req) || out.iter_mut().any(|x| merge_capacity(x, &req)) {
+                continue;
+            }
+        } else if let Some(last) = out.iter_mut().rev().find(|x| !x.is_pure()) {
+            // Pure checks in between don't affect the merge:
+            if merge_ammo(last, &req) {
+                continue;
+            }
+        }
+        out.push(req);
+    }
+    match out.len() {
+        0 => Requirement::Free,
+        1 => out.pop().unwrap(),
+        _ => Requirement::And(out),
+    }
+}
+
+// Whether branch `a` dominates branch `b`: they have the same sequence of terms which depend on
+// or modify the local state, and the pure checks of `a` are a subset of those of `b`. Then
+// whenever `b` passes, `a` also passes with the same resulting state.
+fn dominates(a: &[Requirement], b: &[Requirement]) -> bool {
+    let a_impure = a.iter().filter(|x| !x.is_pure());
+    let b_impure = b.iter().filter(|x| !x.is_pure());
+    a_impure.eq(b_impure) && a.iter().filter(|x| x.is_pure()).all(|x| b.contains(x))
+}
+
+fn normalize_or(reqs: Vec<Requirement>) -> Requirement {
+    let mut branches: Vec<Requirement> = vec![];
+    for req in reqs {
+        match req {
+            Requirement::Or(sub_reqs) => {
+                for r in sub_reqs {
+                    if !branches.contains(&r) {
+                        branches.push(r);
+                    }
+                }
+            }
+            Requirement::Never => {}
+            Requirement::Free => return Requirement::Free,
+            req => {
+                if !branches.contains(&req) {
+                    branches.push(req);
+                }
+            }
+        }
+    }
+
+    // Remove dominated branches. If two branches dominate each other (i.e. they differ only by
+    // the order of their pure checks), the first one is kept.
+    let terms: Vec<Vec<Requirement>> = branches.iter().map(and_terms).collect();
+    let mut keep = vec![true; branches.len()];
+    for j in 0..branches.len() {
+        for i in 0..branches.len() {
+            if i == j || !keep[i] || !dominates(&terms[i], &terms[j]) {
+                continue;
+            }
+            if i < j || !dominates(&terms[j], &terms[i]) {
+                keep[j] = false;
+                break;
+            }
+        }
+    }
+    let terms: Vec<Vec<Requirement>> = terms
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(t, _)| t)
+        .collect();
+
+    if terms.len() == 1 {
+        return normalize_and(terms.into_iter().next().unwrap());
+    }
+    if terms.is_empty() {
+        return Requirement::Never;
+    }
+
+    // Hoist pure checks which are common to all branches:
+    let common: Vec<Requirement> = terms[0]
+        .iter()
+        .filter(|x| x.is_pure() && terms[1..].iter().all(|t| t.contains(x)))
+        .cloned()
+        .collect();
+    if common.is_empty() {
+        return Requirement::Or(terms.into_iter().map(normalize_and).collect());
+    }
+    let reduced: Vec<Requirement> = terms
+        .into_iter()
+        .map(|t| normalize_and(t.into_iter().filter(|x| !common.contains(x)).collect()))
+        .collect();
+    let mut out = common;
+    out.push(normalize_or(reduced));
+    normalize_and(out)
+}
+
+pub fn normalize_requirement(
+    req: &Requirement,
+    difficulty: Option<&DifficultyConfig>,
+) -> Requirement {
+    match req {
+        Requirement::Tech(tech_idx) => match difficulty {
+            Some(d) if !d.tech[*tech_idx] => Requirement::Never,
+            _ => req.clone(),
+        },
+        Requirement::Notable(notable_idx) => match difficulty {
+            Some(d) if !d.notables[*notable_idx] => Requirement::Never,
+            _ => req.clone(),
+        },
+        Requirement::And(reqs) => normalize_and(
+            reqs.iter()
+                .map(|r| normalize_requirement(r, difficulty))
+                .collect(),
+        ),
+        Requirement::Or(reqs) => normalize_or(
+            reqs.iter()
+                .map(|r| normalize_requirement(r, difficulty))
+                .collect(),
+        ),
+        Requirement::Farm {
+            requirement,
+            enemy_drops,
+            enemy_drops_buffed,
+            full_energy,
+            full_missiles,
+            full_supers,
+            full_power_bombs,
+        } => Requirement::Farm {
+            requirement: Box::new(normalize_requirement(requirement, difficulty)),
+            enemy_drops: enemy_drops.clone(),
+            enemy_drops_buffed: enemy_drops_buffed.clone(),
+            full_energy: *full_energy,
+            full_missiles: *full_missiles,
+            full_supers: *full_supers,
+            full_power_bombs: *full_power_bombs,
+        },
+        Requirement::UnlockDoor {
+            room_id,
+            node_id,
+            requirement_red,
+            requirement_green,
+            requirement_yellow,
+            requirement_charge,
+        } => Requirement::UnlockDoor {
+            room_id: *room_id,
+            node_id: *node_id,
+            requirement_red: Box::new(normalize_requirement(requirement_red, difficulty)),
+            requirement_green: Box::new(normalize_requirement(requirement_green, difficulty)),
+            requirement_yellow: Box::new(normalize_requirement(requirement_yellow, difficulty)),
+            requirement_charge: Box::new(normalize_requirement(requirement_charge, difficulty)),
+        },
+        _ => req.clone(),
+    }
+}
+
+// Normalize the requirements of links (typically the output of `filter_links`), dropping links
+// which become impossible.
+pub fn normalize_links(links: &[Link], difficulty: &DifficultyConfig) -> Vec<Link> {
+    let mut out = vec![];
+    for link in links {
+        let requirement = normalize_requirement(&link.requirement, Some(difficulty));
+        if requirement != Requirement::Never {
+            out.push(Link {
+                requirement,
+                ..link.clone()
+            });
+        }
+    }
+    out
+}
+
+// Number of nodes in a requirement tree, as a rough measure of its evaluation cost.
+pub fn requirement_size(req: &Requirement) -> usize {
+    match req {
+        Requirement::And(reqs) | Requirement::Or(reqs) => {
+            1 + reqs.iter().map(requirement_size).sum::<usize>()
+        }
+        Requirement::Farm { requirement, .. } => 1 + requirement_size(requirement),
+        Requirement::UnlockDoor {
+            requirement_red,
+            requirement_green,
+            requirement_yellow,
+            requirement_charge,
+            ..
+        } => {
+            1 + requirement_size(requirement_red)
+                + requirement_size(requirement_green)
+                + requirement_size(requirement_yellow)
+                + requirement_size(requirement_charge)
+        }
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_requirement() {
+        // Duplicate checks are removed and adjacent ammo requirements summed:
+        let req = Requirement::And(vec![
+            Requirement::Item(1),
+            Requirement::Missiles(5),
+            Requirement::Item(1),
+            Requirement::Missiles(10),
+            Requirement::MissilesCapacity(5),
+            Requirement::MissilesCapacity(15),
+        ]);
+        assert_eq!(
+            normalize_requirement(&req, None),
+            Requirement::And(vec![
+                Requirement::Item(1),
+                Requirement::Missiles(15),
+                Requirement::MissilesCapacity(15),
+            ])
+        );
+
+        // A branch needing an extra check is dominated, and common checks are hoisted:
+        let req = Requirement::Or(vec![
+            Requirement::And(vec![Requirement::Item(1), Requirement::Item(2)]),
+            Requirement::And(vec![
+                Requirement::Item(1),
+                Requirement::Item(2),
+                Requirement::Item(3),
+            ]),
+            Requirement::And(vec![Requirement::Item(1), Requirement::Supers(1)]),
+        ]);
+        assert_eq!(
+            normalize_requirement(&req, None),
+            Requirement::And(vec![
+                Requirement::Item(1),
+                Requirement::Or(vec![Requirement::Item(2), Requirement::Supers(1)]),
+            ])
+        );
+
+        // Resource-consuming terms are never reordered or dropped:
+        let req = Requirement::Or(vec![
+            Requirement::And(vec![Requirement::Missiles(1), Requirement::Damage(10)]),
+            Requirement::And(vec![Requirement::Damage(10), Requirement::Missiles(1)]),
+        ]);
+        assert_eq!(normalize_requirement(&req, None), req);
+    }
+}
@@ -1,8 +1,12 @@
 use std::fmt::Display;
 
 use anyhow::{Context, Result, bail};
+#[cfg(feature = "native")]
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use hashbrown::HashMap;
-use maprando_game::{Item, NotableId, RoomId, TechId};
+use maprando_game::{
+    GameData, Item, NUM_AREAS, NotableId, RoomId, TechId, map_validation::LANDING_SITE_ROOM_ID,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::preset::PresetData;
@@ -23,6 +27,8 @@ pub struct RandomizerSettings {
     pub save_animals: SaveAnimals,
     pub other_settings: OtherSettings,
     #[serde(default)]
+    pub map_filter: MapFilterSettings,
+    #[serde(default)]
     pub debug: bool,
 }
 
@@ -44,6 +50,10 @@ pub struct SkillAssumptionSettings {
     pub draygon_proficiency: f32,
     pub ridley_proficiency: f32,
     pub botwoon_proficiency: f32,
+    pub kraid_proficiency: f32,
+    pub crocomire_proficiency: f32,
+    pub spore_spawn_proficiency: f32,
+    pub golden_torizo_proficiency: f32,
     pub mother_brain_proficiency: f32,
     pub escape_timer_multiplier: f32,
     pub tech_settings: Vec<TechSetting>,
@@ -71,9 +81,12 @@ pub struct ItemProgressionSettings {
     pub preset: Option<String>,
     pub progression_rate: ProgressionRate,
     pub item_placement_style: ItemPlacementStyle,
+    pub logic_mode: LogicMode,
     pub item_priority_strength: ItemPriorityStrength,
     pub random_tank: bool,
     pub spazer_before_plasma: bool,
+    pub progressive_beams: bool,
+    pub progressive_suits: bool,
     pub item_pool_preset: Option<ItemPoolPreset>,
     pub stop_item_placement_early: bool,
     pub ammo_collect_fraction: f32,
@@ -203,6 +216,10 @@ pub enum Objective {
     BabyKraidRoom,
     PlasmaRoom,
     MetalPiratesRoom,
+    ItemPercentage,
+    AllEnergyTanks,
+    AllMapStations,
+    BossCount,
 }
 
 impl Objective {
@@ -228,8 +245,22 @@ impl Objective {
             BabyKraidRoom => "f_ClearedBabyKraidRoom",
             PlasmaRoom => "f_ClearedPlasmaRoom",
             MetalPiratesRoom => "f_ClearedMetalPiratesRoom",
+            ItemPercentage => "f_CollectedItemPercentage",
+            AllEnergyTanks => "f_CollectedAllEnergyTanks",
+            AllMapStations => "f_UsedAllMapStations",
+            BossCount => "f_DefeatedBossCount",
         }
     }
+
+    // Objectives which are not completed by a single event in the game, but are instead
+    // derived from the overall game state (items collected, map stations used, etc.):
+    pub fn is_derived(&self) -> bool {
+        use Objective::*;
+        matches!(
+            self,
+            ItemPercentage | AllEnergyTanks | AllMapStations | BossCount
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -258,6 +289,10 @@ pub struct ObjectiveSettings {
     pub min_objectives: i32,
     pub max_objectives: i32,
     pub objective_screen: ObjectiveScreen,
+    // Percentage of items to collect, for the ItemPercentage objective:
+    pub item_percentage: i32,
+    // Number of major bosses (Kraid, Phantoon, Draygon, Ridley) to defeat, for the BossCount objective:
+    pub boss_count: i32,
 }
 
 pub struct ObjectiveGroup {
@@ -326,6 +361,18 @@ pub fn get_objective_groups() -> Vec<ObjectiveGroup> {
             .map(|(x, y)| (x.to_string(), y.to_string()))
             .collect(),
         },
+        ObjectiveGroup {
+            name: "Other".to_string(),
+            objectives: vec![
+                ("ItemPercentage", "Item %"),
+                ("AllEnergyTanks", "All E-Tanks"),
+                ("AllMapStations", "Map Stations"),
+                ("BossCount", "Boss Count"),
+            ]
+            .into_iter()
+            .map(|(x, y)| (x.to_string(), y.to_string()))
+            .collect(),
+        },
     ]
 }
 
@@ -346,6 +393,37 @@ pub struct OtherSettings {
     pub ultra_low_qol: bool,
     pub race_mode: bool,
     pub random_seed: Option<usize>,
+    // Rooms which must be assigned to a given area (with Balanced area assignment):
+    #[serde(default)]
+    pub area_pins: Vec<AreaPin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct AreaPin {
+    pub room_id: RoomId,
+    // 0: Crateria, 1: Brinstar, 2: Norfair, 3: Wrecked Ship, 4: Maridia, 5: Tourian
+    pub area: usize,
+}
+
+// Constraints on which maps from the map pool are acceptable; maps not matching them are skipped.
+// Unset fields impose no constraint.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct MapFilterSettings {
+    // Rooms which must (or must not) be included in the map:
+    pub required_rooms: Vec<RoomId>,
+    pub forbidden_rooms: Vec<RoomId>,
+    pub min_rooms_per_area: Option<usize>,
+    pub max_rooms_per_area: Option<usize>,
+    // Whether the Toilet must (or must not) pass through another room:
+    pub toilet_intersection: Option<bool>,
+    // Number of door connections which are the same as in the vanilla game (not counting those
+    // that every map keeps, such as the vanilla sand connections):
+    pub min_vanilla_connections: Option<usize>,
+    pub max_vanilla_connections: Option<usize>,
+    // Size of the bounding box of all rooms on the map grid, in tiles:
+    pub max_width: Option<usize>,
+    pub max_height: Option<usize>,
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -362,6 +440,17 @@ pub enum ItemPlacementStyle {
     Local,
 }
 
+// How much the item placement is constrained by the logic. Outside of `Standard`, the resulting
+// seeds are not guaranteed to be beatable.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum LogicMode {
+    Standard,
+    // Each item location is reachable (and returnable) with all items:
+    Minimal,
+    // Items are placed uniformly at random:
+    NoLogic,
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum ItemPriorityStrength {
     Moderate,
@@ -439,6 +528,7 @@ pub enum AreaAssignment {
     Ordered,
     Standard,
     Random,
+    Balanced,
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
@@ -475,6 +565,374 @@ pub enum MotherBrainFight {
     Skip,
 }
 
+// Map layouts available in the map repositories:
+pub const MAP_LAYOUTS: [&str; 4] = ["Vanilla", "Small", "Standard", "Wild"];
+// Map layout using a map uploaded along with the settings, rather than one from a map repository:
+pub const CUSTOM_MAP_LAYOUT: &str = "Custom";
+
+// Maximum number of objectives, limited by the size of the objective tables in the ROM:
+pub const MAX_OBJECTIVES: i32 = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsError {
+    // Path to the invalid field, e.g. "skill_assumption_settings.tech_settings[3]":
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl RandomizerSettings {
+    // Check the settings for invalid values, returning the errors found (empty if the settings
+    // are valid). This catches problems up front, which otherwise would fail deep inside
+    // randomization, or not at all.
+    pub fn validate(&self, preset_data: &PresetData, game_data: &GameData) -> Vec<SettingsError> {
+        let mut errors: Vec<SettingsError> = vec![];
+        let mut check = |ok: bool, path: String, message: String| {
+            if !ok {
+                errors.push(SettingsError { path, message });
+            }
+        };
+
+        // Skill assumptions:
+        let skill = &self.skill_assumption_settings;
+        for (name, value, min, max) in [
+            ("shinespark_tiles", skill.shinespark_tiles, 11.0, 33.0),
+            (
+                "heated_shinespark_tiles",
+                skill.heated_shinespark_tiles,
+                11.0,
+                33.0,
+            ),
+            ("speed_ball_tiles", skill.speed_ball_tiles, 11.0, 99.0),
+            ("resource_multiplier", skill.resource_multiplier, 1.0, 10.0),
+            ("farm_time_limit", skill.farm_time_limit, 30.0, 600.0),
+            (
+                "escape_timer_multiplier",
+                skill.escape_timer_multiplier,
+                1.0,
+                10.0,
+            ),
+            ("phantoon_proficiency", skill.phantoon_proficiency, 0.0, 1.0),
+            ("draygon_proficiency", skill.draygon_proficiency, 0.0, 1.0),
+            ("ridley_proficiency", skill.ridley_proficiency, 0.0, 1.0),
+            ("botwoon_proficiency", skill.botwoon_proficiency, 0.0, 1.0),
+            ("kraid_proficiency", skill.kraid_proficiency, 0.0, 1.0),
+            (
+                "crocomire_proficiency",
+                skill.crocomire_proficiency,
+                0.0,
+                1.0,
+            ),
+            (
+                "spore_spawn_proficiency",
+                skill.spore_spawn_proficiency,
+                0.0,
+                1.0,
+            ),
+            (
+                "golden_torizo_proficiency",
+                skill.golden_torizo_proficiency,
+                0.0,
+                1.0,
+            ),
+            (
+                "mother_brain_proficiency",
+                skill.mother_brain_proficiency,
+                0.0,
+                1.0,
+            ),
+        ] {
+            check(
+                (min..=max).contains(&value),
+                format!("skill_assumption_settings.{name}"),
+                format!("{value} is not between {min} and {max}"),
+            );
+        }
+        for (name, value) in [
+            (
+                "shinecharge_leniency_frames",
+                skill.shinecharge_leniency_frames,
+            ),
+            ("gate_glitch_leniency", skill.gate_glitch_leniency),
+            ("door_stuck_leniency", skill.door_stuck_leniency),
+            ("bomb_into_cf_leniency", skill.bomb_into_cf_leniency),
+            ("jump_into_cf_leniency", skill.jump_into_cf_leniency),
+            ("spike_xmode_leniency", skill.spike_xmode_leniency),
+        ] {
+            check(
+                value >= 0,
+                format!("skill_assumption_settings.{name}"),
+                format!("{value} is negative"),
+            );
+        }
+
+        // Implicit tech are always enabled, regardless of the tech settings:
+        let mut enabled_tech: Vec<TechId> = preset_data.tech_by_difficulty["Implicit"].clone();
+        enabled_tech.extend(
+            skill
+                .tech_settings
+                .iter()
+                .filter(|x| x.enabled)
+                .map(|x| x.id),
+        );
+        for (i, t) in skill.tech_settings.iter().enumerate() {
+            let path = format!("skill_assumption_settings.tech_settings[{i}]");
+            if !preset_data.tech_data_map.contains_key(&t.id) {
+                check(false, path, format!("unrecognized tech ID {}", t.id));
+                continue;
+            }
+            if !t.enabled {
+                continue;
+            }
+            for dep_id in game_data.tech_dependencies.get(&t.id).unwrap_or(&vec![]) {
+                check(
+                    enabled_tech.contains(dep_id),
+                    path.clone(),
+                    format!(
+                        "{} requires {}, which is not enabled",
+                        t.name, game_data.tech_names[dep_id]
+                    ),
+                );
+            }
+        }
+        for (i, n) in skill.notable_settings.iter().enumerate() {
+            check(
+                preset_data
+                    .notable_data_map
+                    .contains_key(&(n.room_id, n.notable_id)),
+                format!("skill_assumption_settings.notable_settings[{i}]"),
+                format!("unrecognized notable ID ({}, {})", n.room_id, n.notable_id),
+            );
+        }
+
+        // Item progression:
+        let items = &self.item_progression_settings;
+        check(
+            (0.5..=1.0).contains(&items.ammo_collect_fraction),
+            "item_progression_settings.ammo_collect_fraction".to_string(),
+            format!("{} is not between 0.5 and 1", items.ammo_collect_fraction),
+        );
+        let mut pool_items: Vec<Item> = vec![];
+        for (i, x) in items.item_pool.iter().enumerate() {
+            let path = format!("item_progression_settings.item_pool[{i}]");
+            check(
+                !x.item.is_unique(),
+                path.clone(),
+                format!("{:?} is not a multiple-copy item", x.item),
+            );
+            check(
+                x.item != Item::Nothing,
+                path.clone(),
+                "Nothing is not allowed in the item pool".to_string(),
+            );
+            check(
+                !pool_items.contains(&x.item),
+                path,
+                format!("{:?} is listed more than once", x.item),
+            );
+            pool_items.push(x.item);
+        }
+        let pool_count: usize = items.item_pool.iter().map(|x| x.count).sum();
+        check(
+            pool_count <= game_data.item_locations.len(),
+            "item_progression_settings.item_pool".to_string(),
+            format!(
+                "{pool_count} items exceeds the {} item locations",
+                game_data.item_locations.len()
+            ),
+        );
+        let mut starting_items: Vec<Item> = vec![];
+        for (i, x) in items.starting_items.iter().enumerate() {
+            let path = format!("item_progression_settings.starting_items[{i}]");
+            check(
+                x.item.progressive_sequence().is_none() && x.item != Item::Nothing,
+                path.clone(),
+                format!("{:?} is not allowed as a starting item", x.item),
+            );
+            check(
+                !x.item.is_unique() || x.count <= 1,
+                path.clone(),
+                format!("{:?} is unique, but the count is {}", x.item, x.count),
+            );
+            check(
+                !starting_items.contains(&x.item),
+                path,
+                format!("{:?} is listed more than once", x.item),
+            );
+            starting_items.push(x.item);
+        }
+
+        // Objectives:
+        let obj = &self.objective_settings;
+        let mut objectives: Vec<Objective> = vec![];
+        for (i, x) in obj.objective_options.iter().enumerate() {
+            check(
+                !objectives.contains(&x.objective),
+                format!("objective_settings.objective_options[{i}]"),
+                format!("{:?} is listed more than once", x.objective),
+            );
+            objectives.push(x.objective);
+        }
+        let num_yes = obj
+            .objective_options
+            .iter()
+            .filter(|x| x.setting == ObjectiveSetting::Yes)
+            .count() as i32;
+        let num_maybe = obj
+            .objective_options
+            .iter()
+            .filter(|x| x.setting == ObjectiveSetting::Maybe)
+            .count() as i32;
+        check(
+            obj.min_objectives >= num_yes,
+            "objective_settings.min_objectives".to_string(),
+            format!(
+                "{} is less than the {num_yes} objectives set to Yes",
+                obj.min_objectives
+            ),
+        );
+        check(
+            obj.max_objectives >= obj.min_objectives,
+            "objective_settings.max_objectives".to_string(),
+            format!(
+                "{} is less than min_objectives ({})",
+                obj.max_objectives, obj.min_objectives
+            ),
+        );
+        check(
+            obj.max_objectives <= num_yes + num_maybe,
+            "objective_settings.max_objectives".to_string(),
+            format!(
+                "{} is more than the {} objectives set to Yes or Maybe",
+                obj.max_objectives,
+                num_yes + num_maybe
+            ),
+        );
+        check(
+            obj.max_objectives <= MAX_OBJECTIVES,
+            "objective_settings.max_objectives".to_string(),
+            format!(
+                "{} is more than the limit of {MAX_OBJECTIVES}",
+                obj.max_objectives
+            ),
+        );
+        check(
+            (1..=100).contains(&obj.item_percentage),
+            "objective_settings.item_percentage".to_string(),
+            format!("{} is not between 1 and 100", obj.item_percentage),
+        );
+        check(
+            (1..=4).contains(&obj.boss_count),
+            "objective_settings.boss_count".to_string(),
+            format!("{} is not between 1 and 4", obj.boss_count),
+        );
+
+        // Other settings:
+        check(
+            MAP_LAYOUTS.contains(&self.map_layout.as_str()) || self.map_layout == CUSTOM_MAP_LAYOUT,
+            "map_layout".to_string(),
+            format!("unrecognized map layout {}", self.map_layout),
+        );
+        let start = &self.start_location_settings;
+        if start.mode == StartLocationMode::Custom {
+            check(
+                game_data
+                    .start_locations
+                    .iter()
+                    .any(|x| Some(x.room_id) == start.room_id && Some(x.node_id) == start.node_id),
+                "start_location_settings".to_string(),
+                format!(
+                    "unrecognized start location (room_id={:?}, node_id={:?})",
+                    start.room_id, start.node_id
+                ),
+            );
+        }
+        let filter = &self.map_filter;
+        for (name, room_ids) in [
+            ("required_rooms", &filter.required_rooms),
+            ("forbidden_rooms", &filter.forbidden_rooms),
+        ] {
+            for (i, room_id) in room_ids.iter().enumerate() {
+                check(
+                    game_data.room_idx_by_id.contains_key(room_id),
+                    format!("map_filter.{name}[{i}]"),
+                    format!("unrecognized room ID {room_id}"),
+                );
+            }
+        }
+        for room_id in &filter.required_rooms {
+            check(
+                !filter.forbidden_rooms.contains(room_id),
+                "map_filter.forbidden_rooms".to_string(),
+                format!("room ID {room_id} is also required"),
+            );
+        }
+        for (name, min, max) in [
+            (
+                "rooms_per_area",
+                filter.min_rooms_per_area,
+                filter.max_rooms_per_area,
+            ),
+            (
+                "vanilla_connections",
+                filter.min_vanilla_connections,
+                filter.max_vanilla_connections,
+            ),
+        ] {
+            if let (Some(min), Some(max)) = (min, max) {
+                check(
+                    min <= max,
+                    format!("map_filter.max_{name}"),
+                    format!("{max} is less than min_{name} ({min})"),
+                );
+            }
+        }
+        let other = &self.other_settings;
+        let mut pinned_rooms: Vec<RoomId> = vec![];
+        for (i, pin) in other.area_pins.iter().enumerate() {
+            let path = format!("other_settings.area_pins[{i}]");
+            check(
+                game_data.room_idx_by_id.contains_key(&pin.room_id),
+                path.clone(),
+                format!("unrecognized room ID {}", pin.room_id),
+            );
+            check(
+                pin.area < NUM_AREAS,
+                path.clone(),
+                format!("{} is not a valid area", pin.area),
+            );
+            check(
+                pin.room_id != LANDING_SITE_ROOM_ID || pin.area == 0,
+                path.clone(),
+                "the Landing Site can only be pinned to Crateria (area 0)".to_string(),
+            );
+            check(
+                !pinned_rooms.contains(&pin.room_id),
+                path,
+                format!("room ID {} is listed more than once", pin.room_id),
+            );
+            pinned_rooms.push(pin.room_id);
+        }
+        check(
+            other.area_pins.is_empty() || other.area_assignment == AreaAssignment::Balanced,
+            "other_settings.area_pins".to_string(),
+            "area pins require Balanced area assignment".to_string(),
+        );
+        check(
+            self.other_settings.random_seed != Some(0),
+            "other_settings.random_seed".to_string(),
+            "0 is not a valid random seed".to_string(),
+        );
+
+        errors
+    }
+}
+
 pub fn parse_randomizer_settings(settings_json: &str) -> Result<RandomizerSettings> {
     let mut des = serde_json::Deserializer::from_str(settings_json);
     let settings = serde_path_to_error::deserialize(&mut des)?;
@@ -631,6 +1089,28 @@ fn upgrade_other_skill_settings(settings: &mut serde_json::Value) -> Result<()>
     if !skill_assumption_settings.contains_key("farm_time_limit") {
         skill_assumption_settings.insert("farm_time_limit".to_string(), (60.0).into());
     }
+    // For settings from before these bosses had their own proficiency, conservatively use
+    // the lowest of the existing boss proficiencies.
+    let min_boss_proficiency = [
+        "phantoon_proficiency",
+        "draygon_proficiency",
+        "ridley_proficiency",
+        "botwoon_proficiency",
+        "mother_brain_proficiency",
+    ]
+    .iter()
+    .filter_map(|&k| skill_assumption_settings.get(k).and_then(|x| x.as_f64()))
+    .fold(1.0, f64::min);
+    for key in [
+        "kraid_proficiency",
+        "crocomire_proficiency",
+        "spore_spawn_proficiency",
+        "golden_torizo_proficiency",
+    ] {
+        if !skill_assumption_settings.contains_key(key) {
+            skill_assumption_settings.insert(key.to_string(), min_boss_proficiency.into());
+        }
+    }
 
     Ok(())
 }
@@ -644,6 +1124,15 @@ fn upgrade_item_progression_settings(settings: &mut serde_json::Value) -> Result
     if !item_progression_settings.contains_key("ammo_collect_fraction") {
         item_progression_settings.insert("ammo_collect_fraction".to_string(), (0.7).into());
     }
+    if !item_progression_settings.contains_key("progressive_beams") {
+        item_progression_settings.insert("progressive_beams".to_string(), false.into());
+    }
+    if !item_progression_settings.contains_key("progressive_suits") {
+        item_progression_settings.insert("progressive_suits".to_string(), false.into());
+    }
+    if !item_progression_settings.contains_key("logic_mode") {
+        item_progression_settings.insert("logic_mode".to_string(), "Standard".into());
+    }
 
     Ok(())
 }
@@ -817,6 +1306,39 @@ fn upgrade_objective_settings(
             .unwrap()
             .insert("objective_screen".to_string(), "Enabled".into());
     }
+
+    let objective_settings = settings_obj
+        .get_mut("objective_settings")
+        .unwrap()
+        .as_object_mut()
+        .unwrap();
+    if !objective_settings.contains_key("item_percentage") {
+        objective_settings.insert("item_percentage".to_string(), 100.into());
+    }
+    if !objective_settings.contains_key("boss_count") {
+        objective_settings.insert("boss_count".to_string(), 2.into());
+    }
+    let objective_options = objective_settings
+        .get_mut("objective_options")
+        .context("missing objective_options")?
+        .as_array_mut()
+        .context("objective_options is not array")?;
+    for objective in [
+        "ItemPercentage",
+        "AllEnergyTanks",
+        "AllMapStations",
+        "BossCount",
+    ] {
+        if !objective_options
+            .iter()
+            .any(|x| x["objective"].as_str() == Some(objective))
+        {
+            objective_options.push(serde_json::json!({
+                "objective": objective,
+                "setting": "No",
+            }));
+        }
+    }
     Ok(())
 }
 
@@ -851,3 +1373,224 @@ pub fn try_upgrade_settings(
     let settings_out_str = serde_json::to_string(&settings_out)?;
     Ok((settings_out_str, settings_out))
 }
+
+// Settings codes are a compact, URL-safe encoding of settings, for sharing. A code consists of a
+// format byte followed by the zstd-compressed JSON of the full settings (with tech and notables
+// reduced to lists of those enabled), all encoded as base64url. Codes don't depend on the current
+// presets, so a code keeps its meaning when presets change; they are decoded through
+// `try_upgrade_settings`, so that codes from older versions remain usable.
+//
+// Format 1 codes stored only the differences from the referenced presets, so they can't be decoded
+// reliably and are no longer accepted.
+#[cfg(feature = "native")]
+const SETTINGS_CODE_FORMAT: u8 = 2;
+
+pub(crate) fn find_section_preset(
+    section: &str,
+    preset_name: &str,
+    preset_data: &PresetData,
+) -> Result<Option<serde_json::Value>> {
+    let name = Some(preset_name.to_string());
+    Ok(match section {
+        "skill_assumption_settings" => preset_data
+            .skill_presets
+            .iter()
+            .find(|p| p.preset == name)
+            .map(serde_json::to_value)
+            .transpose()?,
+        "item_progression_settings" => preset_data
+            .item_progression_presets
+            .iter()
+            .find(|p| p.preset == name)
+            .map(serde_json::to_value)
+            .transpose()?,
+        "quality_of_life_settings" => preset_data
+            .quality_of_life_presets
+            .iter()
+            .find(|p| p.preset == name)
+            .map(serde_json::to_value)
+            .transpose()?,
+        "objective_settings" => preset_data
+            .objective_presets
+            .iter()
+            .find(|p| p.preset == name)
+            .map(serde_json::to_value)
+            .transpose()?,
+        _ => bail!("unexpected preset section {section}"),
+    })
+}
+
+#[cfg(feature = "native")]
+fn compact_skill_settings(settings: &mut serde_json::Value) -> Result<()> {
+    let skill_settings = &mut settings["skill_assumption_settings"];
+    let tech_ids: Vec<serde_json::Value> = skill_settings["tech_settings"]
+        .as_array()
+        .context("missing tech_settings")?
+        .iter()
+        .filter(|x| x["enabled"].as_bool() == Some(true))
+        .map(|x| x["id"].clone())
+        .collect();
+    let notable_ids: Vec<serde_json::Value> = skill_settings["notable_settings"]
+        .as_array()
+        .context("missing notable_settings")?
+        .iter()
+        .filter(|x| x["enabled"].as_bool() == Some(true))
+        .map(|x| serde_json::json!([x["room_id"], x["notable_id"]]))
+        .collect();
+    skill_settings["tech_settings"] = tech_ids.into();
+    skill_settings["notable_settings"] = notable_ids.into();
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn expand_skill_settings(settings: &mut serde_json::Value, preset_data: &PresetData) -> Result<()> {
+    let skill_settings = &mut settings["skill_assumption_settings"];
+    let mut enabled_tech: Vec<TechId> = vec![];
+    for x in skill_settings["tech_settings"]
+        .as_array()
+        .context("missing tech_settings")?
+    {
+        enabled_tech.push(x.as_i64().context("invalid tech ID")? as TechId);
+    }
+    let mut enabled_notables: Vec<(RoomId, NotableId)> = vec![];
+    for x in skill_settings["notable_settings"]
+        .as_array()
+        .context("missing notable_settings")?
+    {
+        let room_id = x[0].as_i64().context("invalid notable room ID")? as RoomId;
+        let notable_id = x[1].as_i64().context("invalid notable ID")? as NotableId;
+        enabled_notables.push((room_id, notable_id));
+    }
+
+    let default_skill_settings = &preset_data.default_preset.skill_assumption_settings;
+    let tech_settings: Vec<TechSetting> = default_skill_settings
+        .tech_settings
+        .iter()
+        .map(|t| TechSetting {
+            enabled: enabled_tech.contains(&t.id),
+            ..t.clone()
+        })
+        .collect();
+    let notable_settings: Vec<NotableSetting> = default_skill_settings
+        .notable_settings
+        .iter()
+        .map(|n| NotableSetting {
+            enabled: enabled_notables.contains(&(n.room_id, n.notable_id)),
+            ..n.clone()
+        })
+        .collect();
+    skill_settings["tech_settings"] = serde_json::to_value(tech_settings)?;
+    skill_settings["notable_settings"] = serde_json::to_value(notable_settings)?;
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+pub fn encode_settings_code(settings: &RandomizerSettings) -> Result<String> {
+    let mut value = serde_json::to_value(settings)?;
+    compact_skill_settings(&mut value)?;
+    let mut data = vec![SETTINGS_CODE_FORMAT];
+    data.extend(zstd::encode_all(value.to_string().as_bytes(), 19)?);
+    Ok(URL_SAFE_NO_PAD.encode(data))
+}
+
+#[cfg(feature = "native")]
+pub fn decode_settings_code(
+    code: &str,
+    preset_data: &PresetData,
+) -> Result<(String, RandomizerSettings)> {
+    let data = URL_SAFE_NO_PAD
+        .decode(code.trim())
+        .context("settings code is not valid base64url")?;
+    let (&format, compressed) = data.split_first().context("empty settings code")?;
+    if format != SETTINGS_CODE_FORMAT {
+        bail!("unsupported settings code format: {format}");
+    }
+    let settings_bytes = zstd::decode_all(compressed).context("invalid settings code")?;
+    let mut settings: serde_json::Value = serde_json::from_slice(&settings_bytes)?;
+    expand_skill_settings(&mut settings, preset_data)?;
+    try_upgrade_settings(settings.to_string(), preset_data, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn settings_code_round_trip() {
+        let preset_data = crate::test_data::preset_data();
+        let mut settings = preset_data.default_preset.clone();
+        settings.skill_assumption_settings.kraid_proficiency = 0.25;
+        settings.skill_assumption_settings.golden_torizo_proficiency = 0.75;
+        settings.skill_assumption_settings.tech_settings[0].enabled =
+            !settings.skill_assumption_settings.tech_settings[0].enabled;
+        settings.item_progression_settings.logic_mode = LogicMode::Minimal;
+        settings.item_progression_settings.progressive_beams = true;
+        settings.item_progression_settings.progressive_suits = true;
+        settings.objective_settings.item_percentage = 75;
+        settings.objective_settings.boss_count = 3;
+        settings.other_settings.area_assignment = AreaAssignment::Balanced;
+        settings.other_settings.area_pins = vec![AreaPin {
+            room_id: 8,
+            area: 2,
+        }];
+        settings.map_filter = MapFilterSettings {
+            required_rooms: vec![8],
+            toilet_intersection: Some(false),
+            max_width: Some(60),
+            ..MapFilterSettings::default()
+        };
+
+        let code = encode_settings_code(&settings).unwrap();
+        let (_, decoded) = decode_settings_code(&code, preset_data).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&settings).unwrap()
+        );
+    }
+
+    #[test]
+    fn old_settings_code_keeps_its_settings() {
+        // A code for the "Default" preset (as of settings version 119) with a few changes. It must
+        // keep decoding to the same settings, regardless of later changes to the presets.
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/settings_code_v119.txt");
+        let code = std::fs::read_to_string(path).unwrap();
+        let (_, settings) = decode_settings_code(&code, crate::test_data::preset_data()).unwrap();
+
+        assert_eq!(settings.name.as_deref(), Some("Default"));
+        let skill_settings = &settings.skill_assumption_settings;
+        assert_eq!(skill_settings.preset.as_deref(), Some("Basic"));
+        assert_eq!(skill_settings.kraid_proficiency, 0.25);
+        assert_eq!(skill_settings.mother_brain_proficiency, 0.0);
+        let mut enabled_tech: Vec<TechId> = skill_settings
+            .tech_settings
+            .iter()
+            .filter(|t| t.enabled)
+            .map(|t| t.id)
+            .collect();
+        enabled_tech.sort();
+        assert_eq!(enabled_tech, vec![6, 32, 76, 87, 109, 132]);
+        let enabled_notables: Vec<(RoomId, NotableId)> = skill_settings
+            .notable_settings
+            .iter()
+            .filter(|n| n.enabled)
+            .map(|n| (n.room_id, n.notable_id))
+            .collect();
+        assert_eq!(enabled_notables, vec![(222, 1)]);
+
+        let item_settings = &settings.item_progression_settings;
+        assert_eq!(item_settings.preset.as_deref(), Some("Normal"));
+        assert_eq!(item_settings.logic_mode, LogicMode::Minimal);
+        assert!(item_settings.progressive_beams);
+        assert!(!item_settings.progressive_suits);
+        assert_eq!(
+            settings.objective_settings.preset.as_deref(),
+            Some("Bosses")
+        );
+        assert_eq!(settings.objective_settings.item_percentage, 75);
+        assert_eq!(
+            settings.other_settings.area_assignment,
+            AreaAssignment::Balanced
+        );
+    }
+}
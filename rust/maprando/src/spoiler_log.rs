@@ -2,7 +2,7 @@ use anyhow::Result;
 use hashbrown::HashMap;
 use maprando_game::{
     BeamType, Capacity, DoorType, FlagId, Item, LinkIdx, NodeId, Requirement, RoomId, StepTrailId,
-    TraversalId, VertexId, VertexKey,
+    TraversalId, VertexId, VertexKey, requirement_render::RenderFormat,
 };
 use maprando_logic::{GlobalState, LocalState};
 use serde::{Deserialize, Serialize};
@@ -16,8 +16,9 @@ use crate::{
     },
     settings::SaveAnimals,
     traverse::{
-        NUM_COST_METRICS, Traverser, get_bireachable_idxs, get_one_way_reachable_idx,
-        get_spoiler_trail_ids,
+        NUM_COST_METRICS, Traverser,
+        explain::{UnreachableExplanation, explain_unreachable},
+        get_bireachable_idxs, get_one_way_reachable_idx, get_spoiler_trail_ids,
     },
 };
 
@@ -95,6 +96,16 @@ pub struct SpoilerItemLoc {
     pub item: String,
     pub location: SpoilerLocation,
 }
+// An item location which never became bireachable, with an explanation of what blocks it.
+// If the location is reachable, the explanation is for why it's not possible to return.
+#[derive(Serialize, Deserialize)]
+pub struct SpoilerUnreachableItem {
+    pub item: String,
+    pub location: SpoilerLocation,
+    pub reachable: bool,
+    pub explanation: UnreachableExplanation,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SpoilerRoomLoc {
     // here temporarily, most likely, since these can be baked into the web UI
@@ -191,6 +202,8 @@ pub struct SpoilerLog {
     pub game_data: SpoilerGameData,
     pub forward_traversal: SpoilerTraversal,
     pub reverse_traversal: SpoilerTraversal,
+    #[serde(default)]
+    pub unreachable_items: Vec<SpoilerUnreachableItem>,
 }
 
 // Spoiler log ---------------------------------------------------------
@@ -223,6 +236,9 @@ pub struct SpoilerRouteEntry {
     pub power_bombs_used: Option<Capacity>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub relevant_flags: Vec<String>,
+    // Processed logical requirement of the link, as indented text (empty if free):
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub requirement: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -448,6 +464,78 @@ fn get_vertex_info_by_id(randomizer: &Randomizer, room_id: RoomId, node_id: Node
     }
 }
 
+fn get_spoiler_item_location(randomizer: &Randomizer, item_loc_idx: usize) -> SpoilerLocation {
+    let (r, n) = randomizer.game_data.item_locations[item_loc_idx];
+    let item_vertex_info = get_vertex_info_by_id(randomizer, r, n);
+    let room_id = item_vertex_info.room_id;
+    let node_id = item_vertex_info.node_id;
+    let node_coords = randomizer.game_data.node_coords[&(room_id, node_id)];
+    let coords = (
+        item_vertex_info.room_coords.0 + node_coords.0,
+        item_vertex_info.room_coords.1 + node_coords.1,
+    );
+    SpoilerLocation {
+        area: item_vertex_info.area_name,
+        room_id,
+        room: item_vertex_info.room_name,
+        node_id,
+        node: item_vertex_info.node_name,
+        coords,
+    }
+}
+
+fn get_spoiler_unreachable_items(
+    randomizer: &Randomizer,
+    state: &RandomizationState,
+    traverser_pair: &TraverserPair,
+) -> Vec<SpoilerUnreachableItem> {
+    let Some(last_step) = traverser_pair.forward.past_steps.last() else {
+        return vec![];
+    };
+    let global = &last_step.global_state;
+    let mut out = vec![];
+    for (i, item_state) in state.item_location_state.iter().enumerate() {
+        if item_state.bireachable_traversal.is_some() {
+            continue;
+        }
+        let Some(item) = item_state.placed_item else {
+            continue;
+        };
+        let vertex_ids = &randomizer.game_data.item_vertex_ids[i];
+        let reachable = vertex_ids.iter().any(|&v| {
+            traverser_pair.forward.cost[v]
+                .iter()
+                .any(|&x| f32::is_finite(x))
+        });
+        let traverser = if reachable {
+            &traverser_pair.reverse
+        } else {
+            &traverser_pair.forward
+        };
+        let explanation = explain_unreachable(
+            vertex_ids,
+            traverser,
+            &randomizer.base_links_data,
+            &randomizer.seed_links_data,
+            global,
+            randomizer.settings,
+            &randomizer.difficulty_tiers[0],
+            randomizer.game_data,
+            &randomizer.door_map,
+            randomizer.locked_door_data,
+            &randomizer.objectives,
+            3,
+        );
+        out.push(SpoilerUnreachableItem {
+            item: Item::VARIANTS[item as usize].to_string(),
+            location: get_spoiler_item_location(randomizer, i),
+            reachable,
+            explanation,
+        });
+    }
+    out
+}
+
 fn get_spoiler_start_state(
     randomizer: &Randomizer,
     global_state: &GlobalState,
@@ -524,6 +612,10 @@ pub fn get_spoiler_route(
                 relevant_flags.push(flag_name);
             }
         }
+        let requirement = match link.requirement.simplify() {
+            Requirement::Free => String::new(),
+            req => req.render(randomizer.game_data, RenderFormat::Text),
+        };
 
         let spoiler_entry = SpoilerRouteEntry {
             area: to_vertex_info.area_name,
@@ -544,6 +636,7 @@ pub fn get_spoiler_route(
             supers_used: Some(new_local_state.supers_used),
             power_bombs_used: Some(new_local_state.power_bombs_used),
             relevant_flags,
+            requirement,
         };
         route.push(spoiler_entry);
     }
@@ -869,6 +962,7 @@ pub fn get_spoiler_log(
 ) -> Result<SpoilerLog> {
     let forward_traversal = get_spoiler_traversal(&traverser_pair.forward);
     let reverse_traversal = get_spoiler_traversal(&traverser_pair.reverse);
+    let unreachable_items = get_spoiler_unreachable_items(randomizer, state, traverser_pair);
 
     // Compute the first step on which each node becomes reachable/bireachable:
     let mut node_reachable_step: HashMap<(RoomId, NodeId), usize> = HashMap::new();
@@ -1083,27 +1177,10 @@ pub fn get_spoiler_log(
         .iter()
         .enumerate()
         .map(|(i, x)| {
-            let (r, n) = randomizer.game_data.item_locations[i];
-            let item_vertex_info = get_vertex_info_by_id(randomizer, r, n);
-            let room_id = item_vertex_info.room_id;
-            let node_id = item_vertex_info.node_id;
-            let node_coords = randomizer.game_data.node_coords[&(room_id, node_id)];
-            let coords = (
-                item_vertex_info.room_coords.0 + node_coords.0,
-                item_vertex_info.room_coords.1 + node_coords.1,
-            );
-            let location = SpoilerLocation {
-                area: item_vertex_info.area_name,
-                room_id,
-                room: item_vertex_info.room_name,
-                node_id,
-                node: item_vertex_info.node_name,
-                coords,
-            };
             let item = x.placed_item.unwrap();
             SpoilerItemLoc {
                 item: Item::VARIANTS[item as usize].to_string(),
-                location,
+                location: get_spoiler_item_location(randomizer, i),
             }
         })
         .collect();
@@ -1195,5 +1272,6 @@ pub fn get_spoiler_log(
         game_data: get_spoiler_game_data(randomizer),
         forward_traversal,
         reverse_traversal,
+        unreachable_items,
     })
 }
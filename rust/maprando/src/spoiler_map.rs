@@ -1,21 +1,30 @@
 use anyhow::Result;
 use hashbrown::HashMap;
-use image::{Rgba, RgbaImage};
+use image::{
+    Delay, Frame, Rgba, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+};
 use std::io::Cursor;
 
 pub use image;
 
 use crate::{
     customize::CustomizeSettings,
-    patch::map_tiles::{
-        apply_door_lock, apply_item_interior, get_gray_doors, get_objective_tiles, render_tile,
+    patch::{
+        NUM_AREAS, Rom,
+        map_tiles::{
+            TILE_GFX_ADDR_4BPP, apply_door_lock, apply_item_interior, get_gray_doors,
+            get_objective_tiles, hflip_tile, read_tile_4bpp, render_tile, vflip_tile,
+        },
+        snes2pc, xy_to_map_offset,
     },
     randomize::Randomization,
     settings::RandomizerSettings,
+    spoiler_log::{SpoilerDetails, SpoilerLog},
 };
 use maprando_game::{
-    Direction, DoorLockType, GameData, ItemPtr, MapTile, MapTileEdge, MapTileInterior,
-    MapTileSpecialType,
+    BeamType, Direction, DoorLockType, DoorType, GameData, ItemPtr, MapTile, MapTileEdge,
+    MapTileInterior, MapTileSpecialType, RoomGeometryRoomIdx, map_validation::MAP_GRID_SIZE,
 };
 
 fn get_rgb(r: isize, g: isize, b: isize) -> Rgba<u8> {
@@ -98,19 +107,17 @@ fn add_vanilla_elevators(tiles: &mut [Vec<MapTile>]) {
     }
 }
 
-pub fn get_spoiler_images(
+// Get the tiles of the whole map grid, with the rooms and their gray doors but without items,
+// door locks, or objectives. Also returns the room drawn on each tile.
+fn get_base_tiles(
     randomization: &Randomization,
     game_data: &GameData,
     settings: &RandomizerSettings,
-    show_grid: bool,
-) -> Result<(RgbaImage, RgbaImage)> {
+) -> (Vec<Vec<MapTile>>, Vec<Vec<Option<RoomGeometryRoomIdx>>>) {
     let map = &randomization.map;
-    let max_tiles = 72;
-    let width = max_tiles;
-    let height = max_tiles;
-    let mut tiles: Vec<Vec<MapTile>> = vec![vec![MapTile::default(); width]; height];
-
-    let customize_settings = CustomizeSettings::default();
+    let mut tiles: Vec<Vec<MapTile>> = vec![vec![MapTile::default(); MAP_GRID_SIZE]; MAP_GRID_SIZE];
+    let mut tile_rooms: Vec<Vec<Option<RoomGeometryRoomIdx>>> =
+        vec![vec![None; MAP_GRID_SIZE]; MAP_GRID_SIZE];
 
     if settings.map_layout == "Vanilla" {
         add_vanilla_elevators(&mut tiles);
@@ -141,39 +148,11 @@ pub fn get_spoiler_images(
                 // because of the Toilet, and also Tourian elevator on vanilla map.
                 tiles[y][x] = tile.clone();
                 tiles[y][x].area = Some(area);
+                tile_rooms[y][x] = Some(room_idx);
             }
         }
     }
 
-    // Add item dots:
-    let mut item_coords: HashMap<ItemPtr, (usize, usize)> = HashMap::new();
-    for room in &game_data.room_geometry {
-        let room_id = room.room_id;
-        let room_ptr = game_data.room_ptr_by_id[&room_id];
-        let room_idx = game_data.room_idx_by_ptr[&room_ptr];
-        if !map.room_mask[room_idx] {
-            continue;
-        }
-        for item in &room.items {
-            item_coords.insert(item.addr, (item.x, item.y));
-        }
-    }
-    for (i, &item) in randomization.item_placement.iter().enumerate() {
-        let (room_id, node_id) = game_data.item_locations[i];
-        let room_ptr = game_data.room_ptr_by_id[&room_id];
-        let room_idx = game_data.room_idx_by_ptr[&room_ptr];
-        if !map.room_mask[room_idx] {
-            continue;
-        }
-        let item_ptr = game_data.node_ptr_map[&(room_id, node_id)];
-        let (item_x, item_y) = item_coords[&item_ptr];
-        let room_x = map.rooms[room_idx].0;
-        let room_y = map.rooms[room_idx].1;
-        let x = room_x + item_x;
-        let y = room_y + item_y;
-        tiles[y][x].interior = apply_item_interior(tiles[y][x].clone(), item, settings);
-    }
-
     // Add gray doors:
     let gray_door = MapTileEdge::LockedDoor(DoorLockType::Gray);
     for (room_id, door_x, door_y, dir) in get_gray_doors() {
@@ -203,6 +182,62 @@ pub fn get_spoiler_images(
         }
         tiles[y][x] = tile;
     }
+    (tiles, tile_rooms)
+}
+
+// Get the map coordinates of each item location (indexed like `GameData.item_locations`), or None
+// for items in rooms not included in the map.
+fn get_item_coords(
+    randomization: &Randomization,
+    game_data: &GameData,
+) -> Vec<Option<(usize, usize)>> {
+    let map = &randomization.map;
+    let mut item_coords: HashMap<ItemPtr, (usize, usize)> = HashMap::new();
+    for room in &game_data.room_geometry {
+        for item in &room.items {
+            item_coords.insert(item.addr, (item.x, item.y));
+        }
+    }
+    game_data
+        .item_locations
+        .iter()
+        .map(|&(room_id, node_id)| {
+            let room_ptr = game_data.room_ptr_by_id[&room_id];
+            let room_idx = game_data.room_idx_by_ptr[&room_ptr];
+            if !map.room_mask[room_idx] {
+                return None;
+            }
+            let item_ptr = game_data.node_ptr_map[&(room_id, node_id)];
+            let (item_x, item_y) = item_coords[&item_ptr];
+            Some((
+                map.rooms[room_idx].0 + item_x,
+                map.rooms[room_idx].1 + item_y,
+            ))
+        })
+        .collect()
+}
+
+pub fn get_spoiler_images(
+    randomization: &Randomization,
+    game_data: &GameData,
+    settings: &RandomizerSettings,
+    show_grid: bool,
+) -> Result<(RgbaImage, RgbaImage)> {
+    let map = &randomization.map;
+    let width = MAP_GRID_SIZE;
+    let height = MAP_GRID_SIZE;
+    let (mut tiles, _) = get_base_tiles(randomization, game_data, settings);
+
+    let customize_settings = CustomizeSettings::default();
+
+    // Add item dots:
+    let item_coords = get_item_coords(randomization, game_data);
+    for (&item, coords) in randomization.item_placement.iter().zip(&item_coords) {
+        let Some((x, y)) = *coords else {
+            continue;
+        };
+        tiles[y][x].interior = apply_item_interior(tiles[y][x].clone(), item, settings);
+    }
 
     // Add door locks:
     for locked_door in randomization.locked_doors.iter() {
@@ -317,3 +352,560 @@ pub fn get_spoiler_map(
         outline: vec_outline,
     })
 }
+
+fn svg_color(color: Rgba<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn svg_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn get_door_lock_color(door_type: DoorType) -> Option<Rgba<u8>> {
+    let value = match door_type {
+        DoorType::Blue => return None,
+        DoorType::Red | DoorType::Beam(BeamType::Wave) => 7,
+        DoorType::Green | DoorType::Beam(BeamType::Plasma) => 14,
+        DoorType::Yellow | DoorType::Beam(BeamType::Spazer) => 6,
+        DoorType::Beam(BeamType::Ice) => 8,
+        DoorType::Gray | DoorType::Beam(BeamType::Charge) => 15,
+        DoorType::Wall => 3,
+    };
+    Some(get_explored_color(value, 0))
+}
+
+// Center of a map tile, in SVG coordinates (with a one-tile margin, as in the PNG images):
+fn tile_center(x: usize, y: usize) -> (usize, usize) {
+    ((x + 1) * 8 + 4, (y + 1) * 8 + 4)
+}
+
+// Draw the room tiles as rectangles covering runs of same-colored pixels (merging identical runs
+// in consecutive rows), grouped by room so that each room has a tooltip with its name.
+fn write_svg_rooms(
+    out: &mut String,
+    tiles: &[Vec<MapTile>],
+    tile_rooms: &[Vec<Option<RoomGeometryRoomIdx>>],
+    game_data: &GameData,
+    settings: &RandomizerSettings,
+) -> Result<()> {
+    let customize_settings = CustomizeSettings::default();
+    let size = MAP_GRID_SIZE * 8;
+    // Color and room of each pixel, or None for black pixels (matching the background):
+    type Pixel = Option<(Rgba<u8>, Option<RoomGeometryRoomIdx>)>;
+    let mut pixels: Vec<Vec<Pixel>> = vec![vec![None; size]; size];
+    for y in 0..MAP_GRID_SIZE {
+        for x in 0..MAP_GRID_SIZE {
+            let tile = &tiles[y][x];
+            let Some(area) = tile.area else {
+                continue;
+            };
+            let data = render_tile(tile.clone(), settings, &customize_settings)?;
+            for py in 0..8 {
+                for px in 0..8 {
+                    if [0, 4, 12].contains(&data[py][px]) {
+                        continue;
+                    }
+                    let color = get_explored_color(data[py][px], area);
+                    pixels[y * 8 + py][x * 8 + px] = Some((color, tile_rooms[y][x]));
+                }
+            }
+        }
+    }
+
+    type Run = (usize, usize, Rgba<u8>, Option<RoomGeometryRoomIdx>);
+    type Rect = (usize, usize, usize, usize, Rgba<u8>);
+    let mut rects: HashMap<Option<RoomGeometryRoomIdx>, Vec<Rect>> = HashMap::new();
+    // Runs continuing from the previous row, with the row where they started:
+    let mut active: HashMap<Run, usize> = HashMap::new();
+    for py in 0..=size {
+        let mut runs: Vec<Run> = vec![];
+        if py < size {
+            let row = &pixels[py];
+            let mut px = 0;
+            while px < size {
+                let Some((color, room)) = row[px] else {
+                    px += 1;
+                    continue;
+                };
+                let start = px;
+                while px < size && row[px] == Some((color, room)) {
+                    px += 1;
+                }
+                runs.push((start, px - start, color, room));
+            }
+        }
+        let mut next_active: HashMap<Run, usize> = HashMap::new();
+        for run in runs {
+            let start_y = active.remove(&run).unwrap_or(py);
+            next_active.insert(run, start_y);
+        }
+        for ((x, w, color, room), start_y) in active {
+            rects
+                .entry(room)
+                .or_default()
+                .push((x, start_y, w, py - start_y, color));
+        }
+        active = next_active;
+    }
+
+    // Emit the rectangles, sorted by room (with elevators and other tiles outside rooms first):
+    let mut room_keys: Vec<Option<RoomGeometryRoomIdx>> = rects.keys().copied().collect();
+    room_keys.sort();
+    out.push_str("<g id=\"layer-rooms\">\n");
+    for room in room_keys {
+        match room {
+            Some(room_idx) => {
+                let name = svg_escape(&game_data.room_geometry[room_idx].name);
+                out.push_str(&format!("<g><title>{name}</title>\n"));
+            }
+            None => out.push_str("<g>\n"),
+        }
+        let room_rects = rects.get_mut(&room).unwrap();
+        room_rects.sort_by_key(|&(x, y, _, _, _)| (y, x));
+        for &(x, y, w, h, color) in room_rects.iter() {
+            out.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{w}\" height=\"{h}\" fill=\"{}\"/>\n",
+                x + 8,
+                y + 8,
+                svg_color(color)
+            ));
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</g>\n");
+    Ok(())
+}
+
+// Render the map as an SVG image, with layers for the rooms (with room-name tooltips), items,
+// locked doors, objectives, the start location, and the escape route. Each layer can be shown or
+// hidden with a checkbox in the legend below the map. This is done with CSS alone, without scripts,
+// so that it is safe to serve the SVG directly.
+pub fn get_spoiler_svg(
+    randomization: &Randomization,
+    spoiler_log: &SpoilerLog,
+    game_data: &GameData,
+    settings: &RandomizerSettings,
+) -> Result<String> {
+    let map = &randomization.map;
+    let (tiles, tile_rooms) = get_base_tiles(randomization, game_data, settings);
+    let room_name =
+        |room_idx: RoomGeometryRoomIdx| svg_escape(&game_data.room_geometry[room_idx].name);
+
+    let map_size = (MAP_GRID_SIZE + 2) * 8;
+    let legend_height = 16;
+    let mut out = String::new();
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {map_size} {}\" shape-rendering=\"crispEdges\">\n",
+        map_size + legend_height
+    ));
+    let layers = [
+        ("rooms", "Rooms"),
+        ("items", "Items"),
+        ("doors", "Locked doors"),
+        ("objectives", "Objectives"),
+        ("start", "Start"),
+        ("escape", "Escape route"),
+    ];
+    out.push_str(
+        "<style>.legend { font: 7px sans-serif; color: #ffffff; display: flex; gap: 12px; \
+         padding-top: 4px; } \
+         .legend label { cursor: pointer; } \
+         .legend input { width: 7px; height: 7px; margin: 0 2px 0 0; vertical-align: -1px; }\n",
+    );
+    for (name, _) in layers {
+        out.push_str(&format!(
+            "svg:has(#show-{name}:not(:checked)) #layer-{name} {{ display: none; }}\n"
+        ));
+    }
+    out.push_str("</style>\n");
+    out.push_str(&format!(
+        "<rect width=\"{map_size}\" height=\"{}\" fill=\"#000000\"/>\n",
+        map_size + legend_height
+    ));
+
+    write_svg_rooms(&mut out, &tiles, &tile_rooms, game_data, settings)?;
+
+    // Items:
+    out.push_str("<g id=\"layer-items\">\n");
+    let item_coords = get_item_coords(randomization, game_data);
+    for (i, (&item, coords)) in randomization
+        .item_placement
+        .iter()
+        .zip(&item_coords)
+        .enumerate()
+    {
+        let Some((x, y)) = *coords else {
+            continue;
+        };
+        let (cx, cy) = tile_center(x, y);
+        let room_idx = game_data.room_idx_by_id[&game_data.item_locations[i].0];
+        let (r, fill) = if item.is_unique() {
+            (2.5, "#ffd700")
+        } else {
+            (1.5, "#ffffff")
+        };
+        out.push_str(&format!(
+            "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"{fill}\" stroke=\"#000000\" stroke-width=\"0.5\"><title>{item:?} ({})</title></circle>\n",
+            room_name(room_idx)
+        ));
+    }
+    out.push_str("</g>\n");
+
+    // Locked doors:
+    out.push_str("<g id=\"layer-doors\">\n");
+    for locked_door in &randomization.locked_doors {
+        let Some(color) = get_door_lock_color(locked_door.door_type) else {
+            continue;
+        };
+        let mut ptr_pairs = vec![locked_door.src_ptr_pair];
+        if locked_door.bidirectional {
+            ptr_pairs.push(locked_door.dst_ptr_pair);
+        }
+        for ptr_pair in ptr_pairs {
+            let (room_idx, door_idx) = game_data.room_and_door_idxs_by_door_ptr_pair[&ptr_pair];
+            if !map.room_mask[room_idx] {
+                continue;
+            }
+            let door = &game_data.room_geometry[room_idx].doors[door_idx];
+            let x0 = (map.rooms[room_idx].0 + door.x + 1) * 8;
+            let y0 = (map.rooms[room_idx].1 + door.y + 1) * 8;
+            let (x1, y1, x2, y2) = match door.direction.as_str() {
+                "left" => (x0 + 1, y0 + 1, x0 + 1, y0 + 7),
+                "right" => (x0 + 7, y0 + 1, x0 + 7, y0 + 7),
+                "up" => (x0 + 1, y0 + 1, x0 + 7, y0 + 1),
+                _ => (x0 + 1, y0 + 7, x0 + 7, y0 + 7),
+            };
+            out.push_str(&format!(
+                "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{}\" stroke-width=\"2\"><title>{:?} door ({})</title></line>\n",
+                svg_color(color),
+                locked_door.door_type,
+                room_name(room_idx)
+            ));
+        }
+    }
+    out.push_str("</g>\n");
+
+    // Objectives:
+    out.push_str("<g id=\"layer-objectives\">\n");
+    for objective in &randomization.objectives {
+        for (room_id, tile_x, tile_y) in get_objective_tiles(std::slice::from_ref(objective)) {
+            let room_idx = game_data.room_idx_by_id[&room_id];
+            if !map.room_mask[room_idx] {
+                continue;
+            }
+            let x = (map.rooms[room_idx].0 + tile_x + 1) * 8;
+            let y = (map.rooms[room_idx].1 + tile_y + 1) * 8;
+            out.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"6\" height=\"6\" fill=\"none\" stroke=\"#ff8000\" stroke-width=\"1\"><title>Objective: {objective:?} ({})</title></rect>\n",
+                x + 1,
+                y + 1,
+                room_name(room_idx)
+            ));
+        }
+    }
+    out.push_str("</g>\n");
+
+    // Start location:
+    out.push_str("<g id=\"layer-start\">\n");
+    let start = &randomization.start_location;
+    let start_room_idx = game_data.room_idx_by_id[&start.room_id];
+    let (cx, cy) = tile_center(
+        map.rooms[start_room_idx].0 + (start.x / 16.0) as usize,
+        map.rooms[start_room_idx].1 + (start.y / 16.0) as usize,
+    );
+    out.push_str(&format!(
+        "<polygon points=\"{},{cy} {cx},{} {},{cy} {cx},{}\" fill=\"#00ff80\" stroke=\"#000000\" stroke-width=\"0.5\"><title>Start: {}</title></polygon>\n",
+        cx - 4,
+        cy - 4,
+        cx + 4,
+        cy + 4,
+        svg_escape(&start.name)
+    ));
+    out.push_str("</g>\n");
+
+    // Escape route:
+    out.push_str("<g id=\"layer-escape\" fill=\"none\" stroke-linejoin=\"round\">\n");
+    let escape = &spoiler_log.escape;
+    let mut routes = vec![(&escape.ship_route, "Escape route", "")];
+    if let Some(animals_route) = &escape.animals_route {
+        routes.push((
+            animals_route,
+            "Escape route to the animals",
+            " stroke-dasharray=\"3,2\"",
+        ));
+    }
+    for (route, title, style) in routes {
+        let Some(first) = route.first() else {
+            continue;
+        };
+        let mut points = vec![tile_center(first.from.x, first.from.y)];
+        points.extend(
+            route
+                .iter()
+                .map(|entry| tile_center(entry.to.x, entry.to.y)),
+        );
+        let points_str: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+        out.push_str(&format!(
+            "<polyline points=\"{}\" stroke=\"#ff4040\" stroke-width=\"1.5\"{style}><title>{title} ({:.2} seconds)</title></polyline>\n",
+            points_str.join(" "),
+            escape.final_time_seconds
+        ));
+    }
+    out.push_str("</g>\n");
+
+    // Legend, with a checkbox for showing and hiding each layer:
+    out.push_str(&format!(
+        "<foreignObject x=\"8\" y=\"{map_size}\" width=\"{}\" height=\"{legend_height}\">\n\
+         <div xmlns=\"http://www.w3.org/1999/xhtml\" class=\"legend\">\n",
+        map_size - 16
+    ));
+    for (name, label) in layers {
+        out.push_str(&format!(
+            "<label><input type=\"checkbox\" id=\"show-{name}\" checked=\"checked\"/>{label}</label>\n"
+        ));
+    }
+    out.push_str("</div>\n</foreignObject>\n");
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+fn scale_tile_brightness(img: &mut RgbaImage, x: usize, y: usize, factor: f32) {
+    for py in 0..8 {
+        for px in 0..8 {
+            let pixel = img.get_pixel_mut(((x + 1) * 8 + px) as u32, ((y + 1) * 8 + py) as u32);
+            for c in 0..3 {
+                pixel[c] = (pixel[c] as f32 * factor) as u8;
+            }
+        }
+    }
+}
+
+// Render an animated GIF showing how the reachable part of the map expands with each step of the
+// randomization, based on the spoiler log. Tiles that are reachable but not yet able to return
+// (i.e., not bireachable) are dimmed, and tiles not yet reachable are mostly darkened, matching
+// how the spoiler visualizer shows a selected step. The key items collected in each step are
+// outlined, and a progress bar along the bottom shows the position in the sequence.
+pub fn get_spoiler_route_animation(
+    randomization: &Randomization,
+    spoiler_log: &SpoilerLog,
+    game_data: &GameData,
+    settings: &RandomizerSettings,
+) -> Result<Vec<u8>> {
+    let (base_img, _) = get_spoiler_images(randomization, game_data, settings, false)?;
+
+    // The step at which each map tile becomes reachable and bireachable (or 255 if never).
+    // Tiles not belonging to any room (e.g. elevators) are left as None and always shown.
+    let mut tile_steps: Vec<Vec<Option<(u8, u8)>>> = vec![vec![None; MAP_GRID_SIZE]; MAP_GRID_SIZE];
+    for room in &spoiler_log.all_rooms {
+        for (y, row) in room.map.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                if value == 0 {
+                    continue;
+                }
+                let reachable_step = room.map_reachable_step[y][x];
+                let bireachable_step = room.map_bireachable_step[y][x];
+                let tile = &mut tile_steps[room.coords.1 + y][room.coords.0 + x];
+                // The Toilet overlaps other rooms, so take the earliest step of any room:
+                *tile = Some(match *tile {
+                    Some((r, b)) => (r.min(reachable_step), b.min(bireachable_step)),
+                    None => (reachable_step, bireachable_step),
+                });
+            }
+        }
+    }
+
+    let mut steps: Vec<&SpoilerDetails> = spoiler_log.details.iter().collect();
+    steps.sort_by_key(|d| d.step);
+
+    let size = base_img.width();
+    let item_color = Rgba([255, 255, 255, 255]);
+    let progress_color = Rgba([255, 215, 0, 255]);
+    let mut out: Vec<u8> = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut out, 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+        for (i, details) in steps.iter().enumerate() {
+            let mut img = base_img.clone();
+            for y in 0..MAP_GRID_SIZE {
+                for x in 0..MAP_GRID_SIZE {
+                    let Some((reachable_step, bireachable_step)) = tile_steps[y][x] else {
+                        continue;
+                    };
+                    if (bireachable_step as usize) < details.step {
+                        continue;
+                    } else if (reachable_step as usize) < details.step {
+                        scale_tile_brightness(&mut img, x, y, 0.5);
+                    } else {
+                        scale_tile_brightness(&mut img, x, y, 0.2);
+                    }
+                }
+            }
+
+            // Outline the tiles of the items collected in this step:
+            for item in &details.items {
+                let (x, y) = item.location.coords;
+                if x >= MAP_GRID_SIZE || y >= MAP_GRID_SIZE {
+                    continue;
+                }
+                let x0 = (x as u32 + 1) * 8;
+                let y0 = (y as u32 + 1) * 8;
+                for j in 0..8 {
+                    img.put_pixel(x0 + j, y0, item_color);
+                    img.put_pixel(x0 + j, y0 + 7, item_color);
+                    img.put_pixel(x0, y0 + j, item_color);
+                    img.put_pixel(x0 + 7, y0 + j, item_color);
+                }
+            }
+
+            let progress_width = (size - 16) * (i as u32 + 1) / steps.len() as u32;
+            for px in 0..progress_width {
+                for py in 2..6 {
+                    img.put_pixel(8 + px, size - 8 + py, progress_color);
+                }
+            }
+
+            let delay_ms = if i + 1 == steps.len() { 3000 } else { 700 };
+            encoder.encode_frame(Frame::from_parts(
+                img,
+                0,
+                0,
+                Delay::from_numer_denom_ms(delay_ms, 1),
+            ))?;
+        }
+
+        // Finish with the fully revealed map:
+        encoder.encode_frame(Frame::from_parts(
+            base_img,
+            0,
+            0,
+            Delay::from_numer_denom_ms(3000, 1),
+        ))?;
+    }
+    Ok(out)
+}
+
+// Colors of the pause-map palette used for explored tiles (palette 2), as written into the ROM.
+// Colors 1 and 2 are area-themed and get set at runtime by map_area.asm, so for those we use the
+// same area colors as the spoiler maps.
+fn get_rom_map_palette(rom: &Rom, area: usize) -> Result<[Rgba<u8>; 16]> {
+    let mut palette = [get_rgb(0, 0, 0); 16];
+    for (i, color) in palette.iter_mut().enumerate().skip(1) {
+        let c = rom.read_u16(snes2pc(0xB6F000) + 2 * (0x20 + i))?;
+        *color = get_rgb(c & 31, (c >> 5) & 31, (c >> 10) & 31);
+    }
+    palette[1] = get_explored_color(1, area);
+    palette[2] = get_explored_color(2, area);
+    Ok(palette)
+}
+
+fn draw_rom_map_tile(
+    img: &mut RgbaImage,
+    rom: &Rom,
+    palette: &[Rgba<u8>; 16],
+    word: usize,
+    x: usize,
+    y: usize,
+) -> Result<()> {
+    let mut data = read_tile_4bpp(rom, snes2pc(TILE_GFX_ADDR_4BPP), word & 0x3FF)?;
+    if word & 0x4000 != 0 {
+        data = hflip_tile(data);
+    }
+    if word & 0x8000 != 0 {
+        data = vflip_tile(data);
+    }
+    for py in 0..8 {
+        for px in 0..8 {
+            let color = palette[data[py][px] as usize];
+            img.put_pixel((x * 8 + px) as u32, (y * 8 + py) as u32, color);
+        }
+    }
+    Ok(())
+}
+
+// Render the full map from the pause-screen tilemaps and tile graphics of a patched ROM, combining
+// the area map screens in the same layout as the spoiler map images, so it can be used to check
+// the output of `MapPatcher`. All tiles are shown as explored. Palette colors 1 and 2, which the
+// game sets at runtime, use the spoiler map's area colors instead (see `get_rom_map_palette`), so
+// those may differ from the pause screen. The result is scaled up by the given factor (using
+// nearest-neighbor), e.g. for printing.
+pub fn get_rom_map_image(
+    rom: &Rom,
+    randomization: &Randomization,
+    game_data: &GameData,
+    scale: u32,
+) -> Result<RgbaImage> {
+    let map = &randomization.map;
+
+    // Determine where each area's map screen is placed relative to the full map, based on the
+    // room positions written into the room headers:
+    let mut area_offsets: [Option<(isize, isize)>; NUM_AREAS] = [None; NUM_AREAS];
+    let mut area_tiles: Vec<Vec<Option<usize>>> = vec![vec![None; MAP_GRID_SIZE]; MAP_GRID_SIZE];
+    for (room_idx, room) in game_data.room_geometry.iter().enumerate() {
+        if !map.room_mask[room_idx] {
+            continue;
+        }
+        let area = map.area[room_idx];
+        let (room_x, room_y) = map.rooms[room_idx];
+        if area_offsets[area].is_none() {
+            let local_x = rom.read_u8(room.rom_address + 2)?;
+            let local_y = rom.read_u8(room.rom_address + 3)?;
+            area_offsets[area] = Some((room_x as isize - local_x, room_y as isize - local_y));
+        }
+        if room_idx == game_data.toilet_room_idx {
+            continue;
+        }
+        for (y, row) in room.map.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                if value != 0 {
+                    area_tiles[room_y + y][room_x + x] = Some(area);
+                }
+            }
+        }
+    }
+
+    let mut img = RgbaImage::new(
+        (MAP_GRID_SIZE + 2) as u32 * 8,
+        (MAP_GRID_SIZE + 2) as u32 * 8,
+    );
+    // Tiles outside of rooms (e.g. elevators) are drawn first, and then the room tiles, so that
+    // the arrows which each area shows in place of neighboring rooms in other areas get covered.
+    for rooms_pass in [false, true] {
+        for (area, offset) in area_offsets.iter().enumerate() {
+            let Some((offset_x, offset_y)) = *offset else {
+                continue;
+            };
+            let palette = get_rom_map_palette(rom, area)?;
+            let base_ptr = game_data.area_map_ptrs[area] as usize;
+            for y in 0..MAP_GRID_SIZE {
+                for x in 0..MAP_GRID_SIZE {
+                    let in_room = area_tiles[y][x].is_some();
+                    if in_room != rooms_pass || (in_room && area_tiles[y][x] != Some(area)) {
+                        continue;
+                    }
+                    let local_x = x as isize - offset_x;
+                    let local_y = y as isize - offset_y;
+                    if !(0..64).contains(&local_x) || !(-1..31).contains(&local_y) {
+                        continue;
+                    }
+                    let offset = xy_to_map_offset(local_x, local_y) as usize;
+                    let word = rom.read_u16(base_ptr + offset)? as usize;
+                    if word & 0x3FF == 0x1F {
+                        // Blank tile
+                        continue;
+                    }
+                    draw_rom_map_tile(&mut img, rom, &palette, word, x + 1, y + 1)?;
+                }
+            }
+        }
+    }
+
+    Ok(image::imageops::resize(
+        &img,
+        img.width() * scale,
+        img.height() * scale,
+        image::imageops::FilterType::Nearest,
+    ))
+}
@@ -0,0 +1,47 @@
+// Data shared by the unit tests which need the full game data (sm-json-data, presets, and maps).
+// It is loaded once, the same way as in the benchmarks, with paths relative to the workspace
+// directory rather than the current directory.
+
+use crate::preset::PresetData;
+use maprando_game::GameData;
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+struct TestData {
+    game_data: GameData,
+    preset_data: PresetData,
+}
+
+fn get_test_data() -> &'static TestData {
+    static DATA: OnceLock<TestData> = OnceLock::new();
+    DATA.get_or_init(|| {
+        let base_path = workspace_path("");
+        let game_data = GameData::load_from_dir(&base_path).unwrap();
+        let preset_data = PresetData::load(
+            &base_path.join("data/tech_data.json"),
+            &base_path.join("data/notable_data.json"),
+            &base_path.join("data/presets"),
+            &game_data,
+        )
+        .unwrap();
+        TestData {
+            game_data,
+            preset_data,
+        }
+    })
+}
+
+// Path of a data file, given relative to the workspace directory (`rust/`):
+pub fn workspace_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(path)
+}
+
+pub fn game_data() -> &'static GameData {
+    &get_test_data().game_data
+}
+
+pub fn preset_data() -> &'static PresetData {
+    &get_test_data().preset_data
+}
@@ -0,0 +1,431 @@
+// Logic-aware item tracking: given the items and flags collected so far in a seed, determine
+// which item locations, doors, and flags are currently in logic. Only information which the
+// player already has (the map, locked doors, objectives, and settings) is used, not the item
+// placement, so this can power a tracker without revealing the spoiler.
+
+use anyhow::{Context, Result, bail};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    randomize::{
+        DifficultyConfig, LockedDoor, Preprocessor, Randomization, filter_links,
+        get_initial_flag_vec, get_locked_door_data, normalize::normalize_links,
+    },
+    settings::{ItemCount, Objective, RandomizerSettings},
+    traverse::{
+        LockedDoorData, Traverser, apply_requirement,
+        explain::{UnreachableExplanation, explain_unreachable},
+        get_bireachable_idxs,
+        item_sets::{LocationItemSets, get_location_item_sets, get_vertex_item_sets},
+    },
+};
+use maprando_game::{
+    DoorType, GameData, Item, LinksDataGroup, Map, NodeId, Requirement, RoomId, StartLocation,
+    VertexId, VertexKey,
+};
+use maprando_logic::{GlobalState, Inventory, LocalState};
+
+// The parts of a seed needed for tracking, published alongside the seed.
+#[derive(Serialize, Deserialize)]
+pub struct TrackerData {
+    pub settings: RandomizerSettings,
+    pub difficulty: DifficultyConfig,
+    pub map: Map,
+    pub objectives: Vec<Objective>,
+    pub locked_doors: Vec<LockedDoor>,
+    pub start_location: StartLocation,
+}
+
+impl TrackerData {
+    pub fn new(
+        randomization: &Randomization,
+        settings: &RandomizerSettings,
+        difficulty: &DifficultyConfig,
+    ) -> Self {
+        TrackerData {
+            settings: settings.clone(),
+            difficulty: difficulty.clone(),
+            map: randomization.map.clone(),
+            objectives: randomization.objectives.clone(),
+            locked_doors: randomization.locked_doors.clone(),
+            start_location: randomization.start_location.clone(),
+        }
+    }
+}
+
+// Progress reported by the player:
+#[derive(Serialize, Deserialize, Default)]
+pub struct TrackerProgress {
+    pub items: Vec<ItemCount>, // Collected items, not including starting items
+    pub flags: Vec<String>,    // Flags which have been set, e.g. "f_DefeatedPhantoon"
+    pub doors_unlocked: Vec<usize>, // Indexes (into TrackerData.locked_doors) of opened doors
+}
+
+impl TrackerProgress {
+    // Progress with every item collected, with the counts from the item pool (or one of each
+    // item not in the pool).
+    pub fn all_items(settings: &RandomizerSettings, game_data: &GameData) -> Result<Self> {
+        let mut progress = TrackerProgress::default();
+        for name in &game_data.item_isv.keys {
+            let item = Item::from_str(name).with_context(|| format!("unknown item: {name}"))?;
+            if item == Item::Nothing || item.progressive_sequence().is_some() {
+                continue;
+            }
+            let count = settings
+                .item_progression_settings
+                .item_pool
+                .iter()
+                .find(|x| x.item == item)
+                .map(|x| x.count)
+                .unwrap_or(1);
+            progress.items.push(ItemCount { item, count });
+        }
+        Ok(progress)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrackerItemLocation {
+    pub room_id: RoomId,
+    pub node_id: NodeId,
+    pub reachable: bool,
+    pub bireachable: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrackerDoor {
+    pub index: usize,
+    pub room_id: RoomId,
+    pub node_id: NodeId,
+    pub door_type: DoorType,
+    pub reachable: bool,
+    pub bireachable: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrackerFlag {
+    pub name: String,
+    pub reachable: bool,
+    pub bireachable: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrackerStatus {
+    pub item_locations: Vec<TrackerItemLocation>,
+    pub doors: Vec<TrackerDoor>,
+    pub flags: Vec<TrackerFlag>,
+}
+
+pub struct Tracker {
+    pub data: TrackerData,
+    locked_door_data: LockedDoorData,
+    base_links_data: LinksDataGroup,
+    seed_links_data: LinksDataGroup,
+    door_map: HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+    start_vertex_id: VertexId,
+    start_requirement: Requirement,
+}
+
+impl Tracker {
+    pub fn new(data: TrackerData, game_data: &GameData) -> Result<Self> {
+        let num_vertices = game_data.vertex_isv.keys.len();
+        let base_links_data = LinksDataGroup::new(
+            normalize_links(
+                &filter_links(&game_data.links, game_data, &data.difficulty),
+                &data.difficulty,
+            ),
+            num_vertices,
+            0,
+        );
+        let preprocessor = Preprocessor::new(game_data, &data.map, &data.difficulty);
+        let seed_links_data = LinksDataGroup::new(
+            preprocessor.get_all_door_links(),
+            num_vertices,
+            base_links_data.links.len(),
+        );
+        let door_map = preprocessor.door_map;
+        let locked_door_data = get_locked_door_data(data.locked_doors.clone(), game_data);
+
+        let start = &data.start_location;
+        let start_vertex_id = *game_data
+            .vertex_isv
+            .index_by_key
+            .get(&VertexKey {
+                room_id: start.room_id,
+                node_id: start.node_id,
+                obstacle_mask: 0,
+                actions: vec![],
+            })
+            .with_context(|| format!("invalid start location: {}", start.name))?;
+        // The ship start is not among the start locations, and has no requirements:
+        let start_requirement = match game_data
+            .start_location_id_map
+            .get(&(start.room_id, start.node_id))
+        {
+            Some(&idx) => game_data.start_locations[idx]
+                .requires_parsed
+                .clone()
+                .unwrap(),
+            None => Requirement::Free,
+        };
+
+        Ok(Tracker {
+            data,
+            locked_door_data,
+            base_links_data,
+            seed_links_data,
+            door_map,
+            start_vertex_id,
+            start_requirement,
+        })
+    }
+
+    fn get_global_state(
+        &self,
+        progress: &TrackerProgress,
+        game_data: &GameData,
+    ) -> Result<GlobalState> {
+        let settings = &self.data.settings;
+        let items = vec![false; game_data.item_isv.keys.len()];
+        let weapon_mask = game_data.get_weapon_mask(&items, &self.data.difficulty.tech);
+        let mut global = GlobalState {
+            inventory: Inventory {
+                items,
+                max_energy: 99,
+                max_reserves: 0,
+                max_missiles: 0,
+                max_supers: 0,
+                max_power_bombs: 0,
+                collectible_missile_packs: 0,
+                collectible_super_packs: 0,
+                collectible_power_bomb_packs: 0,
+            },
+            flags: get_initial_flag_vec(settings, game_data),
+            doors_unlocked: vec![false; self.locked_door_data.locked_doors.len()],
+            weapon_mask,
+        };
+
+        // Resources are assumed to be refilled at the start location, so the effect of
+        // collecting items on the local state is ignored.
+        let mut collect_local = LocalState::full();
+        for x in settings
+            .item_progression_settings
+            .starting_items
+            .iter()
+            .chain(progress.items.iter())
+        {
+            for _ in 0..x.count {
+                global.collect(
+                    x.item,
+                    game_data,
+                    settings.item_progression_settings.ammo_collect_fraction,
+                    &self.data.difficulty.tech,
+                    &mut collect_local,
+                );
+            }
+        }
+
+        for flag_name in &progress.flags {
+            let Some(&flag_idx) = game_data.flag_isv.index_by_key.get(flag_name) else {
+                bail!("unrecognized flag: {flag_name}");
+            };
+            global.flags[flag_idx] = true;
+        }
+        for &door_idx in &progress.doors_unlocked {
+            if door_idx >= global.doors_unlocked.len() {
+                bail!("invalid locked door index: {door_idx}");
+            }
+            global.doors_unlocked[door_idx] = true;
+        }
+        Ok(global)
+    }
+
+    fn traverse(&self, global: &GlobalState, reverse: bool, game_data: &GameData) -> Traverser {
+        let mut traverser = Traverser::new(game_data.vertex_isv.keys.len(), reverse, global);
+        let init_local = if reverse {
+            Some(LocalState::full())
+        } else {
+            apply_requirement(
+                &self.start_requirement,
+                global,
+                LocalState::full(),
+                false,
+                &self.data.settings,
+                &self.data.difficulty,
+                game_data,
+                &self.door_map,
+                &self.locked_door_data,
+                &self.data.objectives,
+            )
+        };
+        if let Some(local) = init_local {
+            traverser.add_origin(local, self.start_vertex_id, global);
+        }
+        traverser.traverse(
+            &self.base_links_data,
+            &self.seed_links_data,
+            global,
+            &self.data.settings,
+            &self.data.difficulty,
+            game_data,
+            &self.door_map,
+            &self.locked_door_data,
+            &self.data.objectives,
+            0,
+        );
+        traverser
+    }
+
+    // Determine what is in logic with the given progress. A location is "reachable" if it can be
+    // reached from the start location, and "bireachable" if it is also possible to return.
+    pub fn get_status(
+        &self,
+        progress: &TrackerProgress,
+        game_data: &GameData,
+    ) -> Result<TrackerStatus> {
+        let global = self.get_global_state(progress, game_data)?;
+        let forward = self.traverse(&global, false, game_data);
+        let reverse = self.traverse(&global, true, game_data);
+
+        let get_reachability = |vertex_ids: &[VertexId]| -> (bool, bool) {
+            let mut reachable = false;
+            let mut bireachable = false;
+            for &v in vertex_ids {
+                if forward.cost[v].iter().any(|&x| f32::is_finite(x)) {
+                    reachable = true;
+                    if get_bireachable_idxs(&global, v, &forward, &reverse).is_some() {
+                        bireachable = true;
+                    }
+                }
+            }
+            (reachable, bireachable)
+        };
+
+        let mut item_locations = vec![];
+        for (i, &(room_id, node_id)) in game_data.item_locations.iter().enumerate() {
+            let room_idx = game_data.room_idx_by_id[&room_id];
+            if !self.data.map.room_mask[room_idx] {
+                continue;
+            }
+            let (reachable, bireachable) = get_reachability(&game_data.item_vertex_ids[i]);
+            item_locations.push(TrackerItemLocation {
+                room_id,
+                node_id,
+                reachable,
+                bireachable,
+            });
+        }
+
+        let mut doors = vec![];
+        for (i, door) in self.locked_door_data.locked_doors.iter().enumerate() {
+            if door.door_type == DoorType::Wall {
+                continue;
+            }
+            let (room_id, node_id) = game_data.door_ptr_pair_map[&door.src_ptr_pair];
+            let (reachable, bireachable) =
+                get_reachability(&self.locked_door_data.locked_door_vertex_ids[i]);
+            doors.push(TrackerDoor {
+                index: i,
+                room_id,
+                node_id,
+                door_type: door.door_type,
+                reachable,
+                bireachable,
+            });
+        }
+
+        let mut flags = vec![];
+        for (i, &flag_id) in game_data.flag_ids.iter().enumerate() {
+            let (reachable, bireachable) = get_reachability(&game_data.flag_vertex_ids[i]);
+            flags.push(TrackerFlag {
+                name: game_data.flag_isv.keys[flag_id].clone(),
+                reachable,
+                bireachable,
+            });
+        }
+
+        Ok(TrackerStatus {
+            item_locations,
+            doors,
+            flags,
+        })
+    }
+
+    // Status with all items collected, setting flags as they become bireachable until no more
+    // can be obtained.
+    pub fn get_full_status(&self, game_data: &GameData) -> Result<TrackerStatus> {
+        let mut progress = TrackerProgress::all_items(&self.data.settings, game_data)?;
+        loop {
+            let status = self.get_status(&progress, game_data)?;
+            let mut new_flags = false;
+            for flag in &status.flags {
+                if flag.bireachable && !progress.flags.contains(&flag.name) {
+                    progress.flags.push(flag.name.clone());
+                    new_flags = true;
+                }
+            }
+            if !new_flags {
+                return Ok(status);
+            }
+        }
+    }
+
+    // Explain why a node is not reachable with the given progress, or with `reverse`, why it is
+    // not possible to return from it to the start location.
+    pub fn explain(
+        &self,
+        progress: &TrackerProgress,
+        room_id: RoomId,
+        node_id: NodeId,
+        reverse: bool,
+        game_data: &GameData,
+    ) -> Result<UnreachableExplanation> {
+        let target_vertex_ids: Vec<VertexId> = game_data
+            .vertex_isv
+            .keys
+            .iter()
+            .enumerate()
+            .filter(|(_, k)| k.room_id == room_id && k.node_id == node_id)
+            .map(|(i, _)| i)
+            .collect();
+        if target_vertex_ids.is_empty() {
+            bail!("invalid node: ({room_id}, {node_id})");
+        }
+        let global = self.get_global_state(progress, game_data)?;
+        let traverser = self.traverse(&global, reverse, game_data);
+        Ok(explain_unreachable(
+            &target_vertex_ids,
+            &traverser,
+            &self.base_links_data,
+            &self.seed_links_data,
+            &global,
+            &self.data.settings,
+            &self.data.difficulty,
+            game_data,
+            &self.door_map,
+            &self.locked_door_data,
+            &self.data.objectives,
+            5,
+        ))
+    }
+
+    // The minimal combinations of items and flags needed to reach each item location, ignoring
+    // resource amounts (see `traverse::item_sets`). This does not depend on progress.
+    pub fn get_item_sets(&self, game_data: &GameData, max_sets: usize) -> Vec<LocationItemSets> {
+        let vertex_sets = get_vertex_item_sets(
+            &self.base_links_data,
+            &self.seed_links_data,
+            self.start_vertex_id,
+            &self.start_requirement,
+            &self.data.settings,
+            &self.data.difficulty,
+            game_data,
+            &self.locked_door_data,
+            &self.data.objectives,
+            max_sets,
+        );
+        get_location_item_sets(&vertex_sets, &self.data.map.room_mask, game_data, max_sets)
+    }
+}
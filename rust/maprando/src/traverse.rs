@@ -1,3 +1,6 @@
+pub mod explain;
+pub mod item_sets;
+
 use std::cmp::{max, min};
 
 use hashbrown::HashMap;
@@ -15,8 +18,10 @@ use maprando_game::{
 use maprando_logic::{
     GlobalState, IMPOSSIBLE_LOCAL_STATE, Inventory, LocalState,
     boss_requirements::{
-        apply_botwoon_requirement, apply_draygon_requirement, apply_mother_brain_2_requirement,
-        apply_phantoon_requirement, apply_ridley_requirement,
+        apply_botwoon_requirement, apply_crocomire_requirement, apply_draygon_requirement,
+        apply_golden_torizo_requirement, apply_kraid_requirement, apply_mother_brain_1_requirement,
+        apply_mother_brain_2_requirement, apply_phantoon_requirement, apply_ridley_requirement,
+        apply_spore_spawn_requirement,
     },
     helpers::{suit_damage_factor, validate_energy},
 };
@@ -527,71 +532,6 @@ fn get_heated_speedball_tiles(difficulty: &DifficultyConfig) -> f32 {
     difficulty.speed_ball_tiles + heat_leniency
 }
 
-pub fn debug_requirement(
-    req: &Requirement,
-    global: &GlobalState,
-    local: LocalState,
-    reverse: bool,
-    settings: &RandomizerSettings,
-    difficulty: &DifficultyConfig,
-    game_data: &GameData,
-    door_map: &HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
-    locked_door_data: &LockedDoorData,
-    objectives: &[Objective],
-) {
-    println!(
-        "{:?}: {:?}",
-        req,
-        apply_requirement(
-            req,
-            global,
-            local,
-            reverse,
-            settings,
-            difficulty,
-            game_data,
-            door_map,
-            locked_door_data,
-            objectives
-        )
-    );
-    match req {
-        Requirement::And(reqs) => {
-            for r in reqs {
-                debug_requirement(
-                    r,
-                    global,
-                    local,
-                    reverse,
-                    settings,
-                    difficulty,
-                    game_data,
-                    door_map,
-                    locked_door_data,
-                    objectives,
-                );
-            }
-        }
-        Requirement::Or(reqs) => {
-            for r in reqs {
-                debug_requirement(
-                    r,
-                    global,
-                    local,
-                    reverse,
-                    settings,
-                    difficulty,
-                    game_data,
-                    door_map,
-                    locked_door_data,
-                    objectives,
-                );
-            }
-        }
-        _ => {}
-    }
-}
-
 fn apply_missiles_available_req(
     local: LocalState,
     global: &GlobalState,
@@ -1721,6 +1661,41 @@ pub fn apply_requirement(
                 *r_mode,
             )
         }
+        Requirement::KraidFight => apply_kraid_requirement(
+            &global.inventory,
+            local,
+            difficulty.kraid_proficiency,
+            can_manage_reserves,
+        ),
+        Requirement::CrocomireFight => apply_crocomire_requirement(
+            &global.inventory,
+            local,
+            difficulty.crocomire_proficiency,
+            can_manage_reserves,
+        ),
+        Requirement::SporeSpawnFight => apply_spore_spawn_requirement(
+            &global.inventory,
+            local,
+            difficulty.spore_spawn_proficiency,
+            can_manage_reserves,
+        ),
+        Requirement::GoldenTorizoFight {
+            can_be_very_patient_tech_idx,
+        } => apply_golden_torizo_requirement(
+            &global.inventory,
+            local,
+            difficulty.golden_torizo_proficiency,
+            can_manage_reserves,
+            difficulty.tech[*can_be_very_patient_tech_idx],
+        ),
+        // Mother Brain 1 is still fought with the "Skip" Mother Brain fight setting.
+        Requirement::MotherBrain1Fight => apply_mother_brain_1_requirement(
+            &global.inventory,
+            local,
+            difficulty.mother_brain_proficiency,
+            settings.quality_of_life_settings.supers_double,
+            can_manage_reserves,
+        ),
         Requirement::SpeedBall { used_tiles, heated } => {
             if !difficulty.tech[game_data.speed_ball_tech_idx]
                 || !global.inventory.items[Item::Morph as usize]
@@ -2156,6 +2131,84 @@ pub struct TraversalStep {
     pub start_step_trail_idx: usize,
     pub step_num: usize,
     pub global_state: GlobalState,
+    // Tech and notables of the difficulty used, if the step ended with a completed traversal.
+    // The local states are then a fixed point for this difficulty and `global_state`, which lets
+    // the next traversal skip links whose outcome can't have changed.
+    pub traversed_difficulty: Option<(Vec<bool>, Vec<bool>)>,
+}
+
+// State needed to undo `Traverser::rewind`:
+pub struct TraverserCheckpoint {
+    // Popped steps (latest first), each with the vertex values to redo, in reverse order:
+    steps: Vec<(TraversalStep, Vec<TraversalUpdate>)>,
+    step_trails: Vec<StepTrail>,
+    step: TraversalStep,
+}
+
+// What the outcome of applying a link can depend on, besides the source local state:
+#[derive(Clone)]
+enum LinkDependency {
+    Any,
+    // Only the tech and notables of the difficulty, the inventory capacities, and these items
+    // and flags:
+    Explicit {
+        items: Vec<usize>,
+        flags: Vec<usize>,
+    },
+}
+
+fn collect_explicit_dependencies(
+    req: &Requirement,
+    items: &mut Vec<usize>,
+    flags: &mut Vec<usize>,
+) -> bool {
+    match req {
+        Requirement::Free
+        | Requirement::Never
+        | Requirement::Tech(_)
+        | Requirement::Notable(_)
+        | Requirement::NotFlag(_)
+        | Requirement::DisableableETank
+        | Requirement::MissilesCapacity(_)
+        | Requirement::SupersCapacity(_)
+        | Requirement::PowerBombsCapacity(_)
+        | Requirement::RegularEnergyCapacity(_)
+        | Requirement::ReserveEnergyCapacity(_) => true,
+        Requirement::Item(item_id) => {
+            items.push(*item_id);
+            true
+        }
+        Requirement::Flag(flag_id) => {
+            flags.push(*flag_id);
+            true
+        }
+        Requirement::Walljump => {
+            items.push(Item::WallJump as usize);
+            true
+        }
+        Requirement::And(reqs) | Requirement::Or(reqs) => reqs
+            .iter()
+            .all(|r| collect_explicit_dependencies(r, items, flags)),
+        _ => false,
+    }
+}
+
+fn get_link_dependency(link: &Link) -> LinkDependency {
+    let mut items = vec![];
+    let mut flags = vec![];
+    if collect_explicit_dependencies(&link.requirement, &mut items, &mut flags) {
+        LinkDependency::Explicit { items, flags }
+    } else {
+        LinkDependency::Any
+    }
+}
+
+fn same_capacities(a: &Inventory, b: &Inventory) -> bool {
+    a.max_energy == b.max_energy
+        && a.max_reserves == b.max_reserves
+        && a.max_missiles == b.max_missiles
+        && a.max_supers == b.max_supers
+        && a.max_power_bombs == b.max_power_bombs
 }
 
 #[derive(Clone)]
@@ -2167,6 +2220,8 @@ pub struct Traverser {
     pub cost: Vec<[f32; NUM_COST_METRICS]>,
     pub step: TraversalStep,
     pub past_steps: Vec<TraversalStep>,
+    // Dependencies of each link, by link index. A traverser is always used with the same links.
+    link_deps: Vec<LinkDependency>,
 }
 
 impl Traverser {
@@ -2182,8 +2237,10 @@ impl Traverser {
                 start_step_trail_idx: 0,
                 step_num: 0,
                 global_state: global_state.clone(),
+                traversed_difficulty: None,
             },
             past_steps: vec![],
+            link_deps: vec![],
         }
     }
 
@@ -2225,6 +2282,7 @@ impl Traverser {
             start_step_trail_idx: self.step_trails.len(),
             step_num: 0,
             global_state: self.step.global_state.clone(),
+            traversed_difficulty: None,
         };
         std::mem::swap(&mut self.step, &mut step);
         step.step_num = step_num;
@@ -2242,6 +2300,108 @@ impl Traverser {
         self.step.start_step_trail_idx = self.step_trails.len();
     }
 
+    // Pop steps until `num_steps` remain, the same as repeatedly calling `pop_step`, but keeping
+    // what is needed to undo this with `restore`. This is much cheaper than cloning the traverser
+    // to look at a past state. Any steps added after rewinding must be popped before restoring.
+    pub fn rewind(&mut self, num_steps: usize) -> TraverserCheckpoint {
+        let open_step = self.step.clone();
+        let mut steps = vec![];
+        while self.past_steps.len() > num_steps {
+            let step = self.past_steps.pop().unwrap();
+            let mut redo = Vec::with_capacity(step.updates.len());
+            for u in step.updates.iter().rev() {
+                redo.push(TraversalUpdate {
+                    vertex_id: u.vertex_id,
+                    old_start_trail_id: self.start_trail_ids[u.vertex_id],
+                    old_local_state: self.local_states[u.vertex_id],
+                    old_cost: self.cost[u.vertex_id],
+                });
+                self.start_trail_ids[u.vertex_id] = u.old_start_trail_id;
+                self.local_states[u.vertex_id] = u.old_local_state;
+                self.cost[u.vertex_id] = u.old_cost;
+            }
+            self.step.start_step_trail_idx = step.start_step_trail_idx;
+            steps.push((step, redo));
+        }
+        let step_trails = self.step_trails.split_off(self.step.start_step_trail_idx);
+        TraverserCheckpoint {
+            steps,
+            step_trails,
+            step: open_step,
+        }
+    }
+
+    pub fn restore(&mut self, checkpoint: TraverserCheckpoint) {
+        assert_eq!(self.step_trails.len(), self.step.start_step_trail_idx);
+        for (step, redo) in checkpoint.steps.into_iter().rev() {
+            for u in redo.iter().rev() {
+                self.start_trail_ids[u.vertex_id] = u.old_start_trail_id;
+                self.local_states[u.vertex_id] = u.old_local_state;
+                self.cost[u.vertex_id] = u.old_cost;
+            }
+            self.past_steps.push(step);
+        }
+        self.step_trails.extend(checkpoint.step_trails);
+        self.step = checkpoint.step;
+    }
+
+    // Determine which links need to be applied from vertices whose local states are unchanged
+    // since the last traversal, based on what changed in the global state and difficulty since
+    // then. Applying any other link is guaranteed to not improve anything, so skipping it does
+    // not change the result. Returns None if all links need to be applied.
+    fn get_affected_links(
+        &mut self,
+        base_links_data: &LinksDataGroup,
+        seed_links_data: &LinksDataGroup,
+        global: &GlobalState,
+        difficulty: &DifficultyConfig,
+    ) -> Option<Vec<bool>> {
+        let last_step = self.past_steps.last()?;
+        let (last_tech, last_notables) = last_step.traversed_difficulty.as_ref()?;
+        let last_global = &last_step.global_state;
+        if *last_tech != difficulty.tech
+            || *last_notables != difficulty.notables
+            || !same_capacities(&last_global.inventory, &global.inventory)
+        {
+            return None;
+        }
+
+        let num_links = base_links_data.links.len() + seed_links_data.links.len();
+        if self.link_deps.len() != num_links {
+            self.link_deps = base_links_data
+                .links
+                .iter()
+                .chain(seed_links_data.links.iter())
+                .map(get_link_dependency)
+                .collect();
+        }
+        let item_changed: Vec<bool> = global
+            .inventory
+            .items
+            .iter()
+            .zip(&last_global.inventory.items)
+            .map(|(a, b)| a != b)
+            .collect();
+        let flag_changed: Vec<bool> = global
+            .flags
+            .iter()
+            .zip(&last_global.flags)
+            .map(|(a, b)| a != b)
+            .collect();
+        Some(
+            self.link_deps
+                .iter()
+                .map(|dep| match dep {
+                    LinkDependency::Any => true,
+                    LinkDependency::Explicit { items, flags } => {
+                        items.iter().any(|&i| item_changed[i])
+                            || flags.iter().any(|&f| flag_changed[f])
+                    }
+                })
+                .collect(),
+        )
+    }
+
     pub fn traverse(
         &mut self,
         base_links_data: &LinksDataGroup,
@@ -2255,15 +2415,31 @@ impl Traverser {
         objectives: &[Objective],
         step_num: usize,
     ) {
+        let num_vertices = self.cost.len();
+        let affected_links =
+            self.get_affected_links(base_links_data, seed_links_data, global, difficulty);
         self.step.global_state = global.clone();
-        let mut modified_vertices: HashMap<usize, [bool; NUM_COST_METRICS]> = HashMap::new();
 
+        // Vertices changed since the last traversal (by `add_origin`) need all their links applied:
+        let mut pending = vec![false; num_vertices];
+        for u in &self.step.updates {
+            pending[u.vertex_id] = true;
+        }
+
+        // The frontier of vertices to process, with which of their cost metrics were modified.
+        // Vertices are processed in sorted order, to make the traversal deterministic.
+        let mut modified_vertices: Vec<VertexId> = vec![];
+        let mut modified_costs = vec![[false; NUM_COST_METRICS]; num_vertices];
         for (v, cost) in self.cost.iter().enumerate() {
             let valid = cost.map(f32::is_finite);
             if valid.iter().any(|&x| x) {
-                modified_vertices.insert(v, valid);
+                modified_vertices.push(v);
+                modified_costs[v] = valid;
             }
         }
+        let mut new_modified_vertices: Vec<VertexId> = vec![];
+        let mut new_modified_costs = vec![[false; NUM_COST_METRICS]; num_vertices];
+        let mut first_round = true;
 
         let base_links_by_src: &Vec<Vec<(StepTrailId, Link)>> = if self.reverse {
             &base_links_data.links_by_dst
@@ -2277,20 +2453,12 @@ impl Traverser {
         };
 
         while !modified_vertices.is_empty() {
-            let mut new_modified_vertices: HashMap<usize, [bool; NUM_COST_METRICS]> =
-                HashMap::new();
-            let modified_vertices_vec = {
-                // Process the vertices in sorted order, to make the traversal deterministic.
-                let mut m: Vec<(usize, [bool; NUM_COST_METRICS])> =
-                    modified_vertices.into_iter().collect();
-                m.sort();
-                m
-            };
-            for &(src_id, modified_costs) in &modified_vertices_vec {
+            for &src_id in &modified_vertices {
+                let src_modified_costs = modified_costs[src_id];
                 let src_local_state_arr = self.local_states[src_id];
                 let src_trail_id_arr = self.start_trail_ids[src_id];
                 for src_cost_idx in 0..NUM_COST_METRICS {
-                    if !modified_costs[src_cost_idx] {
+                    if !src_modified_costs[src_cost_idx] {
                         continue;
                     }
                     let src_local_state = src_local_state_arr[src_cost_idx];
@@ -2298,11 +2466,19 @@ impl Traverser {
                     {
                         continue;
                     }
+                    // In the first round, a state which is unchanged since the last traversal only
+                    // needs the links affected by the changes in the global state applied:
+                    let affected = affected_links.as_ref().filter(|_| {
+                        first_round && !pending[src_id] && !new_modified_costs[src_id][src_cost_idx]
+                    });
                     let src_trail_id = src_trail_id_arr[src_cost_idx];
                     let all_src_links = base_links_by_src[src_id]
                         .iter()
                         .chain(seed_links_by_src[src_id].iter());
                     for &(link_idx, ref link) in all_src_links {
+                        if affected.is_some_and(|a| !a[link_idx as usize]) {
+                            continue;
+                        }
                         let dst_id = link.to_vertex_id;
                         let dst_old_cost_arr = self.cost[dst_id];
                         if let Some(dst_new_local_state) = apply_link(
@@ -2327,10 +2503,7 @@ impl Traverser {
                             };
                             let new_trail_id = self.step_trails.len() as StepTrailId;
                             let mut any_improvement: bool = false;
-                            let mut improved_arr: [bool; NUM_COST_METRICS] = new_modified_vertices
-                                .get(&dst_id)
-                                .copied()
-                                .unwrap_or([false; NUM_COST_METRICS]);
+                            let mut improved_arr = new_modified_costs[dst_id];
 
                             let mut new_local_state = self.local_states[dst_id];
                             let mut new_start_trail_ids = self.start_trail_ids[dst_id];
@@ -2369,14 +2542,26 @@ impl Traverser {
                                     new_cost,
                                 );
                                 self.step_trails.push(new_step_trail);
-                                new_modified_vertices.insert(dst_id, improved_arr);
+                                if !new_modified_costs[dst_id].iter().any(|&x| x) {
+                                    new_modified_vertices.push(dst_id);
+                                }
+                                new_modified_costs[dst_id] = improved_arr;
                             }
                         }
                     }
                 }
             }
-            modified_vertices = new_modified_vertices;
+            for &v in &modified_vertices {
+                modified_costs[v] = [false; NUM_COST_METRICS];
+            }
+            new_modified_vertices.sort_unstable();
+            std::mem::swap(&mut modified_vertices, &mut new_modified_vertices);
+            std::mem::swap(&mut modified_costs, &mut new_modified_costs);
+            new_modified_vertices.clear();
+            first_round = false;
         }
+        self.step.traversed_difficulty =
+            Some((difficulty.tech.clone(), difficulty.notables.clone()));
         self.finish_step(step_num);
     }
 }
@@ -2396,3 +2581,258 @@ pub fn get_spoiler_trail_ids(
     steps.reverse();
     steps
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::path::Path;
+
+    const NUM_VERTICES: usize = 24;
+    const NUM_FLAGS: usize = 4;
+    const NUM_TECH: usize = 2;
+
+    struct Fixture {
+        settings: RandomizerSettings,
+        game_data: GameData,
+        base_links_data: LinksDataGroup,
+        seed_links_data: LinksDataGroup,
+        door_map: HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+        locked_door_data: LockedDoorData,
+    }
+
+    fn random_requirement(rng: &mut StdRng, depth: usize) -> Requirement {
+        let num_kinds = if depth == 0 { 7 } else { 9 };
+        match rng.gen_range(0..num_kinds) {
+            0 => Requirement::Free,
+            1 => Requirement::Item(rng.gen_range(0..6)),
+            2 => Requirement::Flag(rng.gen_range(0..NUM_FLAGS)),
+            3 => Requirement::Tech(rng.gen_range(0..NUM_TECH)),
+            4 => Requirement::Missiles(rng.gen_range(1..=5)),
+            5 => Requirement::Supers(rng.gen_range(1..=2)),
+            6 => Requirement::MissilesCapacity(rng.gen_range(5..=15)),
+            7 => Requirement::And(vec![
+                random_requirement(rng, depth - 1),
+                random_requirement(rng, depth - 1),
+            ]),
+            _ => Requirement::Or(vec![
+                random_requirement(rng, depth - 1),
+                random_requirement(rng, depth - 1),
+            ]),
+        }
+    }
+
+    fn make_link(from_vertex_id: VertexId, to_vertex_id: VertexId, req: Requirement) -> Link {
+        Link {
+            from_vertex_id,
+            to_vertex_id,
+            requirement: req,
+            start_with_shinecharge: false,
+            end_with_shinecharge: false,
+            strat_id: None,
+            strat_name: String::new(),
+            strat_notes: vec![],
+        }
+    }
+
+    // A random graph with a chain through all vertices plus extra links, using only requirements
+    // which don't need the game data.
+    fn make_fixture() -> Fixture {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../data/presets/full-settings/Default.json");
+        let settings_str = std::fs::read_to_string(path).unwrap();
+        let settings: RandomizerSettings = serde_json::from_str(&settings_str).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut links = vec![];
+        for v in 0..NUM_VERTICES - 1 {
+            links.push(make_link(v, v + 1, random_requirement(&mut rng, 1)));
+        }
+        for _ in 0..4 * NUM_VERTICES {
+            let from = rng.gen_range(0..NUM_VERTICES);
+            let to = rng.gen_range(0..NUM_VERTICES);
+            links.push(make_link(from, to, random_requirement(&mut rng, 2)));
+        }
+        let num_base_links = links.len();
+        Fixture {
+            settings,
+            game_data: GameData::default(),
+            base_links_data: LinksDataGroup::new(links, NUM_VERTICES, 0),
+            seed_links_data: LinksDataGroup::new(vec![], NUM_VERTICES, num_base_links),
+            door_map: HashMap::new(),
+            locked_door_data: LockedDoorData {
+                locked_doors: vec![],
+                locked_door_node_map: HashMap::new(),
+                locked_door_vertex_ids: vec![],
+            },
+        }
+    }
+
+    fn make_difficulty(settings: &RandomizerSettings, game_data: &GameData) -> DifficultyConfig {
+        let mut skill = settings.skill_assumption_settings.clone();
+        skill.tech_settings.clear();
+        skill.notable_settings.clear();
+        let mut difficulty = DifficultyConfig::new(&skill, game_data, &[], &[]);
+        difficulty.tech = vec![false; NUM_TECH];
+        difficulty
+    }
+
+    // The global states and difficulties of successive steps, with items and flags collected
+    // (which allows incremental traversals) as well as changed capacities and tech (which don't).
+    fn make_steps(fixture: &Fixture) -> Vec<(GlobalState, DifficultyConfig)> {
+        let mut global = GlobalState {
+            inventory: Inventory {
+                items: vec![false; Item::ProgressiveSuit as usize + 1],
+                max_energy: 99,
+                max_reserves: 0,
+                max_missiles: 0,
+                max_supers: 0,
+                max_power_bombs: 0,
+                collectible_missile_packs: 0,
+                collectible_super_packs: 0,
+                collectible_power_bomb_packs: 0,
+            },
+            flags: vec![false; NUM_FLAGS],
+            doors_unlocked: vec![],
+            weapon_mask: 0,
+        };
+        let mut difficulty = make_difficulty(&fixture.settings, &fixture.game_data);
+        let mut steps = vec![(global.clone(), difficulty.clone())];
+
+        global.inventory.items[1] = true;
+        global.inventory.max_missiles = 10;
+        steps.push((global.clone(), difficulty.clone()));
+        global.flags[0] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        global.inventory.items[3] = true;
+        global.inventory.items[4] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        difficulty.tech[1] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        global.flags[1] = true;
+        global.flags[2] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        global.inventory.max_supers = 5;
+        global.inventory.max_missiles = 20;
+        steps.push((global.clone(), difficulty.clone()));
+        global.inventory.items[0] = true;
+        global.inventory.items[2] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        global.inventory.items[5] = true;
+        global.flags[3] = true;
+        difficulty.tech[0] = true;
+        steps.push((global.clone(), difficulty.clone()));
+        steps
+    }
+
+    fn traverse(
+        traverser: &mut Traverser,
+        fixture: &Fixture,
+        global: &GlobalState,
+        difficulty: &DifficultyConfig,
+        step_num: usize,
+    ) {
+        traverser.traverse(
+            &fixture.base_links_data,
+            &fixture.seed_links_data,
+            global,
+            &fixture.settings,
+            difficulty,
+            &fixture.game_data,
+            &fixture.door_map,
+            &fixture.locked_door_data,
+            &[],
+            step_num,
+        );
+    }
+
+    fn assert_same_state(a: &Traverser, b: &Traverser) {
+        assert_eq!(a.cost, b.cost);
+        assert_eq!(a.local_states, b.local_states);
+        assert_eq!(a.start_trail_ids, b.start_trail_ids);
+        assert_eq!(a.step_trails.len(), b.step_trails.len());
+        for (x, y) in a.step_trails.iter().zip(&b.step_trails) {
+            assert_eq!(x.prev_trail_id, y.prev_trail_id);
+            assert_eq!(x.link_idx, y.link_idx);
+            assert_eq!(x.local_state, y.local_state);
+        }
+        let step_nums =
+            |t: &Traverser| -> Vec<usize> { t.past_steps.iter().map(|s| s.step_num).collect() };
+        assert_eq!(step_nums(a), step_nums(b));
+    }
+
+    // Traverse all the steps, starting from vertex 0.
+    fn make_traverser(fixture: &Fixture, steps: &[(GlobalState, DifficultyConfig)]) -> Traverser {
+        let mut traverser = Traverser::new(NUM_VERTICES, false, &steps[0].0);
+        traverser.add_origin(LocalState::full(), 0, &steps[0].0);
+        for (i, (global, difficulty)) in steps.iter().enumerate() {
+            traverse(&mut traverser, fixture, global, difficulty, i + 1);
+        }
+        traverser
+    }
+
+    #[test]
+    fn incremental_traversal_matches_full_traversal() {
+        let fixture = make_fixture();
+        let steps = make_steps(&fixture);
+        for reverse in [false, true] {
+            let mut incremental = Traverser::new(NUM_VERTICES, reverse, &steps[0].0);
+            let mut full = Traverser::new(NUM_VERTICES, reverse, &steps[0].0);
+            let (init_local, start_vertex_id) = if reverse {
+                (LocalState::empty(&steps[0].0), NUM_VERTICES - 1)
+            } else {
+                (LocalState::full(), 0)
+            };
+            incremental.add_origin(init_local, start_vertex_id, &steps[0].0);
+            full.add_origin(init_local, start_vertex_id, &steps[0].0);
+            let mut num_incremental = 0;
+            for (i, (global, difficulty)) in steps.iter().enumerate() {
+                if incremental
+                    .get_affected_links(
+                        &fixture.base_links_data,
+                        &fixture.seed_links_data,
+                        global,
+                        difficulty,
+                    )
+                    .is_some()
+                {
+                    num_incremental += 1;
+                }
+                // Without a completed traversal to compare against, all links get applied:
+                if let Some(step) = full.past_steps.last_mut() {
+                    step.traversed_difficulty = None;
+                }
+                traverse(&mut incremental, &fixture, global, difficulty, i + 1);
+                traverse(&mut full, &fixture, global, difficulty, i + 1);
+                assert_same_state(&incremental, &full);
+            }
+            assert!(num_incremental > 0);
+        }
+    }
+
+    #[test]
+    fn rewind_and_restore_match_pop_step() {
+        let fixture = make_fixture();
+        let steps = make_steps(&fixture);
+        let (last_global, last_difficulty) = steps.last().unwrap();
+        let mut traverser = make_traverser(&fixture, &steps);
+        for num_steps in 1..steps.len() {
+            let original = traverser.clone();
+            let mut popped = traverser.clone();
+            while popped.past_steps.len() > num_steps {
+                popped.pop_step();
+            }
+            let checkpoint = traverser.rewind(num_steps);
+            assert_same_state(&traverser, &popped);
+
+            // Traversing from the rewound state gives the same result as from the popped clone:
+            traverse(&mut traverser, &fixture, last_global, last_difficulty, 100);
+            traverse(&mut popped, &fixture, last_global, last_difficulty, 100);
+            assert_same_state(&traverser, &popped);
+            traverser.pop_step();
+
+            traverser.restore(checkpoint);
+            assert_same_state(&traverser, &original);
+        }
+    }
+}
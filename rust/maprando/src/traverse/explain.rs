@@ -0,0 +1,486 @@
+// Explanations for why a location is not reachable in logic: starting from the reachable vertices
+// nearest to the target (counting links, regardless of their requirements), report the links out
+// of them which could not be traversed, and the requirement leaves (tech, notables, items, flags,
+// locked doors, resource amounts) which blocked each link.
+
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+use maprando_game::{
+    BeamType, Capacity, DoorType, GameData, Link, LinksDataGroup, NodeId, NotableId, Requirement,
+    RoomId, TechId, VertexId,
+};
+use maprando_logic::{GlobalState, LocalState};
+use serde::{Deserialize, Serialize};
+
+use super::{LockedDoorData, Traverser, apply_requirement};
+use crate::{
+    randomize::DifficultyConfig,
+    settings::{Objective, RandomizerSettings},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BlockingLeaf {
+    Tech {
+        tech_id: TechId,
+        name: String,
+    },
+    Notable {
+        room_id: RoomId,
+        notable_id: NotableId,
+        name: String,
+    },
+    Item {
+        item: String,
+    },
+    Flag {
+        flag: String,
+    },
+    LockedDoor {
+        room_id: RoomId,
+        node_id: NodeId,
+        door_type: DoorType,
+    },
+    Resource {
+        resource: String,
+        amount: Capacity,
+    },
+    // Requirements which don't reduce to a single leaf, e.g. boss fights or heat frames.
+    Other {
+        requirement: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlockedLink {
+    pub room_id: RoomId,
+    pub from_node_id: NodeId,
+    pub to_node_id: NodeId,
+    pub strat_id: Option<usize>,
+    pub strat_name: String,
+    pub blockers: Vec<BlockingLeaf>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FrontierVertex {
+    pub vertex_id: VertexId,
+    pub room_id: RoomId,
+    pub room: String,
+    pub node_id: NodeId,
+    pub node: String,
+    pub distance: usize, // Number of links between this vertex and the target
+    pub blocked_links: Vec<BlockedLink>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UnreachableExplanation {
+    pub reachable: bool,
+    pub frontier: Vec<FrontierVertex>,
+}
+
+fn get_beam_item_name(beam: BeamType) -> &'static str {
+    match beam {
+        BeamType::Charge => "Charge",
+        BeamType::Ice => "Ice",
+        BeamType::Wave => "Wave",
+        BeamType::Spazer => "Spazer",
+        BeamType::Plasma => "Plasma",
+    }
+}
+
+fn get_resource_leaf(req: &Requirement) -> Option<BlockingLeaf> {
+    let (resource, amount) = match req {
+        Requirement::Missiles(n)
+        | Requirement::MissilesAvailable(n)
+        | Requirement::MissilesCapacity(n) => ("Missiles", *n),
+        Requirement::Supers(n)
+        | Requirement::SupersAvailable(n)
+        | Requirement::SupersCapacity(n) => ("Supers", *n),
+        Requirement::PowerBombs(n)
+        | Requirement::PowerBombsAvailable(n)
+        | Requirement::PowerBombsCapacity(n) => ("PowerBombs", *n),
+        Requirement::Energy(n) | Requirement::EnergyAvailable(n) | Requirement::Damage(n) => {
+            ("Energy", *n)
+        }
+        Requirement::RegularEnergy(n)
+        | Requirement::RegularEnergyAvailable(n)
+        | Requirement::RegularEnergyCapacity(n) => ("RegularEnergy", *n),
+        Requirement::ReserveEnergy(n)
+        | Requirement::ReserveEnergyAvailable(n)
+        | Requirement::ReserveEnergyCapacity(n) => ("ReserveEnergy", *n),
+        _ => return None,
+    };
+    Some(BlockingLeaf::Resource {
+        resource: resource.to_string(),
+        amount,
+    })
+}
+
+// Collect the leaves of a failing requirement which cause it to fail. For an `And`, only the first
+// failing term is explained (since the local state after it is undefined); for an `Or`, every
+// alternative is explained.
+pub fn explain_requirement(
+    req: &Requirement,
+    global: &GlobalState,
+    local: LocalState,
+    reverse: bool,
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    game_data: &GameData,
+    door_map: &HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+    locked_door_data: &LockedDoorData,
+    objectives: &[Objective],
+    out: &mut Vec<BlockingLeaf>,
+) {
+    let apply = |req: &Requirement, local: LocalState| {
+        apply_requirement(
+            req,
+            global,
+            local,
+            reverse,
+            settings,
+            difficulty,
+            game_data,
+            door_map,
+            locked_door_data,
+            objectives,
+        )
+    };
+    let explain = |req: &Requirement, local: LocalState, out: &mut Vec<BlockingLeaf>| {
+        explain_requirement(
+            req,
+            global,
+            local,
+            reverse,
+            settings,
+            difficulty,
+            game_data,
+            door_map,
+            locked_door_data,
+            objectives,
+            out,
+        )
+    };
+    if apply(req, local).is_some() {
+        return;
+    }
+
+    let leaf = match req {
+        Requirement::Tech(tech_idx) => {
+            let tech_id = game_data.tech_isv.keys[*tech_idx];
+            BlockingLeaf::Tech {
+                tech_id,
+                name: game_data
+                    .tech_names
+                    .get(&tech_id)
+                    .cloned()
+                    .unwrap_or_default(),
+            }
+        }
+        Requirement::Notable(notable_idx) => {
+            let info = &game_data.notable_info[*notable_idx];
+            BlockingLeaf::Notable {
+                room_id: info.room_id,
+                notable_id: info.notable_id,
+                name: info.name.clone(),
+            }
+        }
+        Requirement::Item(item_id) => BlockingLeaf::Item {
+            item: game_data.item_isv.keys[*item_id].clone(),
+        },
+        Requirement::Flag(flag_id) => BlockingLeaf::Flag {
+            flag: game_data.flag_isv.keys[*flag_id].clone(),
+        },
+        Requirement::DoorUnlocked { room_id, node_id } => {
+            let door_idx = locked_door_data.locked_door_node_map[&(*room_id, *node_id)];
+            BlockingLeaf::LockedDoor {
+                room_id: *room_id,
+                node_id: *node_id,
+                door_type: locked_door_data.locked_doors[door_idx].door_type,
+            }
+        }
+        Requirement::UnlockDoor {
+            room_id,
+            node_id,
+            requirement_red,
+            requirement_green,
+            requirement_yellow,
+            requirement_charge,
+        } => {
+            let door_idx = locked_door_data.locked_door_node_map[&(*room_id, *node_id)];
+            let door_type = locked_door_data.locked_doors[door_idx].door_type;
+            match door_type {
+                DoorType::Red => explain(requirement_red.as_ref(), local, out),
+                DoorType::Green => explain(requirement_green.as_ref(), local, out),
+                DoorType::Yellow => explain(requirement_yellow.as_ref(), local, out),
+                DoorType::Beam(beam) => {
+                    let item_name = get_beam_item_name(beam);
+                    let item_idx = game_data.item_isv.index_by_key[item_name];
+                    if global.inventory.items[item_idx] {
+                        explain(requirement_charge.as_ref(), local, out);
+                    } else {
+                        out.push(BlockingLeaf::Item {
+                            item: item_name.to_string(),
+                        });
+                    }
+                }
+                _ => out.push(BlockingLeaf::LockedDoor {
+                    room_id: *room_id,
+                    node_id: *node_id,
+                    door_type,
+                }),
+            }
+            return;
+        }
+        Requirement::And(reqs) => {
+            let mut new_local = local;
+            let ordered_reqs: Vec<&Requirement> = if reverse {
+                reqs.iter().rev().collect()
+            } else {
+                reqs.iter().collect()
+            };
+            for r in ordered_reqs {
+                match apply(r, new_local) {
+                    Some(l) => new_local = l,
+                    None => {
+                        explain(r, new_local, out);
+                        return;
+                    }
+                }
+            }
+            return;
+        }
+        Requirement::Or(reqs) => {
+            for r in reqs {
+                explain(r, local, out);
+            }
+            return;
+        }
+        Requirement::Farm { requirement, .. } if apply(requirement.as_ref(), local).is_none() => {
+            explain(requirement.as_ref(), local, out);
+            return;
+        }
+        _ => match get_resource_leaf(req) {
+            Some(leaf) => leaf,
+            None => BlockingLeaf::Other {
+                requirement: format!("{req:?}"),
+            },
+        },
+    };
+    out.push(leaf);
+}
+
+// Explain why a link can't be traversed from the given local state.
+pub fn explain_link(
+    link: &Link,
+    global: &GlobalState,
+    local: LocalState,
+    reverse: bool,
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    game_data: &GameData,
+    door_map: &HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+    locked_door_data: &LockedDoorData,
+    objectives: &[Objective],
+) -> Vec<BlockingLeaf> {
+    let mut out = vec![];
+    if reverse {
+        if !link.end_with_shinecharge && local.shinecharge_frames_remaining > 0 {
+            out.push(BlockingLeaf::Other {
+                requirement: "end without shinecharge".to_string(),
+            });
+        }
+    } else if link.start_with_shinecharge && local.shinecharge_frames_remaining == 0 {
+        out.push(BlockingLeaf::Other {
+            requirement: "start with shinecharge".to_string(),
+        });
+    }
+    explain_requirement(
+        &link.requirement,
+        global,
+        local,
+        reverse,
+        settings,
+        difficulty,
+        game_data,
+        door_map,
+        locked_door_data,
+        objectives,
+        &mut out,
+    );
+    let mut deduped: Vec<BlockingLeaf> = vec![];
+    for leaf in out {
+        if !deduped.contains(&leaf) {
+            deduped.push(leaf);
+        }
+    }
+    deduped
+}
+
+fn is_reached(traverser: &Traverser, v: VertexId) -> bool {
+    traverser.cost[v].iter().any(|&x| f32::is_finite(x))
+}
+
+// Explain why none of the target vertices were reached by the traverser. With a forward traverser
+// this explains why the target can't be reached from the start; with a reverse traverser, why it is
+// not possible to return from the target. Up to `max_frontier` of the nearest reachable vertices
+// are reported.
+pub fn explain_unreachable(
+    target_vertex_ids: &[VertexId],
+    traverser: &Traverser,
+    base_links_data: &LinksDataGroup,
+    seed_links_data: &LinksDataGroup,
+    global: &GlobalState,
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    game_data: &GameData,
+    door_map: &HashMap<(RoomId, NodeId), (RoomId, NodeId)>,
+    locked_door_data: &LockedDoorData,
+    objectives: &[Objective],
+    max_frontier: usize,
+) -> UnreachableExplanation {
+    if target_vertex_ids.iter().any(|&v| is_reached(traverser, v)) {
+        return UnreachableExplanation {
+            reachable: true,
+            frontier: vec![],
+        };
+    }
+
+    // Links in the direction of traversal, and in the opposite direction:
+    let (out_links, in_links) = if traverser.reverse {
+        (
+            [&base_links_data.links_by_dst, &seed_links_data.links_by_dst],
+            [&base_links_data.links_by_src, &seed_links_data.links_by_src],
+        )
+    } else {
+        (
+            [&base_links_data.links_by_src, &seed_links_data.links_by_src],
+            [&base_links_data.links_by_dst, &seed_links_data.links_by_dst],
+        )
+    };
+
+    // Distance (in links) from each vertex to the nearest target, ignoring requirements:
+    let num_vertices = traverser.cost.len();
+    let mut dist: Vec<Option<usize>> = vec![None; num_vertices];
+    let mut queue: VecDeque<VertexId> = VecDeque::new();
+    for &v in target_vertex_ids {
+        dist[v] = Some(0);
+        queue.push_back(v);
+    }
+    let mut frontier_ids: Vec<VertexId> = vec![];
+    while let Some(v) = queue.pop_front() {
+        if is_reached(traverser, v) {
+            // Don't search past reachable vertices: anything behind them is further away.
+            frontier_ids.push(v);
+            if frontier_ids.len() >= max_frontier {
+                break;
+            }
+            continue;
+        }
+        let d = dist[v].unwrap();
+        for links in in_links {
+            for (_, link) in &links[v] {
+                let u = link.to_vertex_id;
+                if dist[u].is_none() {
+                    dist[u] = Some(d + 1);
+                    queue.push_back(u);
+                }
+            }
+        }
+    }
+
+    let mut frontier = vec![];
+    for v in frontier_ids {
+        let local = traverser.local_states[v]
+            .iter()
+            .zip(traverser.cost[v].iter())
+            .find(|(_, c)| f32::is_finite(**c))
+            .map(|(&l, _)| l)
+            .unwrap();
+        let mut blocked_links = vec![];
+        for links in out_links {
+            for (_, link) in &links[v] {
+                let dst = link.to_vertex_id;
+                if is_reached(traverser, dst) || dist[dst].is_none_or(|d| d >= dist[v].unwrap()) {
+                    continue;
+                }
+                let blockers = explain_link(
+                    link,
+                    global,
+                    local,
+                    traverser.reverse,
+                    settings,
+                    difficulty,
+                    game_data,
+                    door_map,
+                    locked_door_data,
+                    objectives,
+                );
+                // Report links in their original direction:
+                let (from_id, to_id) = if traverser.reverse {
+                    (link.to_vertex_id, link.from_vertex_id)
+                } else {
+                    (link.from_vertex_id, link.to_vertex_id)
+                };
+                let from_key = &game_data.vertex_isv.keys[from_id];
+                let to_key = &game_data.vertex_isv.keys[to_id];
+                blocked_links.push(BlockedLink {
+                    room_id: from_key.room_id,
+                    from_node_id: from_key.node_id,
+                    to_node_id: to_key.node_id,
+                    strat_id: link.strat_id,
+                    strat_name: link.strat_name.clone(),
+                    blockers,
+                });
+            }
+        }
+        let key = &game_data.vertex_isv.keys[v];
+        frontier.push(FrontierVertex {
+            vertex_id: v,
+            room_id: key.room_id,
+            room: game_data.room_json_map[&key.room_id]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            node_id: key.node_id,
+            node: game_data.node_json_map[&(key.room_id, key.node_id)]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            distance: dist[v].unwrap(),
+            blocked_links,
+        });
+    }
+
+    UnreachableExplanation {
+        reachable: false,
+        frontier,
+    }
+}
+
+// Plain-text rendering of an explanation, for the debug tool and logic development.
+pub fn format_explanation(explanation: &UnreachableExplanation) -> String {
+    if explanation.reachable {
+        return "reachable\n".to_string();
+    }
+    let mut out = String::new();
+    if explanation.frontier.is_empty() {
+        out.push_str("no reachable vertex leads to the target\n");
+    }
+    for f in &explanation.frontier {
+        out.push_str(&format!(
+            "{}: {} (vertex {}, {} links from target)\n",
+            f.room, f.node, f.vertex_id, f.distance
+        ));
+        for link in &f.blocked_links {
+            out.push_str(&format!(
+                "  {} -> {}: {} (strat {:?})\n",
+                link.from_node_id, link.to_node_id, link.strat_name, link.strat_id
+            ));
+            for leaf in &link.blockers {
+                out.push_str(&format!("    {leaf:?}\n"));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,704 @@
+// Minimal item sets: for a given difficulty, reduce requirements to a disjunction of item/flag
+// combinations (ignoring resource amounts), and propagate these through the link graph to find,
+// for each vertex, the minimal combinations of items and flags with which it can be reached.
+//
+// This is an over-approximation of the logic: energy, ammo amounts, and boss fights are treated as
+// always satisfiable, beyond needing at least one pack of the relevant ammo type. It is intended
+// for auditing the logic and for showing what is needed for a location, not for generation.
+
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+use maprando_game::{
+    BeamType, Capacity, DoorType, FlagId, GameData, Item, ItemId, LinkIdx, LinksDataGroup, NodeId,
+    Requirement, RoomId, VertexId,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{LockedDoorData, get_heated_speedball_tiles};
+use crate::{
+    randomize::{DifficultyConfig, get_initial_flag_vec},
+    settings::{Objective, RandomizerSettings, WallJump},
+};
+
+// A combination of items and flags, each kept sorted.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemSet {
+    pub items: Vec<ItemId>,
+    pub flags: Vec<FlagId>,
+}
+
+fn is_sorted_subset(a: &[usize], b: &[usize]) -> bool {
+    let mut j = 0;
+    for &x in a {
+        while j < b.len() && b[j] < x {
+            j += 1;
+        }
+        if j == b.len() || b[j] != x {
+            return false;
+        }
+        j += 1;
+    }
+    true
+}
+
+fn sorted_union(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            out.push(a[i]);
+            i += 1;
+        } else if b[j] < a[i] {
+            out.push(b[j]);
+            j += 1;
+        } else {
+            out.push(a[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
+impl ItemSet {
+    pub fn is_subset(&self, other: &ItemSet) -> bool {
+        is_sorted_subset(&self.items, &other.items) && is_sorted_subset(&self.flags, &other.flags)
+    }
+
+    pub fn union(&self, other: &ItemSet) -> ItemSet {
+        ItemSet {
+            items: sorted_union(&self.items, &other.items),
+            flags: sorted_union(&self.flags, &other.flags),
+        }
+    }
+}
+
+// A requirement in disjunctive normal form: it is satisfied by having all of the items and flags
+// in any one of the sets. Only minimal sets are kept. If more than the allowed number of sets
+// would be needed, the excess is dropped and `truncated` is set, in which case the result may be
+// missing some ways of satisfying the requirement.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemSets {
+    pub sets: Vec<ItemSet>,
+    pub truncated: bool,
+}
+
+impl ItemSets {
+    pub fn free() -> Self {
+        ItemSets {
+            sets: vec![ItemSet::default()],
+            truncated: false,
+        }
+    }
+
+    pub fn never() -> Self {
+        ItemSets::default()
+    }
+
+    fn from_bool(b: bool) -> Self {
+        if b { Self::free() } else { Self::never() }
+    }
+
+    fn item(item: Item) -> Self {
+        ItemSets {
+            sets: vec![ItemSet {
+                items: vec![item as ItemId],
+                flags: vec![],
+            }],
+            truncated: false,
+        }
+    }
+
+    // Add a set, unless it is implied by an existing one. Returns true if the sets changed.
+    pub fn insert(&mut self, set: ItemSet, max_sets: usize) -> bool {
+        if self.sets.iter().any(|s| s.is_subset(&set)) {
+            return false;
+        }
+        self.sets.retain(|s| !set.is_subset(s));
+        if self.sets.len() >= max_sets {
+            let changed = !self.truncated;
+            self.truncated = true;
+            return changed;
+        }
+        self.sets.push(set);
+        true
+    }
+
+    pub fn and(&self, other: &ItemSets, max_sets: usize) -> ItemSets {
+        let mut out = ItemSets {
+            sets: vec![],
+            truncated: self.truncated || other.truncated,
+        };
+        for a in &self.sets {
+            for b in &other.sets {
+                out.insert(a.union(b), max_sets);
+            }
+        }
+        out
+    }
+
+    pub fn or(&self, other: &ItemSets, max_sets: usize) -> ItemSets {
+        let mut out = self.clone();
+        out.truncated |= other.truncated;
+        for b in &other.sets {
+            out.insert(b.clone(), max_sets);
+        }
+        out
+    }
+}
+
+struct Context<'a> {
+    settings: &'a RandomizerSettings,
+    difficulty: &'a DifficultyConfig,
+    game_data: &'a GameData,
+    locked_door_data: &'a LockedDoorData,
+    objectives: &'a [Objective],
+    weapon_items: Vec<Option<Vec<ItemId>>>,
+    initial_flags: Vec<bool>,
+    starting_items: Vec<bool>,
+    max_sets: usize,
+}
+
+impl<'a> Context<'a> {
+    fn new(
+        settings: &'a RandomizerSettings,
+        difficulty: &'a DifficultyConfig,
+        game_data: &'a GameData,
+        locked_door_data: &'a LockedDoorData,
+        objectives: &'a [Objective],
+        max_sets: usize,
+    ) -> Self {
+        let mut starting_items = vec![false; game_data.item_isv.keys.len()];
+        for x in &settings.item_progression_settings.starting_items {
+            if x.count > 0 {
+                starting_items[x.item as usize] = true;
+            }
+        }
+        Context {
+            settings,
+            difficulty,
+            game_data,
+            locked_door_data,
+            objectives,
+            weapon_items: game_data.get_weapon_item_requirements(&difficulty.tech),
+            initial_flags: get_initial_flag_vec(settings, game_data),
+            starting_items,
+            max_sets,
+        }
+    }
+
+    fn items(&self, items: &[ItemId]) -> ItemSets {
+        let mut set = ItemSet::default();
+        for &item_id in items {
+            if !self.starting_items[item_id] {
+                set.items.push(item_id);
+            }
+        }
+        set.items.sort();
+        set.items.dedup();
+        ItemSets {
+            sets: vec![set],
+            truncated: false,
+        }
+    }
+
+    fn item(&self, item: Item) -> ItemSets {
+        if self.starting_items[item as usize] {
+            ItemSets::free()
+        } else {
+            ItemSets::item(item)
+        }
+    }
+
+    fn flag(&self, flag_id: FlagId) -> ItemSets {
+        if self.initial_flags[flag_id] {
+            return ItemSets::free();
+        }
+        ItemSets {
+            sets: vec![ItemSet {
+                items: vec![],
+                flags: vec![flag_id],
+            }],
+            truncated: false,
+        }
+    }
+
+    fn and(&self, a: &ItemSets, b: &ItemSets) -> ItemSets {
+        a.and(b, self.max_sets)
+    }
+
+    fn or(&self, a: &ItemSets, b: &ItemSets) -> ItemSets {
+        a.or(b, self.max_sets)
+    }
+
+    fn tech(&self, tech_idx: usize) -> ItemSets {
+        ItemSets::from_bool(self.difficulty.tech[tech_idx])
+    }
+
+    fn ammo(&self, item: Item, count: Capacity) -> ItemSets {
+        if count > 0 {
+            self.item(item)
+        } else {
+            ItemSets::free()
+        }
+    }
+
+    fn heat(&self) -> ItemSets {
+        self.or(
+            &self.item(Item::Varia),
+            &self.tech(self.game_data.heat_run_tech_idx),
+        )
+    }
+
+    // Requirement for a runway of `used_tiles` to be long enough, possibly in a heated room.
+    fn runway(&self, used_tiles: f32, heated: bool, tiles: f32, heated_tiles: f32) -> ItemSets {
+        if !heated || used_tiles >= heated_tiles {
+            ItemSets::from_bool(used_tiles >= tiles)
+        } else if used_tiles >= tiles {
+            self.item(Item::Varia)
+        } else {
+            ItemSets::never()
+        }
+    }
+
+    fn mother_brain_barrier(&self, obj_id: usize) -> ItemSets {
+        let get_flag =
+            |obj: &Objective| self.flag(self.game_data.flag_isv.index_by_key[obj.get_flag_name()]);
+        if self.objectives.len() > 4 {
+            let mut out = ItemSets::free();
+            for obj in self.objectives {
+                out = self.and(&out, &get_flag(obj));
+            }
+            out
+        } else if let Some(obj) = self.objectives.get(obj_id) {
+            get_flag(obj)
+        } else {
+            ItemSets::free()
+        }
+    }
+
+    fn requirement(&self, req: &Requirement) -> ItemSets {
+        let qol = &self.settings.quality_of_life_settings;
+        let difficulty = self.difficulty;
+        match req {
+            Requirement::Free => ItemSets::free(),
+            Requirement::Never => ItemSets::never(),
+            Requirement::Tech(tech_idx) => self.tech(*tech_idx),
+            Requirement::Notable(notable_idx) => {
+                ItemSets::from_bool(difficulty.notables[*notable_idx])
+            }
+            Requirement::Item(item_id) => self.items(&[*item_id]),
+            Requirement::Flag(flag_id) => self.flag(*flag_id),
+            Requirement::MotherBrainBarrierClear(obj_id) => self.mother_brain_barrier(*obj_id),
+            Requirement::DisableableETank => ItemSets::from_bool(qol.disableable_etanks),
+            Requirement::ClimbWithoutLava => ItemSets::from_bool(qol.remove_climb_lava),
+            Requirement::SupersDoubleDamageMotherBrain => ItemSets::from_bool(qol.supers_double),
+            Requirement::ShinesparksCostEnergy => {
+                ItemSets::from_bool(!self.settings.other_settings.energy_free_shinesparks)
+            }
+            Requirement::AmmoStationRefillAll => {
+                ItemSets::from_bool(!self.settings.other_settings.ultra_low_qol)
+            }
+            Requirement::EscapeMorphLocation => {
+                ItemSets::from_bool(self.settings.map_layout == "Vanilla")
+            }
+            Requirement::Walljump => match self.settings.other_settings.wall_jump {
+                WallJump::Vanilla => self.tech(self.game_data.wall_jump_tech_idx),
+                WallJump::Collectible => self.and(
+                    &self.tech(self.game_data.wall_jump_tech_idx),
+                    &self.item(Item::WallJump),
+                ),
+            },
+            Requirement::ShineCharge { used_tiles, heated }
+            | Requirement::GetBlueSpeed { used_tiles, heated } => self.and(
+                &self.item(Item::SpeedBooster),
+                &self.runway(
+                    used_tiles.get(),
+                    *heated,
+                    difficulty.shine_charge_tiles,
+                    difficulty.heated_shine_charge_tiles,
+                ),
+            ),
+            Requirement::SpeedBall { used_tiles, heated } => {
+                let mut out = self.tech(self.game_data.speed_ball_tech_idx);
+                out = self.and(
+                    &out,
+                    &self.items(&[Item::Morph as ItemId, Item::SpeedBooster as ItemId]),
+                );
+                self.and(
+                    &out,
+                    &self.runway(
+                        used_tiles.get(),
+                        *heated,
+                        difficulty.speed_ball_tiles,
+                        get_heated_speedball_tiles(difficulty),
+                    ),
+                )
+            }
+            Requirement::Shinespark {
+                shinespark_tech_idx,
+                ..
+            } => self.tech(*shinespark_tech_idx),
+            Requirement::HeatFrames(_)
+            | Requirement::SimpleHeatFrames(_)
+            | Requirement::HeatFramesWithEnergyDrops(..)
+            | Requirement::MainHallElevatorFrames
+            | Requirement::LowerNorfairElevatorDownFrames
+            | Requirement::LowerNorfairElevatorUpFrames => self.heat(),
+            Requirement::Missiles(n)
+            | Requirement::MissilesAvailable(n)
+            | Requirement::MissilesCapacity(n) => self.ammo(Item::Missile, *n),
+            Requirement::Supers(n)
+            | Requirement::SupersAvailable(n)
+            | Requirement::SupersCapacity(n) => self.ammo(Item::Super, *n),
+            Requirement::PowerBombs(n)
+            | Requirement::PowerBombsAvailable(n)
+            | Requirement::PowerBombsCapacity(n) => self.ammo(Item::PowerBomb, *n),
+            Requirement::ReserveEnergy(n)
+            | Requirement::ReserveEnergyAvailable(n)
+            | Requirement::ReserveEnergyCapacity(n) => self.ammo(Item::ReserveTank, *n),
+            Requirement::ReserveTrigger {
+                min_reserve_energy, ..
+            } => self.ammo(Item::ReserveTank, *min_reserve_energy),
+            Requirement::EnemyKill { vul, .. } => {
+                let mut out = ItemSets::never();
+                for (i, weapon_items) in self.weapon_items.iter().enumerate() {
+                    if vul.non_ammo_vulnerabilities & (1 << i) == 0 {
+                        continue;
+                    }
+                    if let Some(items) = weapon_items {
+                        out = self.or(&out, &self.items(items));
+                    }
+                }
+                if vul.missile_damage > 0 {
+                    out = self.or(&out, &self.item(Item::Missile));
+                }
+                if vul.super_damage > 0 {
+                    out = self.or(&out, &self.item(Item::Super));
+                }
+                if vul.power_bomb_damage > 0 {
+                    out = self.or(
+                        &out,
+                        &self.items(&[Item::Morph as ItemId, Item::PowerBomb as ItemId]),
+                    );
+                }
+                out
+            }
+            Requirement::DoorType {
+                room_id,
+                node_id,
+                door_type,
+            } => {
+                let actual_door_type = match self
+                    .locked_door_data
+                    .locked_door_node_map
+                    .get(&(*room_id, *node_id))
+                {
+                    Some(&idx) => self.locked_door_data.locked_doors[idx].door_type,
+                    None => DoorType::Blue,
+                };
+                ItemSets::from_bool(*door_type == actual_door_type)
+            }
+            Requirement::UnlockDoor {
+                room_id,
+                node_id,
+                requirement_red,
+                requirement_green,
+                requirement_yellow,
+                requirement_charge,
+            } => {
+                let Some(&idx) = self
+                    .locked_door_data
+                    .locked_door_node_map
+                    .get(&(*room_id, *node_id))
+                else {
+                    return ItemSets::free();
+                };
+                match self.locked_door_data.locked_doors[idx].door_type {
+                    DoorType::Blue => ItemSets::free(),
+                    DoorType::Red => self.requirement(requirement_red),
+                    DoorType::Green => self.requirement(requirement_green),
+                    DoorType::Yellow => self.requirement(requirement_yellow),
+                    DoorType::Beam(BeamType::Charge) => self.and(
+                        &self.item(Item::Charge),
+                        &self.requirement(requirement_charge),
+                    ),
+                    DoorType::Beam(BeamType::Ice) => self.item(Item::Ice),
+                    DoorType::Beam(BeamType::Wave) => self.item(Item::Wave),
+                    DoorType::Beam(BeamType::Spazer) => self.item(Item::Spazer),
+                    DoorType::Beam(BeamType::Plasma) => self.item(Item::Plasma),
+                    DoorType::Gray | DoorType::Wall => ItemSets::never(),
+                }
+            }
+            Requirement::Farm { requirement, .. } => self.requirement(requirement),
+            Requirement::And(reqs) => {
+                let mut out = ItemSets::free();
+                for r in reqs {
+                    out = self.and(&out, &self.requirement(r));
+                    if out.sets.is_empty() {
+                        break;
+                    }
+                }
+                out
+            }
+            Requirement::Or(reqs) => {
+                let mut out = ItemSets::never();
+                for r in reqs {
+                    out = self.or(&out, &self.requirement(r));
+                }
+                out
+            }
+            // Everything else depends only on resource amounts (energy, ammo, frames), on boss
+            // fight proficiency, or on the state of doors and rooms during traversal, and is
+            // treated as satisfiable.
+            _ => ItemSets::free(),
+        }
+    }
+}
+
+// Reduce a requirement to the minimal item/flag combinations satisfying it, keeping at most
+// `max_sets` combinations.
+pub fn get_requirement_item_sets(
+    req: &Requirement,
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    game_data: &GameData,
+    locked_door_data: &LockedDoorData,
+    objectives: &[Objective],
+    max_sets: usize,
+) -> ItemSets {
+    let ctx = Context::new(
+        settings,
+        difficulty,
+        game_data,
+        locked_door_data,
+        objectives,
+        max_sets,
+    );
+    ctx.requirement(req)
+}
+
+// For each vertex, the minimal item/flag combinations with which it can be reached from the start
+// vertex, keeping at most `max_sets` combinations per vertex.
+pub fn get_vertex_item_sets(
+    base_links_data: &LinksDataGroup,
+    seed_links_data: &LinksDataGroup,
+    start_vertex_id: VertexId,
+    start_requirement: &Requirement,
+    settings: &RandomizerSettings,
+    difficulty: &DifficultyConfig,
+    game_data: &GameData,
+    locked_door_data: &LockedDoorData,
+    objectives: &[Objective],
+    max_sets: usize,
+) -> Vec<ItemSets> {
+    let ctx = Context::new(
+        settings,
+        difficulty,
+        game_data,
+        locked_door_data,
+        objectives,
+        max_sets,
+    );
+    let num_vertices = base_links_data.links_by_src.len();
+    let mut vertex_sets: Vec<ItemSets> = vec![ItemSets::never(); num_vertices];
+    let mut link_sets: HashMap<LinkIdx, ItemSets> = HashMap::new();
+    let mut in_queue = vec![false; num_vertices];
+    let mut queue: VecDeque<VertexId> = VecDeque::new();
+
+    vertex_sets[start_vertex_id] = ctx.requirement(start_requirement);
+    in_queue[start_vertex_id] = true;
+    queue.push_back(start_vertex_id);
+
+    // Propagate to a fixed point. Sets at a vertex are only ever replaced by smaller ones, so this
+    // terminates.
+    while let Some(src_id) = queue.pop_front() {
+        in_queue[src_id] = false;
+        for links_by_src in [&base_links_data.links_by_src, &seed_links_data.links_by_src] {
+            for (link_idx, link) in &links_by_src[src_id] {
+                let req_sets = link_sets
+                    .entry(*link_idx)
+                    .or_insert_with(|| ctx.requirement(&link.requirement));
+                if req_sets.sets.is_empty() {
+                    continue;
+                }
+                let new_sets = vertex_sets[src_id].and(req_sets, max_sets);
+                let dst_sets = &mut vertex_sets[link.to_vertex_id];
+                let mut changed = false;
+                if new_sets.truncated && !dst_sets.truncated {
+                    dst_sets.truncated = true;
+                    changed = true;
+                }
+                for set in new_sets.sets {
+                    changed |= dst_sets.insert(set, max_sets);
+                }
+                if changed && !in_queue[link.to_vertex_id] {
+                    in_queue[link.to_vertex_id] = true;
+                    queue.push_back(link.to_vertex_id);
+                }
+            }
+        }
+    }
+    vertex_sets
+}
+
+// Combine the item sets of several vertices (e.g. those of an item location), any of which
+// may be reached.
+pub fn combine_item_sets(
+    vertex_sets: &[ItemSets],
+    vertex_ids: &[VertexId],
+    max_sets: usize,
+) -> ItemSets {
+    let mut out = ItemSets::never();
+    for &v in vertex_ids {
+        out = out.or(&vertex_sets[v], max_sets);
+    }
+    out.sets.sort_by_key(|s| {
+        (
+            s.items.len() + s.flags.len(),
+            s.items.clone(),
+            s.flags.clone(),
+        )
+    });
+    out
+}
+
+// An item set with items and flags given by name, for reporting.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamedItemSet {
+    pub items: Vec<String>,
+    pub flags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocationItemSets {
+    pub room_id: RoomId,
+    pub node_id: NodeId,
+    pub room: String,
+    pub node: String,
+    pub item_sets: Vec<NamedItemSet>,
+    pub truncated: bool,
+}
+
+// Item sets for each item location in rooms included in `room_mask`.
+pub fn get_location_item_sets(
+    vertex_sets: &[ItemSets],
+    room_mask: &[bool],
+    game_data: &GameData,
+    max_sets: usize,
+) -> Vec<LocationItemSets> {
+    let mut out = vec![];
+    for (i, &(room_id, node_id)) in game_data.item_locations.iter().enumerate() {
+        if !room_mask[game_data.room_idx_by_id[&room_id]] {
+            continue;
+        }
+        let sets = combine_item_sets(vertex_sets, &game_data.item_vertex_ids[i], max_sets);
+        let item_sets = sets
+            .sets
+            .iter()
+            .map(|s| NamedItemSet {
+                items: s
+                    .items
+                    .iter()
+                    .map(|&x| game_data.item_isv.keys[x].clone())
+                    .collect(),
+                flags: s
+                    .flags
+                    .iter()
+                    .map(|&x| game_data.flag_isv.keys[x].clone())
+                    .collect(),
+            })
+            .collect();
+        out.push(LocationItemSets {
+            room_id,
+            node_id,
+            room: game_data.room_json_map[&room_id]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            node: game_data.node_json_map[&(room_id, node_id)]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            item_sets,
+            truncated: sets.truncated,
+        });
+    }
+    out
+}
+
+// Plain-text rendering of location item sets, one line per combination.
+pub fn format_location_item_sets(locations: &[LocationItemSets]) -> String {
+    let mut out = String::new();
+    for loc in locations {
+        out.push_str(&format!(
+            "{}: {} ({}, {}){}\n",
+            loc.room,
+            loc.node,
+            loc.room_id,
+            loc.node_id,
+            if loc.truncated { " [truncated]" } else { "" }
+        ));
+        if loc.item_sets.is_empty() {
+            out.push_str("  unreachable\n");
+        }
+        for set in &loc.item_sets {
+            let names: Vec<&str> = set
+                .items
+                .iter()
+                .chain(set.flags.iter())
+                .map(|x| x.as_str())
+                .collect();
+            if names.is_empty() {
+                out.push_str("  (nothing)\n");
+            } else {
+                out.push_str(&format!("  {}\n", names.join(" + ")));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(items: &[ItemId], flags: &[FlagId]) -> ItemSet {
+        ItemSet {
+            items: items.to_vec(),
+            flags: flags.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_item_sets_minimal() {
+        let a = ItemSets {
+            sets: vec![set(&[1], &[]), set(&[2, 3], &[])],
+            truncated: false,
+        };
+        let b = ItemSets {
+            sets: vec![set(&[1, 2], &[]), set(&[], &[5])],
+            truncated: false,
+        };
+
+        let or = a.or(&b, 10);
+        assert_eq!(
+            or.sets,
+            vec![set(&[1], &[]), set(&[2, 3], &[]), set(&[], &[5])]
+        );
+
+        let and = a.and(&b, 10);
+        assert_eq!(
+            and.sets,
+            vec![set(&[1, 2], &[]), set(&[1], &[5]), set(&[2, 3], &[5])]
+        );
+        assert!(!and.truncated);
+
+        let and = a.and(&b, 2);
+        assert_eq!(and.sets.len(), 2);
+        assert!(and.truncated);
+    }
+}
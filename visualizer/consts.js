@@ -37,6 +37,9 @@ let item_plm = {
 	"WallJump": 21,
 	"None": 22,
 	"Hidden": 23,
+	// Progressive items are shown using the icon of the first item in their sequence:
+	"ProgressiveBeam": 5,
+	"ProgressiveSuit": 12,
 };
 let itemtypes = {
 	"majors": ["Varia",
@@ -55,7 +58,9 @@ let itemtypes = {
 			"Ice",
 			"Wave",
 			"Spazer",
-			"Plasma"],
+			"Plasma",
+			"ProgressiveBeam",
+			"ProgressiveSuit"],
 	"health": ["ETank", "ReserveTank"],
 	"ammo": ["Super","PowerBomb"],
 	"missiles": ["Missile"]
@@ -83,6 +88,8 @@ let item_rank = {
 	"Super": 20,
 	"PowerBomb": 21,
 	"Missile": 22,
+	"ProgressiveSuit": 2,
+	"ProgressiveBeam": 17,
 };
 
 // Keyed by room ID:
@@ -47,6 +47,14 @@ function migrateValue(name, value) {
             "Yes": "true",
             "No": "false"
         },
+        "progressive_beams": {
+            "Yes": "true",
+            "No": "false"
+        },
+        "progressive_suits": {
+            "Yes": "true",
+            "No": "false"
+        },
         "race_mode": {
             "Yes": "true",
             "No": "false"
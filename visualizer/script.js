@@ -810,9 +810,43 @@ fetch(`../spoiler.json`).then(c => c.json()).then(c => {
 				
 			item_info.appendChild(createHtmlElement(`<div class="category">RETURN ROUTE</div>`));
 			routeData(item_info, j.return_route);
+		} else {
+			unreachableData(item_info, loc);
 		}
 		si.appendChild(item_info);
 	}
+	function unreachableData(p, loc) {
+		let u = (c.unreachable_items || []).find(x => x.location.room_id == loc.room_id && x.location.node_id == loc.node_id);
+		if (u === undefined) {
+			return;
+		}
+		let header = u.reachable ? "WHY NO RETURN" : "WHY UNREACHABLE";
+		p.appendChild(createHtmlElement(`<div class="category">${header}</div>`));
+		if (u.explanation.frontier.length == 0) {
+			p.appendChild(createDiv(`No reachable location leads here.`));
+		}
+		for (let f of u.explanation.frontier) {
+			let out = `<b>${f.room}: ${f.node}</b><br>`;
+			for (let l of f.blocked_links) {
+				let strat_url = `/logic/room/${l.room_id}/${l.from_node_id}/${l.to_node_id}/${l.strat_id}`;
+				let blockers = l.blockers.map(leafText).join(", ");
+				out += `<small><a href="${strat_url}">${l.strat_name}</a>: ${blockers}</small><br>`;
+			}
+			p.appendChild(createDiv(out));
+		}
+	}
+	function leafText(leaf) {
+		let [kind, v] = Object.entries(leaf)[0];
+		switch (kind) {
+			case "Tech": return `tech ${v.name}`;
+			case "Notable": return `notable ${v.name}`;
+			case "Item": return v.item;
+			case "Flag": return v.flag;
+			case "LockedDoor": return `locked door (${JSON.stringify(v.door_type)})`;
+			case "Resource": return `${v.amount} ${v.resource}`;
+			default: return v.requirement;
+		}
+	}
 	function consumableData(k, ss=null) {
 		let remstr = ss == null ? "still needed" : "remaining";
 		let out = "";
@@ -926,6 +960,18 @@ fetch(`../spoiler.json`).then(c => c.json()).then(c => {
 				flagContainer.appendChild(document.createElement("br"));
 				p.appendChild(flagContainer);
 			}
+			if (k.requirement) {
+				let reqDetails = document.createElement("details");
+				let reqSummary = document.createElement("summary");
+				reqSummary.innerText = "Requirements";
+				reqDetails.appendChild(reqSummary);
+				let reqPre = document.createElement("pre");
+				reqPre.innerText = k.requirement;
+				reqDetails.appendChild(reqPre);
+				let reqContainer = document.createElement("small");
+				reqContainer.appendChild(reqDetails);
+				p.appendChild(reqContainer);
+			}
 		}
 	}
 	function flagIcons(p, flags, j=null) {
@@ -935,7 +981,7 @@ fetch(`../spoiler.json`).then(c => c.json()).then(c => {
 			if (x.flag != null)
 				f = x.flag;
 
-			if (f == "f_TourianOpen" || f == "f_MotherBrainGlassBroken" || f == "f_AllItemsSpawn" || f == "f_AcidChozoWithoutSpaceJump" || f.includes("f_KilledZebetites"))
+			if (f == "f_TourianOpen" || f == "f_MotherBrainGlassBroken" || f == "f_AllItemsSpawn" || f == "f_AcidChozoWithoutSpaceJump" || f.includes("f_KilledZebetites") || f.includes("MapStation"))
 				continue;
 
 			let e = document.createElement("img");